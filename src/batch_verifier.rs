@@ -0,0 +1,138 @@
+use std::{borrow::Cow, marker::PhantomData};
+
+use jsonwebtoken::{
+    jwk::{Jwk, JwkSet},
+    Algorithm, DecodingKey,
+};
+
+use crate::{
+    encoded_token::{KeyFamily, VerificationKey},
+    token::Token,
+    DecodeOptions, EncodedToken, EncodedTokenError,
+};
+
+#[derive(Clone)]
+struct PreparedKey {
+    kid: Option<String>,
+    algorithm: Option<Algorithm>,
+    family: KeyFamily,
+    key: DecodingKey,
+}
+
+impl VerificationKey for PreparedKey {
+    fn kid(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+
+    fn algorithm(&self) -> Option<Algorithm> {
+        self.algorithm
+    }
+
+    fn family(&self) -> KeyFamily {
+        self.family
+    }
+
+    fn decoding_key(&self) -> Result<Cow<'_, DecodingKey>, EncodedTokenError> {
+        Ok(Cow::Borrowed(&self.key))
+    }
+}
+
+fn prepare(jwk: &Jwk) -> Option<PreparedKey> {
+    let key = DecodingKey::from_jwk(jwk)
+        .map_err(|e| log::warn!("Skipping unusable JWK: {}", e))
+        .ok()?;
+    Some(PreparedKey {
+        kid: jwk.common.key_id.clone(),
+        algorithm: jwk.common.algorithm,
+        family: KeyFamily::of_jwk(jwk),
+        key,
+    })
+}
+
+// Verifies many tokens against one JWK set, building each DecodingKey once
+// instead of per token. Keys that cannot be used for verification are skipped,
+// so tokens naming them fail with NoJWKError.
+pub struct BatchVerifier<Extension> {
+    keys: Vec<PreparedKey>,
+    options: DecodeOptions,
+    phantom: PhantomData<Extension>,
+}
+
+impl<Extension> BatchVerifier<Extension>
+where
+    for<'a> Extension: serde::Deserialize<'a>,
+{
+    pub fn new(jwk_set: &JwkSet) -> Self {
+        Self {
+            keys: jwk_set.keys.iter().filter_map(prepare).collect(),
+            options: DecodeOptions::default(),
+            phantom: Default::default(),
+        }
+    }
+
+    pub fn decode_options(mut self, value: DecodeOptions) -> Self {
+        self.options = value;
+        self
+    }
+
+    pub fn verify(
+        &self,
+        token: &EncodedToken<Extension>,
+    ) -> Result<Token<Extension>, EncodedTokenError> {
+        token.clone().decode_with_keys(&self.keys, &self.options)
+    }
+
+    pub fn verify_many(
+        &self,
+        tokens: &[EncodedToken<Extension>],
+    ) -> Vec<Result<Token<Extension>, EncodedTokenError>> {
+        tokens.iter().map(|token| self.verify(token)).collect()
+    }
+
+    #[cfg(feature = "rayon")]
+    pub fn par_verify_many(
+        &self,
+        tokens: &[EncodedToken<Extension>],
+    ) -> Vec<Result<Token<Extension>, EncodedTokenError>>
+    where
+        Extension: Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        tokens.par_iter().map(|token| self.verify(token)).collect()
+    }
+}
+
+impl<Extension> Token<Extension>
+where
+    for<'a> Extension: serde::Deserialize<'a>,
+{
+    pub fn decode_many(
+        tokens: &[EncodedToken<Extension>],
+        jwk_set: &JwkSet,
+    ) -> Vec<Result<Token<Extension>, EncodedTokenError>> {
+        BatchVerifier::new(jwk_set).verify_many(tokens)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        token::tests::{generate_token, JWKS_JSON},
+        AuthorizationClaims, Scope,
+    };
+
+    #[test]
+    fn verifies_each_token_with_shared_keys() {
+        let jwk_set: JwkSet = serde_json::from_str(JWKS_JSON).expect("expected JWK set");
+        let valid = generate_token(vec![Scope::new("read", "user")]).expect("expected token");
+        let forged: EncodedToken<AuthorizationClaims> = format!("{valid}x").into();
+        let results = Token::decode_many(&[valid.clone(), forged, valid], &jwk_set);
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        let token = results[2].as_ref().expect("expected decoded token");
+        assert_eq!(token.scopes(), &vec![Scope::new("read", "user")]);
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    io::{self, Read},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use clap::Parser;
+use jsonwebtoken::jwk::JwkSet;
+use lushus_jwt::{AccessToken, Claims, EncodedAccessToken};
+use serde_json::Value;
+
+/// Inspect a JWT and optionally verify it against a JWK set.
+#[derive(Debug, Parser)]
+#[command(name = "lushus-jwt")]
+struct Args {
+    /// Encoded token, with or without a "Bearer " prefix. Read from stdin when omitted.
+    token: Option<String>,
+    /// Verify the token against the JWK set served at this URL.
+    #[arg(long, conflicts_with = "jwks_file")]
+    jwks_url: Option<String>,
+    /// Verify the token against the JWK set in this file.
+    #[arg(long)]
+    jwks_file: Option<PathBuf>,
+    /// Prefix that every scope is expected to carry.
+    #[arg(long)]
+    scope_prefix: Option<String>,
+}
+
+fn read_token(token: Option<String>) -> Result<String> {
+    let token = match token {
+        Some(token) => token,
+        None => {
+            let mut token = String::new();
+            io::stdin()
+                .read_to_string(&mut token)
+                .context("unable to read token from stdin")?;
+            token
+        }
+    };
+    let token = token.trim();
+    let token = token.strip_prefix("Bearer ").unwrap_or(token);
+    Ok(token.trim().to_string())
+}
+
+fn decode_segment(token: &str, index: usize, name: &str) -> Result<Value> {
+    let segment = token
+        .split('.')
+        .nth(index)
+        .ok_or_else(|| anyhow!("token has no {name} segment"))?;
+    let bytes = URL_SAFE_NO_PAD
+        .decode(segment)
+        .with_context(|| format!("{name} is not base64url"))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("{name} is not JSON"))
+}
+
+async fn load_jwk_set(args: &Args) -> Result<Option<JwkSet>> {
+    if let Some(url) = &args.jwks_url {
+        let contents = reqwest::get(url)
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("unable to fetch JWK set from {url}"))?
+            .text()
+            .await
+            .with_context(|| format!("unable to read JWK set from {url}"))?;
+        let jwk_set = serde_json::from_str(&contents).context("unable to deserialize JWK set")?;
+        return Ok(Some(jwk_set));
+    }
+    if let Some(path) = &args.jwks_file {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("unable to read {}", path.display()))?;
+        let jwk_set = serde_json::from_str(&contents).context("unable to deserialize JWK set")?;
+        return Ok(Some(jwk_set));
+    }
+    Ok(None)
+}
+
+fn verify(token: &str, jwk_set: &JwkSet, scope_prefix: Option<&str>) -> Result<AccessToken> {
+    let encoded_token: EncodedAccessToken = token.to_string().into();
+    let token = match scope_prefix {
+        Some(prefix) => encoded_token.decode_with_scope_prefix(jwk_set, prefix),
+        None => encoded_token.decode(jwk_set),
+    };
+    token.map_err(|e| anyhow!("verification failed: {e}"))
+}
+
+async fn run(args: Args) -> Result<()> {
+    let token = read_token(args.token.clone())?;
+    let header = decode_segment(&token, 0, "header")?;
+    let payload = decode_segment(&token, 1, "payload")?;
+    println!("Header:\n{}", serde_json::to_string_pretty(&header)?);
+    println!("Claims:\n{}", serde_json::to_string_pretty(&payload)?);
+
+    let Some(jwk_set) = load_jwk_set(&args).await? else {
+        match serde_json::from_value::<Claims<Value>>(payload) {
+            Ok(claims) => print!("Summary:\n{}", claims.pretty()),
+            Err(e) => println!("Summary: unable to parse claims ({e})"),
+        }
+        println!("Signature: not verified");
+        return Ok(());
+    };
+    let token = verify(&token, &jwk_set, args.scope_prefix.as_deref())?;
+    print!("Summary:\n{}", token.describe());
+    println!("Signature: valid");
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let result = actix_web::rt::System::new().block_on(run(args));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,32 @@
+#[cfg(feature = "moka")]
+mod moka_backend;
+#[cfg(feature = "redis")]
+mod redis_backend;
+
+#[cfg(feature = "moka")]
+pub use moka_backend::MokaCacheBackend;
+#[cfg(feature = "redis")]
+pub use redis_backend::RedisCacheBackend;
+
+use std::time::Duration;
+
+use futures::future::LocalBoxFuture;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    #[error("cache backend failed: {0}")]
+    BackendError(String),
+}
+
+// Backends store opaque bytes so that callers decide how entries are encoded and
+// a shared backend such as Redis can serve several kinds of cached values.
+pub trait CacheBackend {
+    fn get(&self, key: &str) -> LocalBoxFuture<'static, Result<Option<Vec<u8>>, CacheError>>;
+
+    fn set(
+        &self,
+        key: &str,
+        value: Vec<u8>,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<(), CacheError>>;
+}
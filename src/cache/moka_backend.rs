@@ -0,0 +1,92 @@
+use std::{
+    future::ready,
+    time::{Duration, Instant},
+};
+
+use futures::future::LocalBoxFuture;
+use moka::{sync::Cache, Expiry};
+
+use crate::cache::{CacheBackend, CacheError};
+
+const DEFAULT_CAPACITY: u64 = 10_000;
+
+struct EntryExpiry;
+
+impl Expiry<String, (Vec<u8>, Duration)> for EntryExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &String,
+        value: &(Vec<u8>, Duration),
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        Some(value.1)
+    }
+}
+
+// Clones share their entries, so a single backend can be handed to every worker.
+#[derive(Clone)]
+pub struct MokaCacheBackend {
+    cache: Cache<String, (Vec<u8>, Duration)>,
+}
+
+impl MokaCacheBackend {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: u64) -> Self {
+        let cache = Cache::builder()
+            .max_capacity(capacity)
+            .expire_after(EntryExpiry)
+            .build();
+        Self { cache }
+    }
+}
+
+impl Default for MokaCacheBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CacheBackend for MokaCacheBackend {
+    fn get(&self, key: &str) -> LocalBoxFuture<'static, Result<Option<Vec<u8>>, CacheError>> {
+        let value = self.cache.get(key).map(|(value, _)| value);
+        Box::pin(ready(Ok(value)))
+    }
+
+    fn set(
+        &self,
+        key: &str,
+        value: Vec<u8>,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<(), CacheError>> {
+        self.cache.insert(key.to_string(), (value, ttl));
+        Box::pin(ready(Ok(())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    #[test]
+    fn backend_returns_stored_values() {
+        let backend = MokaCacheBackend::new();
+        let ttl = Duration::from_secs(60);
+        block_on(backend.set("jwks", b"{\"keys\":[]}".to_vec(), ttl)).unwrap();
+        let value = block_on(backend.get("jwks")).unwrap();
+        assert_eq!(value.as_deref(), Some(&b"{\"keys\":[]}"[..]));
+        assert_eq!(block_on(backend.get("other")).unwrap(), None);
+    }
+
+    #[test]
+    fn backend_expires_entries_after_ttl() {
+        let backend = MokaCacheBackend::new();
+        block_on(backend.set("jwks", vec![1], Duration::from_millis(10))).unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(block_on(backend.get("jwks")).unwrap(), None);
+    }
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use futures::future::LocalBoxFuture;
+use redis::{aio::ConnectionManager, AsyncCommands};
+
+use crate::cache::{CacheBackend, CacheError};
+
+const DEFAULT_KEY_PREFIX: &str = "lushus-jwt:";
+
+// The connection manager reconnects on its own and is cheap to clone, so every
+// worker can hold a copy of the same backend.
+#[derive(Clone)]
+pub struct RedisCacheBackend {
+    connection: ConnectionManager,
+    key_prefix: String,
+}
+
+impl RedisCacheBackend {
+    pub fn new(connection: ConnectionManager) -> Self {
+        Self {
+            connection,
+            key_prefix: DEFAULT_KEY_PREFIX.to_string(),
+        }
+    }
+
+    pub async fn connect(url: &str) -> Result<Self, CacheError> {
+        let client = redis::Client::open(url).map_err(backend_error)?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .map_err(backend_error)?;
+        Ok(Self::new(connection))
+    }
+
+    pub fn key_prefix(mut self, value: &str) -> Self {
+        self.key_prefix = value.to_string();
+        self
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{}", self.key_prefix, key)
+    }
+}
+
+fn backend_error(e: redis::RedisError) -> CacheError {
+    CacheError::BackendError(e.to_string())
+}
+
+impl CacheBackend for RedisCacheBackend {
+    fn get(&self, key: &str) -> LocalBoxFuture<'static, Result<Option<Vec<u8>>, CacheError>> {
+        let mut connection = self.connection.clone();
+        let key = self.key(key);
+        Box::pin(async move { connection.get(key).await.map_err(backend_error) })
+    }
+
+    fn set(
+        &self,
+        key: &str,
+        value: Vec<u8>,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<(), CacheError>> {
+        let mut connection = self.connection.clone();
+        let key = self.key(key);
+        let ttl = ttl.as_millis().max(1) as u64;
+        Box::pin(async move {
+            redis::cmd("SET")
+                .arg(key)
+                .arg(value)
+                .arg("PX")
+                .arg(ttl)
+                .query_async(&mut connection)
+                .await
+                .map_err(backend_error)
+        })
+    }
+}
@@ -1,26 +1,117 @@
+mod actor;
 mod authorization_claims;
+mod claims_change;
+mod claims_profile;
+mod claims_validator;
+mod lifetime_policy;
+mod machine_claims;
+mod pretty;
+mod subject;
 mod user_claims;
 
 use std::{
+    collections::HashSet,
+    slice,
     time::{Duration, SystemTime},
     vec,
 };
 
-pub use authorization_claims::AuthorizationClaims;
-pub use user_claims::UserClaims;
+pub use actor::Actor;
+pub(crate) use authorization_claims::RawAuthorizationClaims;
+pub use authorization_claims::{AuthorizationClaims, ScopeFormat};
+pub use claims_change::ClaimsChange;
+pub use claims_profile::ClaimsProfile;
+pub use claims_validator::ClaimsValidator;
+pub use lifetime_policy::LifetimePolicy;
+pub use machine_claims::MachineClaims;
+pub use subject::Subject;
+pub(crate) use user_claims::check_hosted_domain;
+pub use user_claims::{UserClaims, UserClaimsError, UserClaimsPolicy};
 
 use crate::scope::Scope;
 
-#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 #[serde(untagged)]
-pub enum Audience {
+enum AudienceRepr {
     Single(String),
     Multiple(Vec<String>),
 }
 
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(from = "AudienceRepr", into = "AudienceRepr")]
+pub struct Audience {
+    values: Vec<String>,
+    index: HashSet<String>,
+    single: bool,
+}
+
+impl Audience {
+    fn from_values(values: Vec<String>, single: bool) -> Self {
+        let mut index = HashSet::new();
+        let values = values
+            .into_iter()
+            .filter(|value| index.insert(value.clone()))
+            .collect();
+        Self {
+            values,
+            index,
+            single,
+        }
+    }
+
+    // A hashed lookup leaks no byte-by-byte timing of the compared values.
+    pub fn contains(&self, audience: &str) -> bool {
+        self.index.contains(audience)
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, String> {
+        self.values.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl PartialEq for Audience {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl Eq for Audience {}
+
+impl From<AudienceRepr> for Audience {
+    fn from(value: AudienceRepr) -> Self {
+        match value {
+            AudienceRepr::Single(single) => Audience::from_values(vec![single], true),
+            AudienceRepr::Multiple(multiple) => Audience::from_values(multiple, false),
+        }
+    }
+}
+
+impl From<Audience> for AudienceRepr {
+    fn from(value: Audience) -> Self {
+        match (value.single, value.values.as_slice()) {
+            (true, [single]) => AudienceRepr::Single(single.clone()),
+            _ => AudienceRepr::Multiple(value.values),
+        }
+    }
+}
+
 impl From<Vec<String>> for Audience {
     fn from(value: Vec<String>) -> Self {
-        Audience::Multiple(value)
+        Audience::from_values(value, false)
+    }
+}
+
+impl From<&str> for Audience {
+    fn from(value: &str) -> Self {
+        Audience::from_values(vec![value.to_string()], true)
     }
 }
 
@@ -29,23 +120,70 @@ impl IntoIterator for Audience {
     type IntoIter = vec::IntoIter<Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
-        match self {
-            Audience::Single(single) => vec![single],
-            Audience::Multiple(multiple) => multiple,
-        }
-        .into_iter()
+        self.values.into_iter()
     }
 }
 
-#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+impl<'a> IntoIterator for &'a Audience {
+    type Item = &'a String;
+    type IntoIter = slice::Iter<'a, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
 pub struct Claims<Extension> {
     pub iss: String,
-    pub sub: String,
-    pub aud: Audience,
-    pub iat: u64,
+    #[serde(default)]
+    pub sub: Option<Subject>,
+    #[serde(default)]
+    pub aud: Option<Audience>,
+    #[serde(default, deserialize_with = "crate::optional_timestamp_deserialize")]
+    pub iat: Option<u64>,
+    #[serde(deserialize_with = "crate::timestamp_deserialize")]
     pub exp: u64,
     #[serde(flatten)]
     pub extension: Extension,
+    #[serde(flatten)]
+    pub additional: serde_json::Map<String, serde_json::Value>,
+}
+
+// Both flattened fields see the unknown claims while deserializing, so an
+// extension that keeps them, such as serde_json::Value, would repeat them.
+// Additional claims the extension already writes are skipped.
+impl<Extension: serde::Serialize> serde::Serialize for Claims<Extension> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{Error, SerializeMap};
+
+        let extension = match serde_json::to_value(&self.extension).map_err(S::Error::custom)? {
+            serde_json::Value::Object(extension) => extension,
+            serde_json::Value::Null => serde_json::Map::new(),
+            _ => return Err(S::Error::custom("extension must serialize to an object")),
+        };
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("iss", &self.iss)?;
+        if let Some(sub) = &self.sub {
+            map.serialize_entry("sub", sub)?;
+        }
+        if let Some(aud) = &self.aud {
+            map.serialize_entry("aud", aud)?;
+        }
+        if let Some(iat) = &self.iat {
+            map.serialize_entry("iat", iat)?;
+        }
+        map.serialize_entry("exp", &self.exp)?;
+        for (name, value) in &extension {
+            map.serialize_entry(name, value)?;
+        }
+        for (name, value) in &self.additional {
+            if !extension.contains_key(name) {
+                map.serialize_entry(name, value)?;
+            }
+        }
+        map.end()
+    }
 }
 
 type Resource = String;
@@ -56,7 +194,7 @@ impl<Extension> Claims<Extension> {
     pub fn new(
         iss: &str,
         sub: &str,
-        aud: &Vec<String>,
+        aud: &[String],
         lifetime: Duration,
         extension: Extension,
     ) -> Self {
@@ -66,13 +204,75 @@ impl<Extension> Claims<Extension> {
         let exp = iat + lifetime;
         Self {
             iss: iss.to_string(),
-            sub: sub.to_string(),
-            aud: aud.clone().into(),
-            iat: iat.as_secs(),
+            sub: Some(sub.into()),
+            aud: Some(aud.to_vec().into()),
+            iat: Some(iat.as_secs()),
             exp: exp.as_secs(),
             extension,
+            additional: Default::default(),
+        }
+    }
+
+    // Registered claims are read from their fields; None for other names.
+    pub(crate) fn registered_claim(&self, name: &str) -> Option<serde_json::Value> {
+        match name {
+            "iss" => Some(self.iss.as_str().into()),
+            "sub" => self
+                .sub
+                .as_ref()
+                .and_then(|sub| serde_json::to_value(sub).ok()),
+            "aud" => self
+                .aud
+                .as_ref()
+                .and_then(|aud| serde_json::to_value(aud).ok()),
+            "iat" => self.iat.map(Into::into),
+            "exp" => Some(self.exp.into()),
+            _ => None,
         }
     }
+
+    // Looks the claim up among the registered claims, then the additional ones,
+    // and finally among those the extension consumed while deserializing.
+    pub fn get_claim<T>(&self, name: &str) -> Result<Option<T>, serde_json::Error>
+    where
+        T: serde::de::DeserializeOwned,
+        Extension: serde::Serialize,
+    {
+        let value = match self
+            .registered_claim(name)
+            .or_else(|| self.additional.get(name).cloned())
+        {
+            Some(value) => Some(value),
+            None => match serde_json::to_value(&self.extension)? {
+                serde_json::Value::Object(mut extension) => extension.remove(name),
+                _ => None,
+            },
+        };
+        value.map(serde_json::from_value).transpose()
+    }
+
+    // RFC 9068 access tokens name the OAuth client they were issued to.
+    pub fn client_id(&self) -> Result<Option<String>, serde_json::Error>
+    where
+        Extension: serde::Serialize,
+    {
+        self.get_claim("client_id")
+    }
+
+    pub(crate) fn try_map_extension<T, E>(
+        self,
+        f: impl FnOnce(Extension) -> Result<T, E>,
+    ) -> Result<Claims<T>, E> {
+        Ok(Claims {
+            iss: self.iss,
+            sub: self.sub,
+            aud: self.aud,
+            iat: self.iat,
+            exp: self.exp,
+            extension: f(self.extension)?,
+            additional: self.additional,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -95,7 +295,11 @@ mod test {
         }"#;
         let claims: Claims<TestExtension> =
             serde_json::from_str(string).expect("Expected deserialize");
-        let aud = claims.aud.into_iter().collect::<Vec<_>>();
+        let aud = claims
+            .aud
+            .expect("Expected audience")
+            .into_iter()
+            .collect::<Vec<_>>();
         let expected_aud = vec!["audience".to_string()];
         assert_eq!(aud, expected_aud);
     }
@@ -113,7 +317,11 @@ mod test {
         }"#;
         let claims: Claims<TestExtension> =
             serde_json::from_str(string).expect("Expected deserialize");
-        let aud = claims.aud.into_iter().collect::<Vec<_>>();
+        let aud = claims
+            .aud
+            .expect("Expected audience")
+            .into_iter()
+            .collect::<Vec<_>>();
         let expected_aud = vec![
             "audience_a".to_string(),
             "audience_b".to_string(),
@@ -121,4 +329,151 @@ mod test {
         ];
         assert_eq!(aud, expected_aud);
     }
+
+    #[test]
+    fn claims_can_be_constructed_without_audience() {
+        let string = r#"
+        {
+            "iss": "issuer",
+            "sub": "subject",
+            "iat": 1000,
+            "exp": 1000
+        }"#;
+        let claims: Claims<TestExtension> =
+            serde_json::from_str(string).expect("Expected deserialize");
+        assert!(claims.aud.is_none());
+    }
+
+    #[test]
+    fn value_extension_claims_are_serialized_once() {
+        let string = r#"{"iss": "issuer", "exp": 1000, "tier": "enterprise"}"#;
+        let claims: Claims<serde_json::Value> =
+            serde_json::from_str(string).expect("Expected deserialize");
+        let serialized = serde_json::to_string(&claims).expect("Expected serialize");
+        assert_eq!(serialized.matches("tier").count(), 1);
+        let claims: Claims<TestExtension> =
+            serde_json::from_str(&serialized).expect("Expected deserialize");
+        assert_eq!(claims.additional.get("tier"), Some(&"enterprise".into()));
+    }
+
+    #[test]
+    fn claims_require_issuer() {
+        let string = r#"{"sub": "subject", "exp": 1000}"#;
+        let claims = serde_json::from_str::<Claims<TestExtension>>(string);
+        assert!(claims.is_err());
+    }
+
+    #[test]
+    fn claims_accept_float_and_string_timestamps() {
+        let string = r#"
+        {
+            "iss": "issuer",
+            "sub": "subject",
+            "aud": "audience",
+            "iat": 1000.75,
+            "exp": "2000"
+        }"#;
+        let claims: Claims<TestExtension> =
+            serde_json::from_str(string).expect("Expected deserialize");
+        assert_eq!(claims.iat, Some(1000));
+        assert_eq!(claims.exp, 2000);
+    }
+
+    #[test]
+    fn claims_reject_negative_timestamps() {
+        let string = r#"
+        {
+            "iss": "issuer",
+            "sub": "subject",
+            "aud": "audience",
+            "exp": -1
+        }"#;
+        let result = serde_json::from_str::<Claims<TestExtension>>(string);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn client_id_is_read_from_additional_claims() {
+        let string = r#"{"iss": "issuer", "exp": 1000, "client_id": "client"}"#;
+        let claims: Claims<TestExtension> =
+            serde_json::from_str(string).expect("Expected deserialize");
+        assert_eq!(
+            claims.client_id().expect("Expected client_id"),
+            Some("client".to_string())
+        );
+        let string = r#"{"iss": "issuer", "exp": 1000, "client_id": 1}"#;
+        let claims: Claims<TestExtension> =
+            serde_json::from_str(string).expect("Expected deserialize");
+        assert!(claims.client_id().is_err());
+    }
+
+    #[test]
+    fn registered_and_extension_claims_can_be_read() {
+        let string = r#"{"iss": "issuer", "sub": "subject", "aud": "audience", "exp": 1000,
+            "scope": "read:users"}"#;
+        let claims: Claims<AuthorizationClaims> =
+            serde_json::from_str(string).expect("Expected deserialize");
+        let claim = |name| claims.get_claim::<String>(name).expect("Expected claim");
+        assert_eq!(claim("iss"), Some("issuer".to_string()));
+        assert_eq!(claim("sub"), Some("subject".to_string()));
+        assert_eq!(claim("aud"), Some("audience".to_string()));
+        assert_eq!(claim("scope"), Some("read:users".to_string()));
+        assert_eq!(claim("iat"), None);
+        assert_eq!(
+            claims.get_claim::<u64>("exp").expect("Expected exp"),
+            Some(1000)
+        );
+    }
+
+    #[test]
+    fn custom_claims_can_be_read_with_types() {
+        let string = r#"
+        {
+            "iss": "issuer",
+            "sub": "subject",
+            "exp": 1000,
+            "https://example.com/roles": ["admin", "editor"],
+            "tier": "enterprise"
+        }"#;
+        let claims: Claims<TestExtension> =
+            serde_json::from_str(string).expect("Expected deserialize");
+        let roles: Option<Vec<String>> = claims
+            .get_claim("https://example.com/roles")
+            .expect("Expected roles");
+        assert_eq!(roles, Some(vec!["admin".to_string(), "editor".to_string()]));
+        let missing: Option<String> = claims.get_claim("missing").expect("Expected no error");
+        assert_eq!(missing, None);
+        let mistyped = claims.get_claim::<u64>("tier");
+        assert!(mistyped.is_err());
+    }
+
+    #[test]
+    fn audience_is_deduplicated_in_order() {
+        let aud: Audience = vec![
+            "audience_b".to_string(),
+            "audience_a".to_string(),
+            "audience_b".to_string(),
+        ]
+        .into();
+        let values = aud.iter().cloned().collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec!["audience_b".to_string(), "audience_a".to_string()]
+        );
+    }
+
+    #[test]
+    fn audience_contains_without_consuming() {
+        let aud: Audience = vec!["audience_a".to_string(), "audience_b".to_string()].into();
+        assert!(aud.contains("audience_b"));
+        assert!(!aud.contains("audience_c"));
+        assert_eq!(aud.len(), 2);
+    }
+
+    #[test]
+    fn single_audience_round_trips_as_string() {
+        let aud: Audience = serde_json::from_str(r#""audience""#).expect("Expected deserialize");
+        let string = serde_json::to_string(&aud).expect("Expected serialize");
+        assert_eq!(string, r#""audience""#);
+    }
 }
@@ -0,0 +1,88 @@
+use serde_json::{Map, Value};
+
+use crate::{Claims, Subject};
+
+// RFC 8693 "act" claim. Each actor may carry the actor that delegated to it, so
+// the chain runs from the current actor back to the original one.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Actor {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub: Option<Subject>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Box<Actor>>,
+    #[serde(flatten)]
+    pub additional: Map<String, Value>,
+}
+
+impl Actor {
+    pub fn chain(&self) -> Vec<&Actor> {
+        let mut chain = vec![self];
+        let mut actor = self;
+        while let Some(previous) = actor.act.as_deref() {
+            chain.push(previous);
+            actor = previous;
+        }
+        chain
+    }
+}
+
+impl<Extension: serde::Serialize> Claims<Extension> {
+    pub fn actor(&self) -> Result<Option<Actor>, serde_json::Error> {
+        self.get_claim("act")
+    }
+
+    pub fn actor_chain(&self) -> Result<Vec<Actor>, serde_json::Error> {
+        let chain = self
+            .actor()?
+            .map(|actor| actor.chain().into_iter().cloned().collect())
+            .unwrap_or_default();
+        Ok(chain)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::AuthorizationClaims;
+
+    #[test]
+    fn actor_chain_follows_nested_actors() {
+        let string = r#"
+        {
+            "iss": "https://issuer.example.com",
+            "sub": "user@example.com",
+            "aud": "https://consumer.example.com",
+            "exp": 1443904177,
+            "act": {
+                "sub": "https://service16.example.com",
+                "act": {
+                    "sub": "https://service77.example.com"
+                }
+            }
+        }"#;
+        let claims: Claims<AuthorizationClaims> =
+            serde_json::from_str(string).expect("expected claims");
+        let chain = claims.actor_chain().expect("expected actor chain");
+        let subjects = chain
+            .iter()
+            .filter_map(|actor| actor.sub.as_ref().map(Subject::as_str))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            subjects,
+            vec![
+                "https://service16.example.com",
+                "https://service77.example.com"
+            ]
+        );
+    }
+
+    #[test]
+    fn actor_is_none_without_act_claim() {
+        let claims: Claims<AuthorizationClaims> =
+            serde_json::from_str(r#"{ "iss": "issuer", "exp": 0 }"#).expect("expected claims");
+        assert_eq!(claims.actor().expect("expected actor"), None);
+        assert!(claims.actor_chain().expect("expected chain").is_empty());
+    }
+}
@@ -1,18 +1,84 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use serde_json::{json, Value};
 
 use super::{ActionList, Claims, Resource, Scope};
+use crate::ScopeError;
+
+// How the scope claim is written. Both forms are accepted when reading.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScopeFormat {
+    #[default]
+    SpaceSeparated,
+    Array,
+}
 
 #[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(from = "ScopeClaims<Scope>")]
 pub struct AuthorizationClaims {
     #[serde(
-        deserialize_with = "crate::space_separated_deserialize",
         serialize_with = "crate::space_separated_serialize",
-        alias = "scope",
         rename(serialize = "scope")
     )]
     pub scopes: Vec<Scope>,
 }
 
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(from = "ScopeClaims<String>")]
+pub(crate) struct RawAuthorizationClaims {
+    pub scopes: Vec<String>,
+}
+
+// scope and scp are read as separate claims and merged, since aliasing both to
+// one field makes serde reject tokens that carry both as a duplicate field.
+#[derive(serde::Deserialize)]
+#[serde(bound(deserialize = "T: FromStr, T::Err: Display"))]
+struct ScopeClaims<T> {
+    #[serde(default, deserialize_with = "crate::space_separated_deserialize")]
+    scope: Vec<T>,
+    #[serde(default, deserialize_with = "crate::space_separated_deserialize")]
+    scp: Vec<T>,
+}
+
+impl<T: PartialEq> ScopeClaims<T> {
+    fn merged(self) -> Vec<T> {
+        let mut scopes = self.scope;
+        for scope in self.scp {
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+        scopes
+    }
+}
+
+impl From<ScopeClaims<Scope>> for AuthorizationClaims {
+    fn from(claims: ScopeClaims<Scope>) -> Self {
+        Self {
+            scopes: claims.merged(),
+        }
+    }
+}
+
+impl From<ScopeClaims<String>> for RawAuthorizationClaims {
+    fn from(claims: ScopeClaims<String>) -> Self {
+        Self {
+            scopes: claims.merged(),
+        }
+    }
+}
+
+impl RawAuthorizationClaims {
+    pub(crate) fn with_scope_prefix(self, prefix: &str) -> Result<AuthorizationClaims, ScopeError> {
+        let scopes = self
+            .scopes
+            .iter()
+            .map(|scope| Scope::parse_with_prefix(scope, prefix))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(AuthorizationClaims { scopes })
+    }
+}
+
 impl Claims<AuthorizationClaims> {
     pub fn scopes(&self) -> &Vec<Scope> {
         &self.extension.scopes
@@ -30,10 +96,26 @@ impl Claims<AuthorizationClaims> {
         }
         resources
     }
+
+    // Serde always writes scope as a space-separated string; this renders the
+    // claims for consumers that expect another format.
+    pub fn to_value_with_scope_format(
+        &self,
+        format: ScopeFormat,
+    ) -> Result<Value, serde_json::Error> {
+        let mut value = serde_json::to_value(self)?;
+        if let (ScopeFormat::Array, Some(object)) = (format, value.as_object_mut()) {
+            let scopes = self.scopes().iter().map(ToString::to_string);
+            object.insert("scope".to_string(), json!(scopes.collect::<Vec<_>>()));
+        }
+        Ok(value)
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use std::time::Duration;
+
     use super::*;
 
     #[test]
@@ -62,11 +144,12 @@ mod test {
         };
         let expected_claims = Claims::<AuthorizationClaims> {
             iss: "issuer".to_string(),
-            sub: "subject".to_string(),
-            aud: vec!["audience".to_string()].into(),
+            sub: Some("subject".into()),
+            aud: Some(vec!["audience".to_string()].into()),
             extension,
-            iat: 1000,
+            iat: Some(1000),
             exp: 1000,
+            additional: Default::default(),
         };
         assert_eq!(claims, expected_claims)
     }
@@ -83,16 +166,84 @@ mod test {
         };
         let claims = Claims::<AuthorizationClaims> {
             iss: "issuer".to_string(),
-            sub: "subject".to_string(),
-            aud: vec!["audience".to_string()].into(),
+            sub: Some("subject".into()),
+            aud: Some(vec!["audience".to_string()].into()),
             extension: AuthorizationClaims {
                 scopes: vec![scope_create_users, scope_read_users],
             },
-            iat: 1000,
+            iat: Some(1000),
             exp: 1000,
+            additional: Default::default(),
         };
         let string = serde_json::to_string(&claims).expect("Expected serialize");
         let expected_string = r#"{"iss":"issuer","sub":"subject","aud":["audience"],"iat":1000,"exp":1000,"scope":"create:users read:users"}"#;
         assert_eq!(string, expected_string);
     }
+
+    #[test]
+    fn can_be_serialized_with_scope_array() {
+        let extension = AuthorizationClaims {
+            scopes: vec![Scope::new("create", "users"), Scope::new("read", "users")],
+        };
+        let claims = Claims::new("issuer", "subject", &[], Duration::from_secs(60), extension);
+        let value = claims
+            .to_value_with_scope_format(ScopeFormat::Array)
+            .expect("Expected serialize");
+        assert_eq!(value["scope"], json!(["create:users", "read:users"]));
+        let claims: Claims<AuthorizationClaims> =
+            serde_json::from_value(value).expect("Expected deserialize");
+        assert_eq!(claims.scopes().len(), 2);
+        let value = claims
+            .to_value_with_scope_format(ScopeFormat::SpaceSeparated)
+            .expect("Expected serialize");
+        assert_eq!(value["scope"], json!("create:users read:users"));
+    }
+
+    #[test]
+    fn can_be_deserialized_with_irregular_whitespace() {
+        let string = r#"{"scope":" create:users  \tread:users\n"}"#;
+        let claims: AuthorizationClaims =
+            serde_json::from_str(string).expect("Expected deserialize");
+        let expected_scopes = vec![Scope::new("create", "users"), Scope::new("read", "users")];
+        assert_eq!(claims.scopes, expected_scopes);
+    }
+
+    #[test]
+    fn can_be_deserialized_from_empty_scope() {
+        let string = r#"{"scope":""}"#;
+        let claims: AuthorizationClaims =
+            serde_json::from_str(string).expect("Expected deserialize");
+        assert!(claims.scopes.is_empty());
+    }
+
+    #[test]
+    fn can_be_deserialized_from_scp_array() {
+        let string = r#"{"iss":"issuer","exp":1000,"scp":["create:users","read:users"]}"#;
+        let claims: Claims<AuthorizationClaims> =
+            serde_json::from_str(string).expect("Expected deserialize");
+        let expected_scopes = vec![Scope::new("create", "users"), Scope::new("read", "users")];
+        assert_eq!(claims.extension.scopes, expected_scopes);
+    }
+
+    #[test]
+    fn merges_scope_and_scp_claims() {
+        let string = r#"{"scope":"create:users read:users","scp":["read:users","read:teams"]}"#;
+        let claims: AuthorizationClaims =
+            serde_json::from_str(string).expect("Expected deserialize");
+        let expected_scopes = vec![
+            Scope::new("create", "users"),
+            Scope::new("read", "users"),
+            Scope::new("read", "teams"),
+        ];
+        assert_eq!(claims.scopes, expected_scopes);
+        let raw: RawAuthorizationClaims =
+            serde_json::from_str(string).expect("Expected deserialize");
+        assert_eq!(raw.scopes, ["create:users", "read:users", "read:teams"]);
+    }
+
+    #[test]
+    fn can_be_deserialized_without_scope() {
+        let claims: AuthorizationClaims = serde_json::from_str("{}").expect("Expected deserialize");
+        assert!(claims.scopes.is_empty());
+    }
 }
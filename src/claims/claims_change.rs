@@ -0,0 +1,92 @@
+use super::{AuthorizationClaims, Claims, Scope};
+
+// What changed between two versions of a token's claims, e.g. before and after a
+// refresh. Callers caching authorization decisions should drop them when the
+// change narrows permissions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClaimsChange {
+    pub gained_scopes: Vec<Scope>,
+    pub lost_scopes: Vec<Scope>,
+    pub added_audiences: Vec<String>,
+    pub removed_audiences: Vec<String>,
+}
+
+impl ClaimsChange {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn narrows(&self) -> bool {
+        !self.lost_scopes.is_empty() || !self.removed_audiences.is_empty()
+    }
+}
+
+fn difference<T: PartialEq + Clone>(values: &[T], other: &[T]) -> Vec<T> {
+    values
+        .iter()
+        .filter(|value| !other.contains(value))
+        .cloned()
+        .collect()
+}
+
+impl Claims<AuthorizationClaims> {
+    // Compares `self`, the earlier claims, with `newer`.
+    pub fn change_to(&self, newer: &Claims<AuthorizationClaims>) -> ClaimsChange {
+        let audiences = |claims: &Claims<AuthorizationClaims>| {
+            claims.aud.iter().flatten().cloned().collect::<Vec<_>>()
+        };
+        let (before, after) = (audiences(self), audiences(newer));
+        ClaimsChange {
+            gained_scopes: difference(newer.scopes(), self.scopes()),
+            lost_scopes: difference(self.scopes(), newer.scopes()),
+            added_audiences: difference(&after, &before),
+            removed_audiences: difference(&before, &after),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn claims(aud: &[&str], scopes: Vec<Scope>) -> Claims<AuthorizationClaims> {
+        let aud = aud.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let extension = AuthorizationClaims { scopes };
+        Claims::new(
+            "issuer",
+            "subject",
+            &aud,
+            Duration::from_secs(60),
+            extension,
+        )
+    }
+
+    #[test]
+    fn change_reports_scopes_and_audiences() {
+        let before = claims(
+            &["api", "admin"],
+            vec![Scope::new("read", "users"), Scope::new("write", "users")],
+        );
+        let after = claims(
+            &["api"],
+            vec![Scope::new("read", "users"), Scope::new("read", "projects")],
+        );
+        let change = before.change_to(&after);
+        assert_eq!(change.gained_scopes, vec![Scope::new("read", "projects")]);
+        assert_eq!(change.lost_scopes, vec![Scope::new("write", "users")]);
+        assert!(change.added_audiences.is_empty());
+        assert_eq!(change.removed_audiences, vec!["admin".to_string()]);
+        assert!(change.narrows());
+    }
+
+    #[test]
+    fn refresh_without_changes_is_empty() {
+        let before = claims(&["api"], vec![Scope::new("read", "users")]);
+        let after = claims(&["api"], vec![Scope::new("read", "users")]);
+        let change = before.change_to(&after);
+        assert!(change.is_empty());
+        assert!(!change.narrows());
+    }
+}
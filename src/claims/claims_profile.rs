@@ -0,0 +1,85 @@
+use super::Claims;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClaimsProfile {
+    require_subject: bool,
+    require_audience: bool,
+    require_issued_at: bool,
+}
+
+impl ClaimsProfile {
+    pub fn new() -> Self {
+        Self {
+            require_subject: true,
+            require_audience: true,
+            require_issued_at: true,
+        }
+    }
+
+    // client_credentials tokens identify a client rather than a user, so only
+    // iss, exp and aud are required; see MachineClaims for the client id.
+    pub fn machine() -> Self {
+        Self::new().require_subject(false).require_issued_at(false)
+    }
+
+    pub fn require_subject(mut self, value: bool) -> Self {
+        self.require_subject = value;
+        self
+    }
+
+    pub fn require_audience(mut self, value: bool) -> Self {
+        self.require_audience = value;
+        self
+    }
+
+    pub fn require_issued_at(mut self, value: bool) -> Self {
+        self.require_issued_at = value;
+        self
+    }
+
+    pub fn missing_claims<Extension>(&self, claims: &Claims<Extension>) -> Vec<&'static str> {
+        let mut missing = vec![];
+        if self.require_subject && claims.sub.is_none() {
+            missing.push("sub");
+        }
+        if self.require_audience && claims.aud.is_none() {
+            missing.push("aud");
+        }
+        if self.require_issued_at && claims.iat.is_none() {
+            missing.push("iat");
+        }
+        missing
+    }
+}
+
+impl Default for ClaimsProfile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn claims(string: &str) -> Claims<serde_json::Value> {
+        serde_json::from_str(string).expect("Expected deserialize")
+    }
+
+    #[test]
+    fn default_profile_requires_all_claims() {
+        let claims = claims(r#"{"iss":"issuer","exp":1000}"#);
+        let missing = ClaimsProfile::default().missing_claims(&claims);
+        assert_eq!(missing, vec!["sub", "aud", "iat"]);
+    }
+
+    #[test]
+    fn relaxed_profile_accepts_missing_claims() {
+        let claims = claims(r#"{"iss":"issuer","aud":"audience","exp":1000}"#);
+        let missing = ClaimsProfile::new()
+            .require_subject(false)
+            .require_issued_at(false)
+            .missing_claims(&claims);
+        assert!(missing.is_empty());
+    }
+}
@@ -0,0 +1,14 @@
+use crate::Claims;
+
+pub trait ClaimsValidator<Extension> {
+    fn validate(&self, claims: &Claims<Extension>) -> Result<(), String>;
+}
+
+impl<Extension, F> ClaimsValidator<Extension> for F
+where
+    F: Fn(&Claims<Extension>) -> Result<(), String>,
+{
+    fn validate(&self, claims: &Claims<Extension>) -> Result<(), String> {
+        self(claims)
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::{Duration, SystemTime};
+
+use super::{Claims, ClaimsValidator};
+
+// Rejects tokens minted with a suspiciously long validity, or used long after
+// they were issued, whatever their exp says. Register it with
+// AuthorizationFactory::validator. Tokens without iat fail either check.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LifetimePolicy {
+    max_lifetime: Option<Duration>,
+    max_age: Option<Duration>,
+}
+
+impl LifetimePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Upper bound on exp - iat.
+    pub fn max_lifetime(mut self, value: Duration) -> Self {
+        self.max_lifetime = Some(value);
+        self
+    }
+
+    // Upper bound on the time elapsed since iat.
+    pub fn max_age(mut self, value: Duration) -> Self {
+        self.max_age = Some(value);
+        self
+    }
+
+    fn check<Extension>(&self, claims: &Claims<Extension>, now: u64) -> Result<(), String> {
+        if self.max_lifetime.is_none() && self.max_age.is_none() {
+            return Ok(());
+        }
+        let iat = claims.iat.ok_or("Missing required claims: iat")?;
+        if let Some(max_lifetime) = self.max_lifetime {
+            let lifetime = claims.exp.saturating_sub(iat);
+            if lifetime > max_lifetime.as_secs() {
+                return Err(format!(
+                    "Token lifetime {lifetime}s exceeds {}s",
+                    max_lifetime.as_secs()
+                ));
+            }
+        }
+        if let Some(max_age) = self.max_age {
+            let age = now.saturating_sub(iat);
+            if age > max_age.as_secs() {
+                return Err(format!("Token age {age}s exceeds {}s", max_age.as_secs()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<Extension> ClaimsValidator<Extension> for LifetimePolicy {
+    fn validate(&self, claims: &Claims<Extension>) -> Result<(), String> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("Expected system time since epoch");
+        self.check(claims, now.as_secs())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::AuthorizationClaims;
+
+    fn claims(iat: Option<u64>, exp: u64) -> Claims<AuthorizationClaims> {
+        let extension = AuthorizationClaims { scopes: vec![] };
+        let mut claims = Claims::new("issuer", "subject", &[], Duration::ZERO, extension);
+        claims.iat = iat;
+        claims.exp = exp;
+        claims
+    }
+
+    #[test]
+    fn max_lifetime_bounds_exp_minus_iat() {
+        let policy = LifetimePolicy::new().max_lifetime(Duration::from_secs(3600));
+        assert!(policy.check(&claims(Some(1000), 4600), 1000).is_ok());
+        assert!(policy.check(&claims(Some(1000), 4601), 1000).is_err());
+        assert!(policy.check(&claims(None, 4600), 1000).is_err());
+    }
+
+    #[test]
+    fn max_age_bounds_time_since_iat() {
+        let policy = LifetimePolicy::new().max_age(Duration::from_secs(600));
+        assert!(policy.check(&claims(Some(1000), 100_000), 1600).is_ok());
+        assert!(policy.check(&claims(Some(1000), 100_000), 1601).is_err());
+        assert!(LifetimePolicy::new().check(&claims(None, 0), 0).is_ok());
+    }
+}
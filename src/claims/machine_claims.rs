@@ -0,0 +1,65 @@
+use super::{AuthorizationClaims, Claims};
+use crate::FromClaims;
+
+// IdPs name the calling client differently: client_id (RFC 9068, Keycloak), azp
+// (Auth0), appid (Azure AD v1) and cid (Okta). The first one present wins, so
+// tokens carrying several of them are not rejected as duplicates.
+#[derive(Debug, Default, serde::Deserialize)]
+struct RawMachineClaims {
+    client_id: Option<String>,
+    azp: Option<String>,
+    appid: Option<String>,
+    cid: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "RawMachineClaims")]
+pub struct MachineClaims {
+    pub client_id: String,
+}
+
+impl TryFrom<RawMachineClaims> for MachineClaims {
+    type Error = String;
+
+    fn try_from(value: RawMachineClaims) -> Result<Self, Self::Error> {
+        let client_id = value
+            .client_id
+            .or(value.azp)
+            .or(value.appid)
+            .or(value.cid)
+            .ok_or("missing client_id claim")?;
+        Ok(Self { client_id })
+    }
+}
+
+impl FromClaims for MachineClaims {
+    type Error = serde_json::Error;
+
+    fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error> {
+        serde_json::from_value(claims.additional.clone().into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ClaimsProfile;
+
+    #[test]
+    fn client_id_is_read_from_provider_specific_claims() {
+        let string = r#"{"iss":"issuer","aud":"api","exp":1000,"cid":"okta-client"}"#;
+        let claims: Claims<MachineClaims> = serde_json::from_str(string).expect("expected claims");
+        assert_eq!(claims.extension.client_id, "okta-client");
+        assert!(ClaimsProfile::machine().missing_claims(&claims).is_empty());
+
+        let string = r#"{"iss":"issuer","aud":"api","exp":1000,"azp":"a","client_id":"b"}"#;
+        let claims: Claims<MachineClaims> = serde_json::from_str(string).expect("expected claims");
+        assert_eq!(claims.extension.client_id, "b");
+    }
+
+    #[test]
+    fn missing_client_id_is_rejected() {
+        let string = r#"{"iss":"issuer","aud":"api","exp":1000}"#;
+        assert!(serde_json::from_str::<Claims<MachineClaims>>(string).is_err());
+    }
+}
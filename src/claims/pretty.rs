@@ -0,0 +1,147 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use chrono::{SecondsFormat, TimeZone, Utc};
+use serde_json::Value;
+
+use crate::{token::Token, Claims, Scope};
+
+const REDACTED: &str = "<redacted>";
+const SCOPE_CLAIMS: [&str; 2] = ["scope", "scp"];
+
+fn timestamp(value: u64) -> String {
+    i64::try_from(value)
+        .ok()
+        .and_then(|value| Utc.timestamp_opt(value, 0).single())
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_else(|| value.to_string())
+}
+
+// Scopes are grouped as resource -> actions, keeping the order actions appear in
+// the token. Returns None when any scope is not in action:resource form.
+fn grouped_scopes(value: &Value) -> Option<BTreeMap<String, Vec<String>>> {
+    let scopes = match value {
+        Value::String(scopes) => scopes.split_whitespace().map(str::to_string).collect(),
+        Value::Array(scopes) => scopes
+            .iter()
+            .map(|scope| scope.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()?,
+        _ => return None,
+    };
+    let mut resources = BTreeMap::<String, Vec<String>>::new();
+    for scope in scopes {
+        let scope = scope.parse::<Scope>().ok()?;
+        resources
+            .entry(scope.resource)
+            .or_default()
+            .push(scope.action);
+    }
+    Some(resources)
+}
+
+fn write_custom_claim(out: &mut String, name: &str, value: &Value, redacted: &[&str]) {
+    if redacted.contains(&name) {
+        let _ = writeln!(out, "{name}: {REDACTED}");
+        return;
+    }
+    if SCOPE_CLAIMS.contains(&name) {
+        if let Some(resources) = grouped_scopes(value) {
+            let _ = writeln!(out, "scopes:");
+            for (resource, actions) in resources {
+                let _ = writeln!(out, "  {}: {}", resource, actions.join(", "));
+            }
+            return;
+        }
+    }
+    let _ = writeln!(out, "{name}: {value}");
+}
+
+impl<Extension> Claims<Extension>
+where
+    Extension: serde::Serialize,
+{
+    pub fn pretty(&self) -> String {
+        self.pretty_redacted(&[])
+    }
+
+    pub fn pretty_redacted(&self, redacted: &[&str]) -> String {
+        let mut out = String::new();
+        let sub = self.sub.as_ref().map(ToString::to_string);
+        let aud = self
+            .aud
+            .as_ref()
+            .map(|aud| aud.iter().cloned().collect::<Vec<_>>().join(", "));
+        let _ = writeln!(out, "iss: {}", self.iss);
+        let _ = writeln!(out, "sub: {}", sub.as_deref().unwrap_or("-"));
+        let _ = writeln!(out, "aud: {}", aud.as_deref().unwrap_or("-"));
+        let iat = self.iat.map(timestamp);
+        let _ = writeln!(out, "iat: {}", iat.as_deref().unwrap_or("-"));
+        let _ = writeln!(out, "exp: {}", timestamp(self.exp));
+
+        let mut custom = self
+            .additional
+            .clone()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+        if let Ok(Value::Object(extension)) = serde_json::to_value(&self.extension) {
+            custom.extend(extension);
+        }
+        for (name, value) in &custom {
+            write_custom_claim(&mut out, name, value, redacted);
+        }
+        out
+    }
+}
+
+impl<Extension> Token<Extension>
+where
+    Extension: serde::Serialize,
+{
+    pub fn describe(&self) -> String {
+        self.describe_redacted(&[])
+    }
+
+    pub fn describe_redacted(&self, redacted: &[&str]) -> String {
+        let header = self.header();
+        let mut out = String::new();
+        let _ = writeln!(out, "alg: {:?}", header.alg);
+        let _ = writeln!(out, "kid: {}", header.kid.as_deref().unwrap_or("-"));
+        out.push_str(&self.claims().pretty_redacted(redacted));
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::AuthorizationClaims;
+
+    #[test]
+    fn pretty_groups_scopes_and_redacts_claims() {
+        let string = r#"
+        {
+            "iss": "issuer",
+            "sub": "subject",
+            "aud": ["audience_a", "audience_b"],
+            "scope": "read:users create:users read:orders",
+            "email": "user@example.com",
+            "tenant": "acme",
+            "iat": 0,
+            "exp": 3600
+        }"#;
+        let claims: Claims<AuthorizationClaims> =
+            serde_json::from_str(string).expect("expected claims");
+        let expected = "\
+iss: issuer
+sub: subject
+aud: audience_a, audience_b
+iat: 1970-01-01T00:00:00Z
+exp: 1970-01-01T01:00:00Z
+email: <redacted>
+scopes:
+  orders: read
+  users: read, create
+tenant: \"acme\"
+";
+        assert_eq!(claims.pretty_redacted(&["email"]), expected);
+    }
+}
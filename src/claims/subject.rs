@@ -0,0 +1,61 @@
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+#[serde(transparent)]
+pub struct Subject(String);
+
+impl Subject {
+    pub fn new(subject: &str) -> Self {
+        Self(subject.to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn provider(&self) -> Option<&str> {
+        self.0.split_once('|').map(|(provider, _)| provider)
+    }
+
+    pub fn id(&self) -> &str {
+        self.0.split_once('|').map_or(&self.0, |(_, id)| id)
+    }
+}
+
+impl From<&str> for Subject {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for Subject {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl Display for Subject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn subject_can_be_split_into_provider_and_id() {
+        let subject = Subject::new("google-oauth2|1234");
+        assert_eq!(subject.provider(), Some("google-oauth2"));
+        assert_eq!(subject.id(), "1234");
+        assert_eq!(subject.to_string(), "google-oauth2|1234");
+    }
+
+    #[test]
+    fn subject_without_provider_is_its_own_id() {
+        let subject = Subject::new("1234");
+        assert_eq!(subject.provider(), None);
+        assert_eq!(subject.id(), "1234");
+    }
+}
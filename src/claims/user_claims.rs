@@ -1,8 +1,175 @@
+use super::{AuthorizationClaims, Claims, ClaimsValidator};
+use crate::FromClaims;
+
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct UserClaims {
     pub name: Option<String>,
+    #[serde(alias = "given_name")]
     pub first_name: Option<String>,
+    #[serde(alias = "family_name")]
     pub last_name: Option<String>,
     pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
     pub picture: Option<String>,
 }
+
+impl FromClaims for UserClaims {
+    type Error = serde_json::Error;
+
+    fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error> {
+        serde_json::from_value(claims.additional.clone().into())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum UserClaimsError {
+    #[error("email is missing")]
+    MissingEmail,
+    #[error("email {0} is not verified")]
+    UnverifiedEmail(String),
+    #[error("hosted domain {0:?} is not allowed")]
+    HostedDomainNotAllowed(Option<String>),
+}
+
+// Checks on the user claims of ID tokens, which are otherwise all optional.
+// Hosted domains are read from the hd claim that Google sets for Workspace
+// accounts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserClaimsPolicy {
+    require_email: bool,
+    require_verified_email: bool,
+    hosted_domains: Option<Vec<String>>,
+}
+
+impl UserClaimsPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn require_email(mut self, value: bool) -> Self {
+        self.require_email = value;
+        self
+    }
+
+    // Implies require_email.
+    pub fn require_verified_email(mut self, value: bool) -> Self {
+        self.require_verified_email = value;
+        self
+    }
+
+    pub fn hosted_domains(mut self, values: &[&str]) -> Self {
+        let values = values.iter().map(ToString::to_string).collect();
+        self.hosted_domains = Some(values);
+        self
+    }
+
+    pub fn check<Extension: serde::Serialize>(
+        &self,
+        claims: &Claims<Extension>,
+        user: &UserClaims,
+    ) -> Result<(), UserClaimsError> {
+        let email = user.email.as_ref();
+        if (self.require_email || self.require_verified_email) && email.is_none() {
+            return Err(UserClaimsError::MissingEmail);
+        }
+        if let Some(email) = email.filter(|_| self.require_verified_email) {
+            if user.email_verified != Some(true) {
+                return Err(UserClaimsError::UnverifiedEmail(email.clone()));
+            }
+        }
+        match &self.hosted_domains {
+            Some(hosted_domains) => check_hosted_domain(claims, hosted_domains),
+            None => Ok(()),
+        }
+    }
+}
+
+// Domain names are case-insensitive, so every check of the hd claim, including
+// the Google preset's, goes through here.
+pub(crate) fn check_hosted_domain<Extension: serde::Serialize>(
+    claims: &Claims<Extension>,
+    hosted_domains: &[String],
+) -> Result<(), UserClaimsError> {
+    let hd = claims.get_claim::<String>("hd").ok().flatten();
+    let allowed = hd.as_ref().is_some_and(|hd| {
+        hosted_domains
+            .iter()
+            .any(|domain| domain.eq_ignore_ascii_case(hd))
+    });
+    match allowed {
+        true => Ok(()),
+        false => Err(UserClaimsError::HostedDomainNotAllowed(hd)),
+    }
+}
+
+impl ClaimsValidator<UserClaims> for UserClaimsPolicy {
+    fn validate(&self, claims: &Claims<UserClaims>) -> Result<(), String> {
+        self.check(claims, &claims.extension)
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl ClaimsValidator<AuthorizationClaims> for UserClaimsPolicy {
+    fn validate(&self, claims: &Claims<AuthorizationClaims>) -> Result<(), String> {
+        let user = UserClaims::from_claims(claims).map_err(|e| e.to_string())?;
+        self.check(claims, &user).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn claims(user: serde_json::Value) -> Claims<UserClaims> {
+        let mut value = serde_json::json!({ "iss": "issuer", "exp": 1000 });
+        value
+            .as_object_mut()
+            .unwrap()
+            .extend(user.as_object().unwrap().clone());
+        serde_json::from_value(value).expect("expected claims")
+    }
+
+    fn check(
+        policy: &UserClaimsPolicy,
+        claims: &Claims<UserClaims>,
+    ) -> Result<(), UserClaimsError> {
+        policy.check(claims, &claims.extension)
+    }
+
+    #[test]
+    fn verified_email_is_required() {
+        let policy = UserClaimsPolicy::new().require_verified_email(true);
+        let verified =
+            claims(serde_json::json!({ "email": "a@example.com", "email_verified": true }));
+        assert_eq!(check(&policy, &verified), Ok(()));
+        let unverified = claims(serde_json::json!({ "email": "a@example.com" }));
+        assert_eq!(
+            check(&policy, &unverified),
+            Err(UserClaimsError::UnverifiedEmail(
+                "a@example.com".to_string()
+            ))
+        );
+        let missing = claims(serde_json::json!({}));
+        assert_eq!(check(&policy, &missing), Err(UserClaimsError::MissingEmail));
+    }
+
+    #[test]
+    fn hosted_domain_must_be_allowed() {
+        let policy = UserClaimsPolicy::new().hosted_domains(&["example.com"]);
+        let allowed = claims(serde_json::json!({ "hd": "Example.com" }));
+        assert_eq!(check(&policy, &allowed), Ok(()));
+        let other = claims(serde_json::json!({ "hd": "other.com" }));
+        assert_eq!(
+            check(&policy, &other),
+            Err(UserClaimsError::HostedDomainNotAllowed(Some(
+                "other.com".to_string()
+            )))
+        );
+        let missing = claims(serde_json::json!({}));
+        assert_eq!(
+            check(&policy, &missing),
+            Err(UserClaimsError::HostedDomainNotAllowed(None))
+        );
+    }
+}
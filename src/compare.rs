@@ -0,0 +1,47 @@
+use subtle::ConstantTimeEq;
+
+use crate::Scope;
+
+// Equality for token values checked against configuration or other secrets.
+// The time taken does not depend on where the inputs differ; only their
+// lengths can leak.
+pub(crate) fn secure_eq(a: &str, b: &str) -> bool {
+    a.as_bytes().ct_eq(b.as_bytes()).into()
+}
+
+// Compares against every candidate rather than stopping at the first match.
+pub(crate) fn secure_contains<'a>(
+    candidates: impl IntoIterator<Item = &'a str>,
+    value: &str,
+) -> bool {
+    candidates.into_iter().fold(false, |found, candidate| {
+        found | secure_eq(candidate, value)
+    })
+}
+
+pub(crate) fn secure_scope_eq(a: &Scope, b: &Scope) -> bool {
+    secure_eq(&a.action, &b.action) & secure_eq(&a.resource, &b.resource)
+}
+
+pub(crate) fn secure_scope_contains(scopes: &[Scope], scope: &Scope) -> bool {
+    scopes.iter().fold(false, |found, candidate| {
+        found | secure_scope_eq(candidate, scope)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn secure_comparisons_match_equality() {
+        assert!(secure_eq("issuer", "issuer"));
+        assert!(!secure_eq("issuer", "issuer/"));
+        assert!(!secure_eq("issuer", "issues"));
+        assert!(secure_contains(["a", "b"], "b"));
+        assert!(!secure_contains(Vec::<&str>::new(), "b"));
+        let scopes = vec![Scope::new("read", "users")];
+        assert!(secure_scope_contains(&scopes, &Scope::new("read", "users")));
+        assert!(!secure_scope_contains(&scopes, &Scope::new("read", "user")));
+    }
+}
@@ -0,0 +1,166 @@
+#[cfg(feature = "jwe")]
+use std::sync::Arc;
+use std::time::Duration;
+
+use jsonwebtoken::Algorithm;
+
+#[cfg(feature = "jwe")]
+use crate::DecryptionKeySet;
+#[cfg(feature = "paseto")]
+use crate::PasetoKeySet;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingKidPolicy {
+    #[default]
+    Reject,
+    TryAllKeys,
+    MatchAlgorithm,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+    algorithms: Vec<Algorithm>,
+    missing_kid_policy: MissingKidPolicy,
+    critical_extensions: Vec<String>,
+    leeway: Duration,
+    max_token_length: Option<usize>,
+    max_scopes: Option<usize>,
+    max_audiences: Option<usize>,
+    svid_issuer: Option<String>,
+    #[cfg(feature = "jwe")]
+    decryption_keys: Option<Arc<DecryptionKeySet>>,
+    #[cfg(feature = "paseto")]
+    paseto_keys: Option<PasetoKeySet>,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            algorithms: vec![
+                Algorithm::RS256,
+                Algorithm::RS384,
+                Algorithm::RS512,
+                Algorithm::PS256,
+                Algorithm::PS384,
+                Algorithm::PS512,
+                Algorithm::ES256,
+                Algorithm::ES384,
+                Algorithm::EdDSA,
+            ],
+            missing_kid_policy: MissingKidPolicy::default(),
+            critical_extensions: Vec::new(),
+            leeway: Duration::from_secs(60),
+            max_token_length: None,
+            max_scopes: None,
+            max_audiences: None,
+            svid_issuer: None,
+            #[cfg(feature = "jwe")]
+            decryption_keys: None,
+            #[cfg(feature = "paseto")]
+            paseto_keys: None,
+        }
+    }
+}
+
+impl DecodeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn algorithms(mut self, value: &[Algorithm]) -> Self {
+        self.algorithms = value.to_vec();
+        self
+    }
+
+    pub fn missing_kid_policy(mut self, value: MissingKidPolicy) -> Self {
+        self.missing_kid_policy = value;
+        self
+    }
+
+    pub fn handle_critical(mut self, name: &str) -> Self {
+        self.critical_extensions.push(name.to_string());
+        self
+    }
+
+    pub(crate) fn handles_critical(&self, name: &str) -> bool {
+        self.critical_extensions
+            .iter()
+            .any(|handled| handled == name)
+    }
+
+    // How long after exp a token is still accepted, to absorb clock skew.
+    pub fn leeway(mut self, value: Duration) -> Self {
+        self.leeway = value;
+        self
+    }
+
+    pub(crate) fn expiry_leeway(&self) -> Duration {
+        self.leeway
+    }
+
+    // Limits are checked before the token is parsed, so oversized tokens are
+    // rejected without deserializing their claims or verifying signatures.
+    pub fn max_token_length(mut self, value: usize) -> Self {
+        self.max_token_length = Some(value);
+        self
+    }
+
+    pub fn max_scopes(mut self, value: usize) -> Self {
+        self.max_scopes = Some(value);
+        self
+    }
+
+    pub fn max_audiences(mut self, value: usize) -> Self {
+        self.max_audiences = Some(value);
+        self
+    }
+
+    pub(crate) fn token_length_limit(&self) -> Option<usize> {
+        self.max_token_length
+    }
+
+    pub(crate) fn claim_count_limits(&self) -> (Option<usize>, Option<usize>) {
+        (self.max_scopes, self.max_audiences)
+    }
+
+    // Set by the SPIFFE preset only: JWT-SVIDs without iss are decoded as if
+    // issued by the trust domain.
+    pub(crate) fn svid_issuer(mut self, value: &str) -> Self {
+        self.svid_issuer = Some(value.to_string());
+        self
+    }
+
+    pub(crate) fn missing_issuer(&self) -> Option<&str> {
+        self.svid_issuer.as_deref()
+    }
+
+    #[cfg(feature = "jwe")]
+    pub fn decryption_keys(mut self, value: DecryptionKeySet) -> Self {
+        self.decryption_keys = Some(Arc::new(value));
+        self
+    }
+
+    #[cfg(feature = "jwe")]
+    pub(crate) fn decryption_key_set(&self) -> Option<&DecryptionKeySet> {
+        self.decryption_keys.as_deref()
+    }
+
+    #[cfg(feature = "paseto")]
+    pub fn paseto_keys(mut self, value: PasetoKeySet) -> Self {
+        self.paseto_keys = Some(value);
+        self
+    }
+
+    #[cfg(feature = "paseto")]
+    pub(crate) fn paseto_key_set(&self) -> Option<&PasetoKeySet> {
+        self.paseto_keys.as_ref()
+    }
+
+    pub(crate) fn kid_policy(&self) -> MissingKidPolicy {
+        self.missing_kid_policy
+    }
+
+    pub fn allows(&self, algorithm: Algorithm) -> bool {
+        self.algorithms.contains(&algorithm)
+    }
+}
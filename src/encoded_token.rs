@@ -1,13 +1,26 @@
 use std::{
+    borrow::Cow,
     fmt::{Display, Formatter},
     marker::PhantomData,
+    sync::Arc,
 };
 
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use jsonwebtoken::{
-    decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+    crypto, decode, decode_header,
+    errors::ErrorKind,
+    get_current_timestamp,
+    jwk::{AlgorithmParameters, Jwk, JwkSet},
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
 };
+use serde::Deserialize;
+use serde_json::{Map, Value};
 
-use crate::{token::Token, Claims};
+use crate::{token::Token, Claims, DecodeOptions, MissingKidPolicy, ScopeError};
+
+const REGISTERED_HEADER_PARAMETERS: [&str; 11] = [
+    "alg", "jku", "jwk", "kid", "x5u", "x5c", "x5t", "x5t#S256", "typ", "cty", "crit",
+];
 
 #[derive(Debug, thiserror::Error)]
 pub enum EncodedTokenError {
@@ -17,14 +30,270 @@ pub enum EncodedTokenError {
     NoJWKError,
     #[error("JWT does not provide a valid key id")]
     NoKID,
+    #[error(transparent)]
+    ScopeError(#[from] ScopeError),
+    #[error("algorithm {0:?} is not allowed")]
+    DisallowedAlgorithm(Algorithm),
+    #[error("token algorithm {0:?} does not match JWK algorithm {1:?}")]
+    AlgorithmMismatch(Algorithm, Algorithm),
+    #[error("crit header is invalid: {0}")]
+    InvalidCritical(String),
+    #[error("critical header extension {0} is not handled")]
+    UnhandledCritical(String),
+    #[error("token header is invalid: {0}")]
+    InvalidHeader(String),
+    #[error("token exceeds limit: {0}")]
+    LimitExceeded(String),
+    #[cfg(feature = "jwe")]
+    #[error(transparent)]
+    JweError(#[from] crate::JweError),
+    #[cfg(feature = "paseto")]
+    #[error(transparent)]
+    PasetoError(#[from] crate::PasetoError),
+}
+
+// Header fields readable before verification, for routing decisions such as
+// which issuer's keys to use or whether the token is a JWE. alg is kept as a
+// string since JWE algorithms are not JWS algorithms.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+pub struct PeekedHeader {
+    pub alg: Option<String>,
+    pub kid: Option<String>,
+    pub typ: Option<String>,
+    pub cty: Option<String>,
+    pub enc: Option<String>,
+}
+
+impl PeekedHeader {
+    pub fn is_encrypted(&self) -> bool {
+        self.enc.is_some()
+    }
+}
+
+fn header_object(encoded: &str) -> Result<Map<String, Value>, EncodedTokenError> {
+    let invalid = |message: &str| EncodedTokenError::InvalidHeader(message.to_string());
+    let segment = encoded.split('.').next().unwrap_or_default();
+    let bytes = URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|_| invalid("header is not base64url"))?;
+    serde_json::from_slice(&bytes).map_err(|_| invalid("header is not a JSON object"))
+}
+
+// Counts scope and aud entries without allocating them, so limits can be
+// enforced before the claims are deserialized.
+#[derive(Debug, Default, serde::Deserialize)]
+struct ClaimCounts {
+    #[serde(default, deserialize_with = "count_entries")]
+    scope: usize,
+    #[serde(default, deserialize_with = "count_entries")]
+    scp: usize,
+    #[serde(default, rename = "aud", deserialize_with = "count_entries")]
+    audiences: usize,
+}
+
+fn count_entries<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct CountVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for CountVisitor {
+        type Value = usize;
+
+        fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+            write!(f, "a string or an array")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<usize, E> {
+            Ok(value.split_whitespace().count())
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<usize, A::Error>
+        where
+            A: serde::de::SeqAccess<'de>,
+        {
+            let mut count = 0;
+            while seq.next_element::<serde::de::IgnoredAny>()?.is_some() {
+                count += 1;
+            }
+            Ok(count)
+        }
+    }
+
+    deserializer.deserialize_any(CountVisitor)
+}
+
+fn token_error(kind: ErrorKind) -> EncodedTokenError {
+    jsonwebtoken::errors::Error::from(kind).into()
+}
+
+// A compact JWS split and decoded once, so the header and payload are not
+// parsed again for each check and candidate key.
+struct ParsedJws<'a> {
+    header: Header,
+    header_object: Map<String, Value>,
+    payload: Vec<u8>,
+    message: &'a str,
+    signature: &'a str,
+}
+
+impl<'a> ParsedJws<'a> {
+    fn parse(encoded: &'a str) -> Result<Self, EncodedTokenError> {
+        let malformed = || token_error(ErrorKind::InvalidToken);
+        let (message, signature) = encoded.rsplit_once('.').ok_or_else(malformed)?;
+        let (header, payload) = message.rsplit_once('.').ok_or_else(malformed)?;
+        let header = URL_SAFE_NO_PAD.decode(header).map_err(|_| malformed())?;
+        let header =
+            serde_json::from_slice::<Value>(&header).map_err(jsonwebtoken::errors::Error::from)?;
+        let header_object = header.as_object().cloned().ok_or_else(malformed)?;
+        let header = Header::deserialize(&header).map_err(jsonwebtoken::errors::Error::from)?;
+        let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| malformed())?;
+        Ok(Self {
+            header,
+            header_object,
+            payload,
+            message,
+            signature,
+        })
+    }
+
+    fn check_critical(&self, options: &DecodeOptions) -> Result<(), EncodedTokenError> {
+        let invalid = |message: &str| EncodedTokenError::InvalidCritical(message.to_string());
+        let Some(crit) = self.header_object.get("crit") else {
+            return Ok(());
+        };
+        let names = crit
+            .as_array()
+            .filter(|names| !names.is_empty())
+            .ok_or_else(|| invalid("expected a non-empty array"))?;
+        for name in names {
+            let name = name.as_str().ok_or_else(|| invalid("expected strings"))?;
+            if REGISTERED_HEADER_PARAMETERS.contains(&name) {
+                return Err(invalid(&format!("{name} is a registered header parameter")));
+            }
+            if !self.header_object.contains_key(name) {
+                return Err(invalid(&format!("{name} is missing from the header")));
+            }
+            if !options.handles_critical(name) {
+                return Err(EncodedTokenError::UnhandledCritical(name.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_with_key<K: VerificationKey>(&self, key: &K) -> Result<(), EncodedTokenError> {
+        let alg = self.header.alg;
+        if let Some(key_alg) = key.algorithm() {
+            if key_alg != alg {
+                return Err(EncodedTokenError::AlgorithmMismatch(alg, key_alg));
+            }
+        }
+        // crypto::verify panics when the key is of another family than alg.
+        if key.family() != KeyFamily::of(alg) {
+            return Err(token_error(ErrorKind::InvalidAlgorithm));
+        }
+        let decoding_key = key.decoding_key()?;
+        match crypto::verify(self.signature, self.message.as_bytes(), &decoding_key, alg)? {
+            true => Ok(()),
+            false => Err(token_error(ErrorKind::InvalidSignature)),
+        }
+    }
+}
+
+// Payloads that do not parse are left for decoding to reject.
+fn check_claim_counts(payload: &[u8], options: &DecodeOptions) -> Result<(), EncodedTokenError> {
+    let (max_scopes, max_audiences) = options.claim_count_limits();
+    if max_scopes.is_none() && max_audiences.is_none() {
+        return Ok(());
+    }
+    let counts = serde_json::from_slice::<ClaimCounts>(payload).unwrap_or_default();
+    check_limit("scopes", counts.scope + counts.scp, max_scopes)?;
+    check_limit("audiences", counts.audiences, max_audiences)
+}
+
+fn check_limit(name: &str, count: usize, limit: Option<usize>) -> Result<(), EncodedTokenError> {
+    match limit {
+        Some(limit) if count > limit => Err(EncodedTokenError::LimitExceeded(format!(
+            "{count} {name}, at most {limit} allowed"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyFamily {
+    Hmac,
+    Rsa,
+    Ec,
+    Ed,
 }
 
-#[derive(Debug, Clone)]
+impl KeyFamily {
+    fn of(alg: Algorithm) -> Self {
+        match alg {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => KeyFamily::Hmac,
+            Algorithm::ES256 | Algorithm::ES384 => KeyFamily::Ec,
+            Algorithm::EdDSA => KeyFamily::Ed,
+            _ => KeyFamily::Rsa,
+        }
+    }
+
+    pub(crate) fn of_jwk(jwk: &Jwk) -> Self {
+        match jwk.algorithm {
+            AlgorithmParameters::OctetKey(_) => KeyFamily::Hmac,
+            AlgorithmParameters::RSA(_) => KeyFamily::Rsa,
+            AlgorithmParameters::EllipticCurve(_) => KeyFamily::Ec,
+            AlgorithmParameters::OctetKeyPair(_) => KeyFamily::Ed,
+        }
+    }
+}
+
+// A key tokens are verified with: a JWK, or a key whose DecodingKey is built
+// once and reused across tokens.
+pub(crate) trait VerificationKey {
+    fn kid(&self) -> Option<&str>;
+
+    fn algorithm(&self) -> Option<Algorithm>;
+
+    fn family(&self) -> KeyFamily;
+
+    fn decoding_key(&self) -> Result<Cow<'_, DecodingKey>, EncodedTokenError>;
+}
+
+impl VerificationKey for Jwk {
+    fn kid(&self) -> Option<&str> {
+        self.common.key_id.as_deref()
+    }
+
+    fn algorithm(&self) -> Option<Algorithm> {
+        self.common.algorithm
+    }
+
+    fn family(&self) -> KeyFamily {
+        KeyFamily::of_jwk(self)
+    }
+
+    fn decoding_key(&self) -> Result<Cow<'_, DecodingKey>, EncodedTokenError> {
+        Ok(Cow::Owned(DecodingKey::from_jwk(self)?))
+    }
+}
+
+#[derive(Debug)]
 pub struct EncodedToken<Extension> {
     encoded: String,
     phantom_data: PhantomData<Extension>,
 }
 
+// Derived Clone would require Extension: Clone, which claims types need not be.
+impl<Extension> Clone for EncodedToken<Extension> {
+    fn clone(&self) -> Self {
+        Self {
+            encoded: self.encoded.clone(),
+            phantom_data: Default::default(),
+        }
+    }
+}
+
 impl<Extension> From<&str> for EncodedToken<Extension> {
     fn from(encoded: &str) -> Self {
         let split = encoded.split("Bearer ").collect::<Vec<_>>();
@@ -45,6 +314,14 @@ impl<Extension> From<String> for EncodedToken<Extension> {
     }
 }
 
+impl<Extension> EncodedToken<Extension> {
+    pub fn peek_header(&self) -> Result<PeekedHeader, EncodedTokenError> {
+        let header = header_object(&self.encoded)?;
+        serde_json::from_value(Value::Object(header))
+            .map_err(|e| EncodedTokenError::InvalidHeader(e.to_string()))
+    }
+}
+
 impl<Extension> Display for EncodedToken<Extension> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.encoded)
@@ -78,19 +355,120 @@ where
         Ok(header)
     }
 
-    fn kid(&self) -> Result<String, EncodedTokenError> {
-        let kid = self.header()?.kid.ok_or(EncodedTokenError::NoKID)?;
-        Ok(kid)
+    pub fn decode(self, jwk_set: &JwkSet) -> Result<Token<Extension>, EncodedTokenError> {
+        self.decode_with_options(jwk_set, &DecodeOptions::default())
     }
 
-    pub fn decode(self, jwk_set: &JwkSet) -> Result<Token<Extension>, EncodedTokenError> {
-        let kid = self.kid()?;
-        let jwk = jwk_set.find(&kid).ok_or(EncodedTokenError::NoJWKError)?;
-        let decoding_key = DecodingKey::from_jwk(jwk)?;
-        let validation = Validation::new(Algorithm::RS256);
+    pub fn decode_with_options(
+        self,
+        jwk_set: &JwkSet,
+        options: &DecodeOptions,
+    ) -> Result<Token<Extension>, EncodedTokenError> {
+        self.decode_with_keys(&jwk_set.keys, options)
+    }
+
+    pub(crate) fn decode_with_keys<K: VerificationKey>(
+        self,
+        keys: &[K],
+        options: &DecodeOptions,
+    ) -> Result<Token<Extension>, EncodedTokenError> {
+        let raw = Arc::from(self.encoded());
+        let token = self.decode_encoded(keys, options)?;
+        Ok(token.with_raw(raw))
+    }
+
+    fn decode_encoded<K: VerificationKey>(
+        self,
+        keys: &[K],
+        options: &DecodeOptions,
+    ) -> Result<Token<Extension>, EncodedTokenError> {
+        check_limit("bytes", self.encoded().len(), options.token_length_limit())?;
+        #[cfg(feature = "paseto")]
+        if let Some(keys) = options.paseto_key_set() {
+            if crate::EncodedPaseto::<Extension>::is_paseto(self.encoded()) {
+                let paseto: crate::EncodedPaseto<Extension> = self.encoded.into();
+                check_claim_counts(&paseto.untrusted_payload()?, options)?;
+                return Ok(paseto.decode_with_options(keys, options)?);
+            }
+        }
+        #[cfg(feature = "jwe")]
+        if self.is_encrypted() {
+            let decryption_keys = options
+                .decryption_key_set()
+                .ok_or(crate::JweError::NoDecryptionKeys)?;
+            return self.decrypt(decryption_keys)?.decode_encoded(keys, options);
+        }
+        let parsed = ParsedJws::parse(self.encoded())?;
+        check_claim_counts(&parsed.payload, options)?;
+        parsed.check_critical(options)?;
+        let alg = parsed.header.alg;
+        if !options.allows(alg) {
+            return Err(EncodedTokenError::DisallowedAlgorithm(alg));
+        }
+        let candidates = match parsed.header.kid.as_deref() {
+            Some(kid) => {
+                let key = keys.iter().find(|key| key.kid() == Some(kid));
+                vec![key.ok_or(EncodedTokenError::NoJWKError)?]
+            }
+            None => missing_kid_candidates(keys, alg, options.kid_policy())?,
+        };
+        let mut verified = Err(EncodedTokenError::NoJWKError);
+        for key in candidates {
+            verified = parsed.verify_with_key(key);
+            if verified.is_ok() {
+                break;
+            }
+        }
+        verified?;
+        // Expiry is checked against the parsed claims so that timestamps
+        // jsonwebtoken cannot read, like numeric strings, are still validated.
+        let claims = match options.missing_issuer() {
+            Some(issuer) => crate::providers::spiffe::svid_claims(&parsed.payload, issuer),
+            None => serde_json::from_slice::<Claims<Extension>>(&parsed.payload),
+        }
+        .map_err(jsonwebtoken::errors::Error::from)?;
+        let leeway = options.expiry_leeway().as_secs();
+        if claims.exp < get_current_timestamp().saturating_sub(leeway) {
+            return Err(token_error(ErrorKind::ExpiredSignature));
+        }
+        Ok(Token::new(parsed.header, claims))
+    }
+
+    // Parses the header and claims WITHOUT checking the signature, expiry or any
+    // other claim. Anyone can forge such a token, so the result must never be
+    // used for authentication or authorization; it exists for error logs and
+    // tooling that inspects expired or foreign tokens.
+    pub fn decode_insecure(&self) -> Result<Token<Extension>, EncodedTokenError> {
+        let header = self.header()?;
+        let mut validation = Validation::new(header.alg);
+        validation.insecure_disable_signature_validation();
+        validation.validate_exp = false;
+        validation.required_spec_claims.clear();
+        let decoding_key = DecodingKey::from_secret(&[]);
         let decoded_token =
             decode::<Claims<Extension>>(self.encoded(), &decoding_key, &validation)?;
         let token = Token::new(decoded_token.header, decoded_token.claims);
-        Ok(token)
+        Ok(token.with_raw(Arc::from(self.encoded())))
+    }
+}
+
+fn missing_kid_candidates<K: VerificationKey>(
+    keys: &[K],
+    alg: Algorithm,
+    policy: MissingKidPolicy,
+) -> Result<Vec<&K>, EncodedTokenError> {
+    match policy {
+        MissingKidPolicy::Reject => Err(EncodedTokenError::NoKID),
+        MissingKidPolicy::TryAllKeys => Ok(keys.iter().collect()),
+        MissingKidPolicy::MatchAlgorithm => {
+            let matching = keys
+                .iter()
+                .filter(|key| key.algorithm() == Some(alg))
+                .collect::<Vec<_>>();
+            match matching.len() {
+                1 => Ok(matching),
+                _ => Err(EncodedTokenError::NoJWKError),
+            }
+        }
     }
 }
@@ -0,0 +1,99 @@
+use std::time::{Duration, Instant};
+
+use actix_web::{dev::ServiceRequest, HttpMessage};
+use tokio::sync::broadcast;
+
+use crate::AccessToken;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthEventKind {
+    Authorized,
+    Denied,
+    Unauthenticated,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthEvent {
+    pub kind: AuthEventKind,
+    pub sub: Option<String>,
+    pub iss: Option<String>,
+    pub route: String,
+    pub latency: Duration,
+    pub reason: Option<String>,
+}
+
+impl AuthEvent {
+    // Route is the matched resource pattern when there is one, so events for
+    // "/users/{id}" group together.
+    pub(crate) fn new(
+        kind: AuthEventKind,
+        req: &ServiceRequest,
+        reason: Option<String>,
+        started: Instant,
+    ) -> Self {
+        let extensions = req.extensions();
+        let claims = extensions.get::<AccessToken>().map(AccessToken::claims);
+        Self {
+            kind,
+            sub: claims
+                .and_then(|claims| claims.sub.as_ref())
+                .map(ToString::to_string),
+            iss: claims.map(|claims| claims.iss.clone()),
+            route: req
+                .match_pattern()
+                .unwrap_or_else(|| req.path().to_string()),
+            latency: started.elapsed(),
+            reason,
+        }
+    }
+}
+
+// Broadcasts authentication and authorization outcomes to any number of
+// subscribers. Clones share the channel, so one instance can be handed to the
+// factories of every worker. Events are dropped when nobody is subscribed, and
+// slow subscribers miss the oldest events once `capacity` is exceeded.
+#[derive(Debug, Clone)]
+pub struct AuthEvents {
+    sender: broadcast::Sender<AuthEvent>,
+}
+
+impl AuthEvents {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<AuthEvent> {
+        self.sender.subscribe()
+    }
+
+    pub(crate) fn emit(&self, event: AuthEvent) {
+        let _ = self.sender.send(event);
+    }
+}
+
+impl Default for AuthEvents {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn subscribers_receive_emitted_events() {
+        let events = AuthEvents::new(8);
+        let mut receiver = events.subscribe();
+        let req = TestRequest::get().uri("/projects").to_srv_request();
+        let event = AuthEvent::new(AuthEventKind::Unauthenticated, &req, None, Instant::now());
+        events.emit(event);
+        let event = receiver.recv().await.expect("expected event");
+        assert_eq!(event.kind, AuthEventKind::Unauthenticated);
+        assert_eq!(event.route, "/projects");
+        assert_eq!(event.sub, None);
+    }
+}
@@ -0,0 +1,119 @@
+use async_graphql::{Context, Error, ErrorExtensions, Guard, Result};
+
+use crate::{
+    middleware::{verify, Authorization, AuthorizationError},
+    AuthorizationClaims, Claims, Scope,
+};
+
+// Handlers hand the extracted Authorization to the schema with
+// `request.data(authorization)`, after which resolvers read it from the context.
+pub trait GraphQLAuthorization {
+    fn authorization(&self) -> Option<&Authorization>;
+
+    fn claims(&self) -> Option<Claims<AuthorizationClaims>> {
+        self.authorization()?.claims()
+    }
+}
+
+impl GraphQLAuthorization for Context<'_> {
+    fn authorization(&self) -> Option<&Authorization> {
+        self.data_opt::<Authorization>()
+    }
+}
+
+impl ErrorExtensions for AuthorizationError {
+    fn extend(&self) -> Error {
+        Error::new(self.to_string())
+    }
+}
+
+fn graphql_error(e: AuthorizationError) -> Error {
+    let code = match e {
+        AuthorizationError::Unauthorized => "UNAUTHENTICATED",
+        _ => "FORBIDDEN",
+    };
+    e.extend_with(|_, extensions| extensions.set("code", code))
+}
+
+// Field guard sharing verify() with REST handlers:
+// `#[graphql(guard = "RequireScope::new(\"read:users\")")]`.
+#[derive(Debug, Clone)]
+pub struct RequireScope {
+    scope: String,
+}
+
+impl RequireScope {
+    pub fn new(scope: &str) -> Self {
+        Self {
+            scope: scope.to_string(),
+        }
+    }
+}
+
+impl Guard for RequireScope {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        let scope = self
+            .scope
+            .parse::<Scope>()
+            .map_err(|e| Error::new(e.to_string()))?;
+        let authorization = ctx
+            .authorization()
+            .ok_or_else(|| graphql_error(AuthorizationError::Unauthorized))?;
+        verify(authorization, &scope.resource, &scope.action).map_err(graphql_error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema};
+    use jsonwebtoken::Header;
+
+    use super::*;
+    use crate::token::Token;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        #[graphql(guard = "RequireScope::new(\"read:users\")")]
+        async fn users(&self, ctx: &Context<'_>) -> Option<String> {
+            ctx.claims().map(|claims| claims.iss)
+        }
+    }
+
+    fn authorization(scopes: Vec<Scope>) -> Authorization {
+        let extension = AuthorizationClaims { scopes };
+        let claims = Claims::new("issuer", "subject", &[], Duration::from_secs(60), extension);
+        Authorization::new(Some(Token::new(Header::default(), claims)))
+    }
+
+    async fn execute(authorization: Authorization) -> async_graphql::Response {
+        let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+        let request = Request::new("{ users }").data(authorization);
+        schema.execute(request).await
+    }
+
+    #[actix_web::test]
+    async fn guard_allows_tokens_with_scope() {
+        let response = execute(authorization(vec![Scope::new("read", "users")])).await;
+        assert!(response.errors.is_empty());
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            serde_json::json!({ "users": "issuer" })
+        );
+    }
+
+    #[actix_web::test]
+    async fn guard_rejects_missing_scope_and_token() {
+        let response = execute(authorization(vec![Scope::new("read", "projects")])).await;
+        assert_eq!(response.errors.len(), 1);
+        let response = execute(Authorization::new(None)).await;
+        let extensions = response.errors[0].extensions.as_ref().unwrap();
+        assert_eq!(
+            extensions.get("code"),
+            Some(&async_graphql::Value::from("UNAUTHENTICATED"))
+        );
+    }
+}
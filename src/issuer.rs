@@ -1,3 +1,256 @@
+use std::{collections::HashSet, sync::Arc};
+
+use crate::{
+    compare::{secure_contains, secure_eq},
+    ScopeFormat,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeySetFormat {
+    #[default]
+    Jwks,
+    X509Certificates,
+}
+
 pub trait Issuer {
     fn url(&self) -> String;
+
+    fn matches(&self, iss: &str) -> bool {
+        secure_eq(iss, &self.url())
+    }
+
+    fn jwks_url(&self) -> String {
+        format!("{}/.well-known/jwks.json", self.url())
+    }
+
+    fn key_set_format(&self) -> KeySetFormat {
+        KeySetFormat::Jwks
+    }
+
+    // The scope format downstream consumers of this issuer's tokens expect, for
+    // use with EncodedAccessToken::new_with_scope_format.
+    fn scope_format(&self) -> ScopeFormat {
+        ScopeFormat::SpaceSeparated
+    }
+}
+
+// Issuer chosen at runtime, e.g. from configuration. Use it as the type
+// parameter of every factory, such as `IssuerFactory::<DynIssuer>::new(issuer)`.
+// A Box<dyn Issuer> converts with `.into()`.
+pub type DynIssuer = Arc<dyn Issuer>;
+
+macro_rules! delegate_issuer {
+    ($pointer:ident) => {
+        impl<T: Issuer + ?Sized> Issuer for $pointer<T> {
+            fn url(&self) -> String {
+                (**self).url()
+            }
+
+            fn matches(&self, iss: &str) -> bool {
+                (**self).matches(iss)
+            }
+
+            fn jwks_url(&self) -> String {
+                (**self).jwks_url()
+            }
+
+            fn key_set_format(&self) -> KeySetFormat {
+                (**self).key_set_format()
+            }
+
+            fn scope_format(&self) -> ScopeFormat {
+                (**self).scope_format()
+            }
+        }
+    };
+}
+
+delegate_issuer!(Arc);
+delegate_issuer!(Box);
+
+// Lowercases the scheme and host and drops trailing slashes, which IdPs and
+// configuration disagree on. The path keeps its case.
+pub(crate) fn normalize_issuer(iss: &str) -> String {
+    let iss = iss.trim_end_matches('/');
+    let Some((scheme, rest)) = iss.split_once("://") else {
+        return iss.to_string();
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+    format!(
+        "{}://{}{}",
+        scheme.to_ascii_lowercase(),
+        authority.to_ascii_lowercase(),
+        path
+    )
+}
+
+// Normalized forms of iss to retry an issuer's own matching with, one with and
+// one without a trailing slash, so custom `matches` implementations benefit too.
+pub(crate) fn issuer_variants(iss: &str) -> [String; 2] {
+    let normalized = normalize_issuer(iss);
+    let slashed = format!("{normalized}/");
+    [normalized, slashed]
+}
+
+const TENANT_PLACEHOLDER: &str = "{tenantid}";
+
+// Accepts the issuers of every tenant of a multi-tenant IdP, e.g. as the type
+// parameter of the factories. Such IdPs publish one key set for all tenants,
+// which is named with jwks_url.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IssuerTemplate {
+    template: String,
+    allowed_tenants: Option<HashSet<String>>,
+    jwks_url: Option<String>,
+}
+
+impl IssuerTemplate {
+    pub fn new(template: &str) -> Self {
+        Self {
+            template: template.to_string(),
+            allowed_tenants: None,
+            jwks_url: None,
+        }
+    }
+
+    pub fn jwks_url(mut self, value: &str) -> Self {
+        self.jwks_url = Some(value.to_string());
+        self
+    }
+
+    pub fn allowed_tenants(mut self, tenants: &[&str]) -> Self {
+        let tenants = tenants.iter().map(|tenant| tenant.to_string()).collect();
+        self.allowed_tenants = Some(tenants);
+        self
+    }
+
+    pub fn url(&self, tenant: &str) -> String {
+        self.template.replace(TENANT_PLACEHOLDER, tenant)
+    }
+
+    pub fn tenant<'a>(&self, iss: &'a str) -> Option<&'a str> {
+        let (prefix, suffix) = self.template.split_once(TENANT_PLACEHOLDER)?;
+        let tenant = iss.strip_prefix(prefix)?.strip_suffix(suffix)?;
+        let valid = !tenant.is_empty() && !tenant.contains('/');
+        valid.then_some(tenant)
+    }
+
+    pub fn matches(&self, iss: &str) -> bool {
+        if !self.template.contains(TENANT_PLACEHOLDER) {
+            return secure_eq(iss, &self.template);
+        }
+        match (self.tenant(iss), &self.allowed_tenants) {
+            (Some(tenant), Some(allowed_tenants)) => {
+                secure_contains(allowed_tenants.iter().map(String::as_str), tenant)
+            }
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+impl Issuer for IssuerTemplate {
+    fn url(&self) -> String {
+        self.template.clone()
+    }
+
+    fn matches(&self, iss: &str) -> bool {
+        IssuerTemplate::matches(self, iss)
+    }
+
+    fn jwks_url(&self) -> String {
+        match &self.jwks_url {
+            Some(jwks_url) => jwks_url.clone(),
+            None => format!("{}/.well-known/jwks.json", self.template),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalization_ignores_case_and_trailing_slash() {
+        assert_eq!(
+            normalize_issuer("HTTPS://Tenant.Auth0.com/"),
+            "https://tenant.auth0.com"
+        );
+        assert_eq!(
+            normalize_issuer("https://Login.example.com/Tenant/v2.0/"),
+            "https://login.example.com/Tenant/v2.0"
+        );
+        assert_eq!(
+            normalize_issuer("accounts.google.com"),
+            "accounts.google.com"
+        );
+    }
+
+    struct CustomIssuer;
+
+    impl Issuer for CustomIssuer {
+        fn url(&self) -> String {
+            "https://issuer.example.com".to_string()
+        }
+
+        fn jwks_url(&self) -> String {
+            "https://keys.example.com/jwks".to_string()
+        }
+    }
+
+    #[test]
+    fn dyn_issuer_delegates_to_the_configured_issuer() {
+        let boxed: Box<dyn Issuer> = Box::new(CustomIssuer);
+        let issuer: DynIssuer = boxed.into();
+        assert_eq!(issuer.jwks_url(), "https://keys.example.com/jwks");
+        assert!(issuer.matches("https://issuer.example.com"));
+        assert!(!issuer.matches("https://other.example.com"));
+    }
+
+    const AZURE_TEMPLATE: &str = "https://login.microsoftonline.com/{tenantid}/v2.0";
+
+    #[test]
+    fn template_matches_any_tenant_without_allowlist() {
+        let template = IssuerTemplate::new(AZURE_TEMPLATE);
+        let iss = "https://login.microsoftonline.com/9188040d-6c67-4c5b/v2.0";
+        assert!(template.matches(iss));
+        assert_eq!(template.tenant(iss), Some("9188040d-6c67-4c5b"));
+    }
+
+    #[test]
+    fn template_only_matches_allowed_tenants() {
+        let template = IssuerTemplate::new(AZURE_TEMPLATE).allowed_tenants(&["tenant-a"]);
+        assert!(template.matches("https://login.microsoftonline.com/tenant-a/v2.0"));
+        assert!(!template.matches("https://login.microsoftonline.com/tenant-b/v2.0"));
+    }
+
+    #[test]
+    fn template_rejects_other_issuers() {
+        let template = IssuerTemplate::new(AZURE_TEMPLATE);
+        assert!(!template.matches("https://login.microsoftonline.com//v2.0"));
+        assert!(!template.matches("https://sts.windows.net/tenant-a/"));
+        assert!(!template.matches("https://login.microsoftonline.com/a/b/v2.0"));
+    }
+
+    #[test]
+    fn template_is_an_issuer_with_a_shared_key_set() {
+        let jwks_url = "https://login.microsoftonline.com/common/discovery/v2.0/keys";
+        let template = IssuerTemplate::new(AZURE_TEMPLATE)
+            .allowed_tenants(&["tenant-a"])
+            .jwks_url(jwks_url);
+        let issuer: DynIssuer = Arc::new(template);
+        assert!(issuer.matches("https://login.microsoftonline.com/tenant-a/v2.0"));
+        assert!(!issuer.matches("https://login.microsoftonline.com/tenant-b/v2.0"));
+        assert_eq!(issuer.jwks_url(), jwks_url);
+    }
+
+    #[test]
+    fn template_without_placeholder_matches_exactly() {
+        let template = IssuerTemplate::new("https://issuer.example.com");
+        assert!(template.matches("https://issuer.example.com"));
+        assert!(!template.matches("https://issuer.example.com/"));
+    }
 }
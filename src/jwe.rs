@@ -0,0 +1,249 @@
+use std::fmt::{Debug, Formatter};
+
+use josekit::{
+    jwe::{
+        self, JweDecrypter, JweHeader, A128KW, A256KW, ECDH_ES, ECDH_ES_A128KW, ECDH_ES_A256KW,
+        RSA_OAEP, RSA_OAEP_256,
+    },
+    jwk::{Jwk, JwkSet},
+    JoseError,
+};
+
+use crate::{token::Token, DecodeOptions, EncodedToken, EncodedTokenError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum JweError {
+    #[error("unable to decrypt token: {0}")]
+    DecryptError(String),
+    #[error("decrypted payload is not a compact JWT")]
+    InvalidPayload,
+    #[error("encrypted token does not declare a nested JWT content type")]
+    NotNested,
+    #[error("no decryption keys configured for encrypted token")]
+    NoDecryptionKeys,
+    #[error("unsupported decryption key algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("invalid decryption key: {0}")]
+    InvalidKey(String),
+}
+
+struct DecryptionKey {
+    kid: Option<String>,
+    decrypter: Box<dyn JweDecrypter>,
+}
+
+#[derive(Default)]
+pub struct DecryptionKeySet {
+    keys: Vec<DecryptionKey>,
+}
+
+impl Debug for DecryptionKeySet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let kids = self.keys.iter().map(|key| &key.kid).collect::<Vec<_>>();
+        f.debug_struct("DecryptionKeySet")
+            .field("keys", &kids)
+            .finish()
+    }
+}
+
+fn decrypter_from_jwk(jwk: &Jwk) -> Result<Box<dyn JweDecrypter>, JweError> {
+    let algorithm = jwk.algorithm().unwrap_or_default();
+    let invalid_key = |e: JoseError| JweError::InvalidKey(e.to_string());
+    let decrypter: Box<dyn JweDecrypter> = match algorithm {
+        "RSA-OAEP" => Box::new(RSA_OAEP.decrypter_from_jwk(jwk).map_err(invalid_key)?),
+        "RSA-OAEP-256" => Box::new(RSA_OAEP_256.decrypter_from_jwk(jwk).map_err(invalid_key)?),
+        "ECDH-ES" => Box::new(ECDH_ES.decrypter_from_jwk(jwk).map_err(invalid_key)?),
+        "ECDH-ES+A128KW" => Box::new(
+            ECDH_ES_A128KW
+                .decrypter_from_jwk(jwk)
+                .map_err(invalid_key)?,
+        ),
+        "ECDH-ES+A256KW" => Box::new(
+            ECDH_ES_A256KW
+                .decrypter_from_jwk(jwk)
+                .map_err(invalid_key)?,
+        ),
+        "A128KW" => Box::new(A128KW.decrypter_from_jwk(jwk).map_err(invalid_key)?),
+        "A256KW" => Box::new(A256KW.decrypter_from_jwk(jwk).map_err(invalid_key)?),
+        _ => return Err(JweError::UnsupportedAlgorithm(algorithm.to_string())),
+    };
+    Ok(decrypter)
+}
+
+impl DecryptionKeySet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn key(mut self, kid: Option<&str>, decrypter: impl JweDecrypter + 'static) -> Self {
+        self.keys.push(DecryptionKey {
+            kid: kid.map(ToString::to_string),
+            decrypter: Box::new(decrypter),
+        });
+        self
+    }
+
+    pub fn from_jwk_set(jwk_set: &JwkSet) -> Result<Self, JweError> {
+        let keys = jwk_set
+            .keys()
+            .into_iter()
+            .map(|jwk| {
+                let decrypter = decrypter_from_jwk(jwk)?;
+                let kid = jwk.key_id().map(ToString::to_string);
+                Ok(DecryptionKey { kid, decrypter })
+            })
+            .collect::<Result<_, JweError>>()?;
+        Ok(Self { keys })
+    }
+
+    fn select(&self, header: &JweHeader) -> Option<&dyn JweDecrypter> {
+        let algorithm = header.algorithm()?;
+        self.keys
+            .iter()
+            .filter(|key| key.decrypter.algorithm().name() == algorithm)
+            .find(|key| match (header.key_id(), key.kid.as_deref()) {
+                (Some(kid), Some(key_kid)) => kid == key_kid,
+                _ => true,
+            })
+            .map(|key| key.decrypter.as_ref())
+    }
+
+    pub(crate) fn decrypt(&self, encoded: &str) -> Result<(Vec<u8>, JweHeader), JweError> {
+        jwe::deserialize_compact_with_selector(encoded, |header| Ok(self.select(header)))
+            .map_err(|e| JweError::DecryptError(e.to_string()))
+    }
+}
+
+impl<Extension> EncodedToken<Extension>
+where
+    for<'a> Extension: serde::Deserialize<'a>,
+{
+    pub(crate) fn is_encrypted(&self) -> bool {
+        self.to_string().split('.').count() == 5
+    }
+
+    pub fn decrypt(&self, keys: &DecryptionKeySet) -> Result<Self, EncodedTokenError> {
+        let (payload, header) = keys.decrypt(&self.to_string())?;
+        let nested = header
+            .content_type()
+            .is_some_and(|cty| cty.eq_ignore_ascii_case("JWT"));
+        if !nested {
+            return Err(JweError::NotNested.into());
+        }
+        let inner = String::from_utf8(payload).map_err(|_| JweError::InvalidPayload)?;
+        if inner.split('.').count() != 3 {
+            return Err(JweError::InvalidPayload.into());
+        }
+        Ok(inner.into())
+    }
+
+    pub fn decode_encrypted(
+        self,
+        jwk_set: &jsonwebtoken::jwk::JwkSet,
+        keys: &DecryptionKeySet,
+        options: &DecodeOptions,
+    ) -> Result<Token<Extension>, EncodedTokenError> {
+        self.decrypt(keys)?.decode_with_options(jwk_set, options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use josekit::jwk::{alg::rsa::RsaKeyPair, KeyPair};
+
+    use super::*;
+    use crate::{
+        token::tests::{generate_token, JWKS_JSON},
+        Scope,
+    };
+
+    fn encrypt(key_pair: &RsaKeyPair, kid: &str, payload: &str) -> String {
+        encrypt_with_cty(key_pair, kid, payload, "JWT")
+    }
+
+    fn encrypt_with_cty(key_pair: &RsaKeyPair, kid: &str, payload: &str, cty: &str) -> String {
+        let mut header = JweHeader::new();
+        header.set_content_encryption("A256GCM");
+        header.set_content_type(cty);
+        header.set_key_id(kid);
+        let mut public_key = key_pair.to_jwk_public_key();
+        public_key.set_algorithm("RSA-OAEP");
+        let encrypter = RSA_OAEP
+            .encrypter_from_jwk(&public_key)
+            .expect("expected encrypter");
+        jwe::serialize_compact(payload.as_bytes(), &header, &encrypter).expect("expected JWE")
+    }
+
+    fn key_set(key_pair: &RsaKeyPair, kid: &str) -> DecryptionKeySet {
+        let mut private_key = key_pair.to_jwk_private_key();
+        private_key.set_algorithm("RSA-OAEP");
+        private_key.set_key_id(kid);
+        let jwk_set =
+            JwkSet::from_bytes(format!(r#"{{"keys":[{private_key}]}}"#)).expect("expected JWK set");
+        DecryptionKeySet::from_jwk_set(&jwk_set).expect("expected decryption keys")
+    }
+
+    #[test]
+    fn encrypted_token_is_decrypted_and_verified() {
+        let key_pair = RSA_OAEP.generate_key_pair(2048).expect("expected key pair");
+        let signed = generate_token(vec![Scope::new("read", "user")])
+            .expect("expected token")
+            .to_string();
+        let encrypted: EncodedToken<crate::AuthorizationClaims> =
+            encrypt(&key_pair, "enc-1", &signed).into();
+        let jwk_set = serde_json::from_str(JWKS_JSON).expect("expected JWK set");
+        let token = encrypted
+            .decode_encrypted(
+                &jwk_set,
+                &key_set(&key_pair, "enc-1"),
+                &DecodeOptions::new(),
+            )
+            .expect("expected decoded token");
+        assert_eq!(token.claims().iss, "issuer");
+    }
+
+    #[test]
+    fn encrypted_token_requires_matching_key() {
+        let key_pair = RSA_OAEP.generate_key_pair(2048).expect("expected key pair");
+        let other = RSA_OAEP.generate_key_pair(2048).expect("expected key pair");
+        let encrypted: EncodedToken<crate::AuthorizationClaims> =
+            encrypt(&key_pair, "enc-1", "a.b.c").into();
+        let result = encrypted.decrypt(&key_set(&other, "enc-1"));
+        assert!(matches!(
+            result,
+            Err(EncodedTokenError::JweError(JweError::DecryptError(_)))
+        ));
+    }
+
+    #[test]
+    fn nested_token_is_decoded_transparently() {
+        let key_pair = RSA_OAEP.generate_key_pair(2048).expect("expected key pair");
+        let signed = generate_token(vec![Scope::new("read", "user")])
+            .expect("expected token")
+            .to_string();
+        let jwk_set = serde_json::from_str(JWKS_JSON).expect("expected JWK set");
+        let options = DecodeOptions::new().decryption_keys(key_set(&key_pair, "enc-1"));
+        let encrypted: EncodedToken<crate::AuthorizationClaims> =
+            encrypt(&key_pair, "enc-1", &signed).into();
+        let token = encrypted
+            .decode_with_options(&jwk_set, &options)
+            .expect("expected decoded token");
+        assert_eq!(token.claims().iss, "issuer");
+
+        let encrypted: EncodedToken<crate::AuthorizationClaims> =
+            encrypt(&key_pair, "enc-1", &signed).into();
+        let result = encrypted.decode(&jwk_set);
+        assert!(matches!(
+            result,
+            Err(EncodedTokenError::JweError(JweError::NoDecryptionKeys))
+        ));
+
+        let encrypted: EncodedToken<crate::AuthorizationClaims> =
+            encrypt_with_cty(&key_pair, "enc-1", &signed, "json").into();
+        let result = encrypted.decode_with_options(&jwk_set, &options);
+        assert!(matches!(
+            result,
+            Err(EncodedTokenError::JweError(JweError::NotNested))
+        ));
+    }
+}
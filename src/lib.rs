@@ -2,25 +2,96 @@ use std::{fmt, fmt::Display, marker::PhantomData, str::FromStr};
 
 use serde::{de, de::Visitor, Deserializer, Serializer};
 
+mod batch_verifier;
+mod cache;
 mod claims;
+mod compare;
+mod decode_options;
 mod encoded_token;
+mod events;
+#[cfg(feature = "graphql")]
+mod graphql;
 mod issuer;
+#[cfg(feature = "jwe")]
+mod jwe;
 mod middleware;
+mod on_behalf_of;
+#[cfg(feature = "paseto")]
+mod paseto;
 mod scope;
 mod scope_deserializer;
+mod scope_index;
+mod scope_registry;
 mod scope_serializer;
 mod serde_scope;
+mod stats;
 mod token;
+mod token_reminter;
+mod x509;
 
-pub use claims::{AuthorizationClaims, Claims, UserClaims};
-pub use encoded_token::{EncodedToken, EncodedTokenError};
-pub use issuer::Issuer;
+pub mod providers;
+
+pub use batch_verifier::BatchVerifier;
+#[cfg(feature = "moka")]
+pub use cache::MokaCacheBackend;
+#[cfg(feature = "redis")]
+pub use cache::RedisCacheBackend;
+pub use cache::{CacheBackend, CacheError};
+pub use claims::{
+    Actor, Audience, AuthorizationClaims, Claims, ClaimsChange, ClaimsProfile, ClaimsValidator,
+    LifetimePolicy, MachineClaims, ScopeFormat, Subject, UserClaims, UserClaimsError,
+    UserClaimsPolicy,
+};
+pub use decode_options::{DecodeOptions, MissingKidPolicy};
+pub use encoded_token::{EncodedToken, EncodedTokenError, PeekedHeader};
+pub use events::{AuthEvent, AuthEventKind, AuthEvents};
+#[cfg(feature = "graphql")]
+pub use graphql::{GraphQLAuthorization, RequireScope};
+pub use issuer::{DynIssuer, Issuer, IssuerTemplate, KeySetFormat};
+#[cfg(feature = "jwe")]
+pub use jwe::{DecryptionKeySet, JweError};
+#[cfg(feature = "httpauth")]
+pub use middleware::httpauth::{bearer_validator, BearerValidator};
+#[cfg(feature = "casbin")]
+pub use middleware::policy::CasbinPolicyEngine;
+#[cfg(feature = "cedar")]
+pub use middleware::policy::CedarPolicyEngine;
+#[cfg(feature = "cookies")]
+pub use middleware::TokenCookie;
 pub use middleware::{
-    authorization_middleware::AuthorizationFactory, jwk_set_middleware::JwkSetFactory,
-    jwt_middleware::JWTFactory, verify, Authorization, AuthorizationError,
+    authorization_middleware::{AuthorizationFactory, AuthorizationMiddlewareError},
+    back_channel_logout,
+    issuer_middleware::{HostIssuerResolver, IssuerError, IssuerFactory},
+    jwk_set_middleware::{JwkSetFactory, JwkSetHandle, KeyRotation, KeyRotationTracker},
+    jwt_middleware::{JWTFactory, TokenSource},
+    policy::{PolicyDecision, PolicyEngine, PolicyError, PolicyRequest},
+    rate_limit_middleware::{
+        InMemoryRateLimitStore, RateLimitError, RateLimitFactory, RateLimitHit, RateLimitStore,
+    },
+    verify, verify_all, verify_any, verify_with,
+    websocket::{WebSocketAuth, WebSocketAuthError, WebSocketHandshake, WebSocketTokenSource},
+    AudienceGuard, AuthScheme, AuthStack, Authenticated, AuthenticatedError, Authorization,
+    AuthorizationError, BackChannelLogout, BackChannelLogoutError, ClaimGuard, ClientBinding,
+    ClientBindingSource, CorrelatedError, EnrichmentError, ExpectedClaims, ForwardToken,
+    FromClaims, HasScope, IsSubject, LogoutClaims, LogoutRequest, LogoutSession,
+    OptionalAuthorization, PathRequirements, RequestIdHeader, RequiredAuthorization, RuntimeToggle,
+    ScopeDiff, ScopeRequirement, StatusMapping, Tenant, ValidationOutcome, VerifyWith,
 };
+#[cfg(feature = "dpop")]
+pub use middleware::{DPoPError, DPoPVerifier};
+#[cfg(feature = "session")]
+pub use middleware::{SessionClaims, SessionClaimsError, SessionTokenCache};
+pub use on_behalf_of::{OnBehalfOf, OnBehalfOfError, OnBehalfOfToken};
+#[cfg(feature = "paseto")]
+pub use paseto::{EncodedPaseto, PasetoError, PasetoKeySet};
 pub use scope::{Scope, ScopeError};
-pub use token::{AccessToken, EncodedAccessToken, EncodedIdToken, IdToken};
+pub use scope_registry::{ScopeRegistry, UnknownScopePolicy};
+pub use stats::{Stats, StatsCollector};
+pub use token::{
+    AccessToken, EncodedAccessToken, EncodedIdToken, EncodedMachineToken, IdToken, MachineToken,
+};
+pub use token_reminter::{RemintError, TokenReminter};
+pub use x509::CertificateError;
 
 fn space_separated_deserialize<'de, V, T, D>(deserializer: D) -> Result<V, D::Error>
 where
@@ -40,20 +111,33 @@ where
         type Value = V;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("string containing space-separated elements")
+            formatter.write_str(
+                "string containing whitespace-separated elements or a sequence of strings",
+            )
         }
 
         fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
         where
             E: de::Error,
         {
-            let iter = s.split(" ").map(FromStr::from_str);
+            let iter = s.split_whitespace().map(FromStr::from_str);
             Result::from_iter(iter).map_err(de::Error::custom)
         }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: de::SeqAccess<'de>,
+        {
+            let mut elements = Vec::new();
+            while let Some(element) = seq.next_element::<String>()? {
+                elements.push(T::from_str(&element).map_err(de::Error::custom)?);
+            }
+            Ok(elements.into_iter().collect())
+        }
     }
 
     let visitor = SpaceSeparated(PhantomData, PhantomData);
-    deserializer.deserialize_str(visitor)
+    deserializer.deserialize_any(visitor)
 }
 
 fn space_separated_serialize<V, T, S>(x: &V, s: S) -> Result<S::Ok, S::Error>
@@ -70,3 +154,80 @@ where
     let res = iter.join(" ");
     s.serialize_str(&res)
 }
+
+struct Timestamp(u64);
+
+impl<'de> de::Deserialize<'de> for Timestamp {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct TimestampVisitor;
+
+        impl<'de> Visitor<'de> for TimestampVisitor {
+            type Value = Timestamp;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("integer, float or numeric string timestamp")
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Timestamp(v))
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                u64::try_from(v)
+                    .map(Timestamp)
+                    .map_err(|_| de::Error::invalid_value(de::Unexpected::Signed(v), &self))
+            }
+
+            fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                if !v.is_finite() || v < 0.0 {
+                    return Err(de::Error::invalid_value(de::Unexpected::Float(v), &self));
+                }
+                Ok(Timestamp(v.trunc() as u64))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                let v = v.trim();
+                match v.parse::<u64>() {
+                    Ok(timestamp) => self.visit_u64(timestamp),
+                    Err(_) => v
+                        .parse::<f64>()
+                        .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(v), &self))
+                        .and_then(|timestamp| self.visit_f64(timestamp)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(TimestampVisitor)
+    }
+}
+
+fn timestamp_deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let timestamp = <Timestamp as de::Deserialize>::deserialize(deserializer)?;
+    Ok(timestamp.0)
+}
+
+fn optional_timestamp_deserialize<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let timestamp = <Option<Timestamp> as de::Deserialize>::deserialize(deserializer)?;
+    Ok(timestamp.map(|timestamp| timestamp.0))
+}
@@ -1,12 +1,68 @@
 pub mod authorization_middleware;
+#[cfg(feature = "httpauth")]
+pub mod httpauth;
+pub mod issuer_middleware;
 pub mod jwk_set_middleware;
 pub mod jwt_middleware;
+pub mod policy;
+pub mod rate_limit_middleware;
+pub mod websocket;
 
+mod audience_guard;
+mod auth_scheme;
+mod auth_stack;
+mod authenticated;
 mod authorization;
 mod authorization_error;
+mod back_channel_logout;
+mod claim_guard;
+mod client_binding;
+#[cfg(feature = "dpop")]
+mod dpop;
+mod enrichment;
 mod error_response;
+mod expected_claims;
+mod forward_token;
+mod has_scope;
+mod jwks_http_cache;
+mod path_requirements;
+mod runtime_toggle;
+#[cfg(feature = "session")]
+mod session_cache;
+mod tenant;
+#[cfg(feature = "cookies")]
+mod token_cookie;
+mod validation_outcome;
 mod verify;
 
-pub use authorization::Authorization;
-pub use authorization_error::AuthorizationError;
-pub use verify::verify;
+pub use audience_guard::AudienceGuard;
+pub(crate) use auth_scheme::is_dpop_bound;
+pub use auth_scheme::AuthScheme;
+pub use auth_stack::AuthStack;
+pub use authenticated::{Authenticated, AuthenticatedError, FromClaims};
+pub use authorization::{Authorization, OptionalAuthorization, RequiredAuthorization};
+pub use authorization_error::{AuthorizationError, ScopeDiff};
+pub use back_channel_logout::{
+    back_channel_logout, BackChannelLogout, BackChannelLogoutError, LogoutClaims, LogoutRequest,
+    LogoutSession,
+};
+pub use claim_guard::ClaimGuard;
+pub use client_binding::{ClientBinding, ClientBindingSource};
+#[cfg(feature = "dpop")]
+pub use dpop::{DPoPError, DPoPVerifier};
+pub use enrichment::EnrichmentError;
+pub(crate) use enrichment::{enricher, Enricher};
+pub use error_response::{CorrelatedError, RequestIdHeader, StatusMapping};
+pub use expected_claims::ExpectedClaims;
+pub use forward_token::ForwardToken;
+pub use has_scope::{HasScope, ScopeRequirement};
+pub use path_requirements::PathRequirements;
+pub use runtime_toggle::RuntimeToggle;
+#[cfg(feature = "session")]
+pub use session_cache::{SessionClaims, SessionClaimsError, SessionTokenCache};
+pub use tenant::Tenant;
+pub(crate) use tenant::TenantClaim;
+#[cfg(feature = "cookies")]
+pub use token_cookie::TokenCookie;
+pub use validation_outcome::ValidationOutcome;
+pub use verify::{verify, verify_all, verify_any, verify_with, IsSubject, VerifyWith};
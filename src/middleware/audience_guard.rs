@@ -0,0 +1,88 @@
+use actix_web::guard::{Guard, GuardContext};
+
+use crate::AccessToken;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudienceGuard {
+    audience: String,
+}
+
+impl AudienceGuard {
+    pub fn new(audience: &str) -> Self {
+        Self {
+            audience: audience.to_string(),
+        }
+    }
+}
+
+impl Guard for AudienceGuard {
+    fn check(&self, ctx: &GuardContext<'_>) -> bool {
+        ctx.req_data()
+            .get::<AccessToken>()
+            .and_then(|token| token.claims().aud.as_ref())
+            .is_some_and(|aud| aud.contains(&self.audience))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use actix_web::{
+        dev::Service as _, http::StatusCode, test, web, App, HttpMessage, HttpResponse,
+    };
+    use jsonwebtoken::Header;
+
+    use super::*;
+    use crate::{token::Token, AuthorizationClaims, Claims};
+
+    fn token(aud: &str) -> AccessToken {
+        let extension = AuthorizationClaims { scopes: vec![] };
+        let aud = vec![aud.to_string()];
+        let claims = Claims::new(
+            "issuer",
+            "subject",
+            &aud,
+            Duration::from_secs(60),
+            extension,
+        );
+        Token::new(Header::default(), claims)
+    }
+
+    #[actix_web::test]
+    async fn guard_routes_by_audience() {
+        let app = test::init_service(
+            App::new()
+                .wrap_fn(|req, srv| {
+                    let aud = req.headers().get("x-aud").unwrap().to_str().unwrap();
+                    req.extensions_mut().insert(token(aud));
+                    srv.call(req)
+                })
+                .service(
+                    web::scope("/api")
+                        .guard(AudienceGuard::new("admin"))
+                        .default_service(web::to(|| async { HttpResponse::Ok().body("admin") })),
+                )
+                .service(
+                    web::scope("/api")
+                        .guard(AudienceGuard::new("public"))
+                        .default_service(web::to(|| async { HttpResponse::Ok().body("public") })),
+                ),
+        )
+        .await;
+        for aud in ["admin", "public"] {
+            let req = test::TestRequest::get()
+                .uri("/api/items")
+                .insert_header(("x-aud", aud))
+                .to_request();
+            let body = test::call_and_read_body(&app, req).await;
+            assert_eq!(body, aud);
+        }
+        let req = test::TestRequest::get()
+            .uri("/api/items")
+            .insert_header(("x-aud", "other"))
+            .to_request();
+        let res = app.call(req).await.expect("expected response");
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    fmt::{Display, Formatter},
+    future::{ready, Ready},
+};
+
+use actix_web::{FromRequest, HttpMessage};
+
+use crate::{middleware::AuthenticatedError, AccessToken};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuthScheme {
+    Bearer,
+    DPoP,
+}
+
+impl AuthScheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthScheme::Bearer => "Bearer",
+            AuthScheme::DPoP => "DPoP",
+        }
+    }
+
+    // Scheme names are case-insensitive (RFC 9110 section 11.1).
+    pub(crate) fn parse(authorization: &str) -> Option<(AuthScheme, &str)> {
+        let (scheme, credentials) = authorization.trim().split_once(' ')?;
+        let scheme = [AuthScheme::Bearer, AuthScheme::DPoP]
+            .into_iter()
+            .find(|candidate| candidate.as_str().eq_ignore_ascii_case(scheme))?;
+        let credentials = credentials.trim();
+        (!credentials.is_empty()).then_some((scheme, credentials))
+    }
+}
+
+impl Display for AuthScheme {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// DPoP-bound tokens carry the thumbprint of the client's proof key in cnf.jkt
+// (RFC 9449 section 6).
+pub(crate) fn is_dpop_bound(token: &AccessToken) -> bool {
+    token
+        .claims()
+        .additional
+        .get("cnf")
+        .and_then(|cnf| cnf.get("jkt"))
+        .is_some()
+}
+
+impl FromRequest for AuthScheme {
+    type Error = AuthenticatedError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        _payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        let result = req
+            .extensions()
+            .get::<AuthScheme>()
+            .copied()
+            .ok_or(AuthenticatedError::Unauthenticated);
+        ready(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_accepts_known_schemes_case_insensitively() {
+        assert_eq!(
+            AuthScheme::parse("Bearer abc"),
+            Some((AuthScheme::Bearer, "abc"))
+        );
+        assert_eq!(
+            AuthScheme::parse("dpop abc"),
+            Some((AuthScheme::DPoP, "abc"))
+        );
+        assert_eq!(AuthScheme::parse("Basic abc"), None);
+        assert_eq!(AuthScheme::parse("Bearer "), None);
+        assert_eq!(AuthScheme::parse("abc"), None);
+    }
+}
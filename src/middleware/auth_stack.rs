@@ -0,0 +1,146 @@
+use std::future::{ready, Ready};
+
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+
+use crate::{
+    middleware::{
+        authorization_middleware::{AuthorizationFactory, AuthorizationMiddleware},
+        issuer_middleware::{IssuerFactory, IssuerMiddleware},
+        jwk_set_middleware::{JwkSetFactory, JwkSetMiddleware},
+        jwt_middleware::{JWTFactory, JWTMiddleware},
+    },
+    Issuer,
+};
+
+// The four factories wrapped in the order they must run, so a single
+// `.wrap(AuthStack::new(..))` cannot get it wrong. Apps that place other
+// middleware between them wrap the factories one by one instead.
+pub struct AuthStack<I: Issuer> {
+    issuer: IssuerFactory<I>,
+    jwk_set: JwkSetFactory<I>,
+    jwt: JWTFactory,
+    authorization: AuthorizationFactory<I>,
+}
+
+impl<I: Issuer> AuthStack<I> {
+    pub fn new(
+        issuer: IssuerFactory<I>,
+        jwk_set: JwkSetFactory<I>,
+        jwt: JWTFactory,
+        authorization: AuthorizationFactory<I>,
+    ) -> Self {
+        Self {
+            issuer,
+            jwk_set,
+            jwt,
+            authorization,
+        }
+    }
+}
+
+type StackMiddleware<I, S> =
+    IssuerMiddleware<I, JwkSetMiddleware<I, JWTMiddleware<AuthorizationMiddleware<I, S>>>>;
+
+impl<I, S, B> Transform<S, ServiceRequest> for AuthStack<I>
+where
+    I: Issuer + Clone + 'static,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = StackMiddleware<I, S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    // Innermost first, as `.wrap()` calls would register them.
+    fn new_transform(&self, service: S) -> Self::Future {
+        let middleware = self
+            .authorization
+            .new_transform(service)
+            .into_inner()
+            .and_then(|service| self.jwt.new_transform(service).into_inner())
+            .and_then(|service| self.jwk_set.new_transform(service).into_inner())
+            .and_then(|service| self.issuer.new_transform(service).into_inner());
+        ready(middleware)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+    use futures::future::LocalBoxFuture;
+
+    use super::*;
+    use crate::{
+        cache::{CacheBackend, CacheError},
+        token::tests::{generate_token, JWKS_JSON},
+        Scope,
+    };
+
+    #[derive(Clone)]
+    struct TestIssuer;
+
+    impl Issuer for TestIssuer {
+        fn url(&self) -> String {
+            "issuer".to_string()
+        }
+    }
+
+    // Answers every lookup with the test key set so no request leaves the test.
+    struct PreloadedKeys;
+
+    impl CacheBackend for PreloadedKeys {
+        fn get(&self, _key: &str) -> LocalBoxFuture<'static, Result<Option<Vec<u8>>, CacheError>> {
+            Box::pin(async { Ok(Some(JWKS_JSON.as_bytes().to_vec())) })
+        }
+
+        fn set(
+            &self,
+            _key: &str,
+            _value: Vec<u8>,
+            _ttl: Duration,
+        ) -> LocalBoxFuture<'static, Result<(), CacheError>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    fn stack(audience: &str) -> AuthStack<TestIssuer> {
+        AuthStack::new(
+            IssuerFactory::new(TestIssuer),
+            JwkSetFactory::new().cache(PreloadedKeys),
+            JWTFactory::new(),
+            AuthorizationFactory::new(audience.to_string()),
+        )
+    }
+
+    async fn call(stack: AuthStack<TestIssuer>) -> StatusCode {
+        let app = test::init_service(
+            App::new()
+                .wrap(stack)
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+        let token = generate_token(vec![Scope::new("read", "projects")]).expect("expected token");
+        let req = test::TestRequest::get()
+            .uri("/projects")
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_request();
+        match test::try_call_service(&app, req).await {
+            Ok(res) => res.status(),
+            Err(e) => e.as_response_error().status_code(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn stack_runs_the_factories_in_order() {
+        assert_eq!(call(stack("audience")).await, StatusCode::OK);
+        assert_eq!(call(stack("other")).await, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    fmt::Display,
+    future::{ready, Ready},
+};
+
+use actix_web::{
+    body::BoxBody, http::StatusCode, FromRequest, HttpMessage, HttpResponse, ResponseError,
+};
+
+use crate::{
+    middleware::error_response::{status_error_body, BearerChallenge, ErrorBody, MiddlewareError},
+    token::Token,
+    AccessToken, AuthorizationClaims, Claims,
+};
+
+pub trait FromClaims: Sized {
+    type Error: Display;
+
+    fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuthenticatedError {
+    #[error("no authenticated token")]
+    Unauthenticated,
+    #[error("claims could not be converted: {0}")]
+    InvalidClaims(String),
+}
+
+impl ResponseError for AuthenticatedError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthenticatedError::Unauthenticated => StatusCode::UNAUTHORIZED,
+            AuthenticatedError::InvalidClaims(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        self.respond(None)
+    }
+}
+
+impl MiddlewareError for AuthenticatedError {
+    fn error_body(&self) -> ErrorBody {
+        match self {
+            AuthenticatedError::Unauthenticated => {
+                status_error_body(self.status_code(), "UNAUTHENTICATED", self)
+            }
+            AuthenticatedError::InvalidClaims(_) => {
+                status_error_body(self.status_code(), "INVALID_CLAIMS", self)
+            }
+        }
+    }
+
+    fn challenge(&self) -> Option<BearerChallenge> {
+        match self {
+            AuthenticatedError::Unauthenticated => Some(BearerChallenge::new()),
+            AuthenticatedError::InvalidClaims(_) => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Authenticated<U>(U);
+
+impl<U> Authenticated<U> {
+    pub fn into_inner(self) -> U {
+        self.0
+    }
+}
+
+impl<U: FromClaims> FromRequest for Authenticated<U> {
+    type Error = AuthenticatedError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        _payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        let extensions = req.extensions();
+        let result = extensions
+            .get::<AccessToken>()
+            .ok_or(AuthenticatedError::Unauthenticated)
+            .and_then(|token| {
+                U::from_claims(token.claims())
+                    .map_err(|e| AuthenticatedError::InvalidClaims(e.to_string()))
+            })
+            .map(Authenticated)
+            .map_err(|e| {
+                log::info!("{}", e);
+                e
+            });
+        ready(result)
+    }
+}
+
+impl<Extension: Clone + 'static> FromRequest for Claims<Extension> {
+    type Error = AuthenticatedError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        _payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        let result = req
+            .extensions()
+            .get::<Token<Extension>>()
+            .map(|token| token.claims().clone())
+            .ok_or(AuthenticatedError::Unauthenticated)
+            .map_err(|e| {
+                log::info!("{}", e);
+                e
+            });
+        ready(result)
+    }
+}
+
+impl<U> std::ops::Deref for Authenticated<U> {
+    type Target = U;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use actix_web::test::TestRequest;
+    use jsonwebtoken::Header;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct User {
+        id: String,
+    }
+
+    impl FromClaims for User {
+        type Error = &'static str;
+
+        fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error> {
+            let id = claims.sub.as_ref().ok_or("missing subject")?.to_string();
+            Ok(User { id })
+        }
+    }
+
+    fn token(sub: Option<&str>) -> AccessToken {
+        let aud = vec!["audience".to_string()];
+        let extension = AuthorizationClaims { scopes: vec![] };
+        let mut claims = Claims::new("issuer", "", &aud, Duration::from_secs(60), extension);
+        claims.sub = sub.map(Into::into);
+        Token::new(Header::default(), claims)
+    }
+
+    #[actix_web::test]
+    async fn authenticated_converts_claims() {
+        let req = TestRequest::default().to_http_request();
+        req.extensions_mut().insert(token(Some("subject")));
+        let user = Authenticated::<User>::extract(&req)
+            .await
+            .expect("expected user");
+        assert_eq!(user.id, "subject");
+    }
+
+    #[actix_web::test]
+    async fn missing_token_is_unauthorized() {
+        let req = TestRequest::default().to_http_request();
+        let error = Authenticated::<User>::extract(&req)
+            .await
+            .expect_err("expected error");
+        assert_eq!(error.status_code(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn failed_conversion_is_unprocessable() {
+        let req = TestRequest::default().to_http_request();
+        req.extensions_mut().insert(token(None));
+        let error = Authenticated::<User>::extract(&req)
+            .await
+            .expect_err("expected error");
+        assert_eq!(error.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct CustomClaims {
+        department: String,
+    }
+
+    #[actix_web::test]
+    async fn claims_are_extracted_for_inserted_token_type() {
+        let req = TestRequest::default().to_http_request();
+        req.extensions_mut().insert(token(Some("subject")));
+        let claims = Claims::<AuthorizationClaims>::extract(&req)
+            .await
+            .expect("expected claims");
+        assert_eq!(claims.sub, Some("subject".into()));
+        let error = Claims::<CustomClaims>::extract(&req)
+            .await
+            .expect_err("expected error");
+        assert_eq!(error.status_code(), StatusCode::UNAUTHORIZED);
+
+        let extension = CustomClaims {
+            department: "sales".to_string(),
+        };
+        let claims = Claims::new("issuer", "subject", &[], Duration::from_secs(60), extension);
+        req.extensions_mut()
+            .insert(Token::new(Header::default(), claims));
+        let claims = Claims::<CustomClaims>::extract(&req)
+            .await
+            .expect("expected claims");
+        assert_eq!(claims.extension.department, "sales");
+    }
+}
@@ -1,18 +1,72 @@
 use std::{
     convert::Infallible,
     future::{ready, Ready},
+    sync::Arc,
 };
 
-use actix_web::{FromRequest, HttpMessage};
+use actix_web::{FromRequest, HttpMessage, HttpRequest};
 
-use crate::{AccessToken, AuthorizationClaims, Claims};
+use crate::{
+    middleware::AuthenticatedError, scope_index::ScopeIndex, AccessToken, AuthorizationClaims,
+    Claims,
+};
 
+// The token and its resource map, built once per request and shared by every
+// extractor and verify() call through the request extensions.
 #[derive(Debug)]
-pub struct Authorization(Option<AccessToken>);
+struct RequestToken {
+    token: Option<AccessToken>,
+    resources: ScopeIndex,
+}
+
+impl RequestToken {
+    fn new(token: Option<AccessToken>) -> Self {
+        let resources = token
+            .as_ref()
+            .map(|token| ScopeIndex::new(token.scopes()))
+            .unwrap_or_default();
+        Self { token, resources }
+    }
+}
+
+fn request_token(req: &HttpRequest) -> Arc<RequestToken> {
+    if let Some(cached) = req.extensions().get::<Arc<RequestToken>>() {
+        return cached.clone();
+    }
+    // Nothing is cached until a token is present, so extracting before an
+    // authenticator has run does not hide the token it inserts later.
+    let token = req.extensions().get::<AccessToken>().cloned();
+    let cached = Arc::new(RequestToken::new(token));
+    if cached.token.is_some() {
+        req.extensions_mut().insert(cached.clone());
+    }
+    cached
+}
+
+#[derive(Debug, Clone)]
+pub struct Authorization(Arc<RequestToken>);
+
+pub type OptionalAuthorization = Authorization;
+
+#[derive(Debug, Clone)]
+pub struct RequiredAuthorization(Arc<RequestToken>);
 
 impl Authorization {
+    #[cfg(test)]
+    pub(crate) fn new(token: Option<AccessToken>) -> Self {
+        Self(Arc::new(RequestToken::new(token)))
+    }
+
     pub fn claims(&self) -> Option<Claims<AuthorizationClaims>> {
-        self.0.as_ref().map(|token| token.claims().clone())
+        self.0.token.as_ref().map(|token| token.claims().clone())
+    }
+
+    pub(crate) fn grants(&self, resource: &str, action: &str) -> Option<bool> {
+        self.0.resources.grants(resource, action)
+    }
+
+    pub(crate) fn of(req: &HttpRequest) -> Self {
+        Authorization(request_token(req))
     }
 }
 
@@ -20,13 +74,8 @@ impl FromRequest for Authorization {
     type Error = Infallible;
     type Future = Ready<Result<Self, Self::Error>>;
 
-    fn from_request(
-        req: &actix_web::HttpRequest,
-        _payload: &mut actix_web::dev::Payload,
-    ) -> Self::Future {
-        let token = req.extensions().get::<AccessToken>().cloned();
-        let result = Ok(Authorization(token));
-        ready(result)
+    fn from_request(req: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+        ready(Ok(Authorization::of(req)))
     }
 }
 
@@ -34,6 +83,109 @@ impl std::ops::Deref for Authorization {
     type Target = Option<AccessToken>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.0.token
+    }
+}
+
+impl RequiredAuthorization {
+    pub fn claims(&self) -> &Claims<AuthorizationClaims> {
+        self.token().claims()
+    }
+
+    pub fn into_inner(self) -> AccessToken {
+        self.token().clone()
+    }
+
+    fn token(&self) -> &AccessToken {
+        self.0
+            .token
+            .as_ref()
+            .expect("RequiredAuthorization is only extracted with a token")
+    }
+}
+
+impl From<RequiredAuthorization> for Authorization {
+    fn from(value: RequiredAuthorization) -> Self {
+        Authorization(value.0)
+    }
+}
+
+impl FromRequest for RequiredAuthorization {
+    type Error = AuthenticatedError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+        let cached = request_token(req);
+        let result = cached
+            .token
+            .is_some()
+            .then_some(RequiredAuthorization(cached))
+            .ok_or(AuthenticatedError::Unauthenticated)
+            .map_err(|e| {
+                log::info!("{}", e);
+                e
+            });
+        ready(result)
+    }
+}
+
+impl std::ops::Deref for RequiredAuthorization {
+    type Target = AccessToken;
+
+    fn deref(&self) -> &Self::Target {
+        self.token()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use actix_web::{test::TestRequest, ResponseError};
+    use jsonwebtoken::Header;
+
+    use super::*;
+    use crate::token::Token;
+
+    #[actix_web::test]
+    async fn required_authorization_rejects_missing_token() {
+        let req = TestRequest::default().to_http_request();
+        let optional = OptionalAuthorization::extract(&req)
+            .await
+            .expect("infallible");
+        assert!(optional.is_none());
+        let error = RequiredAuthorization::extract(&req)
+            .await
+            .expect_err("expected error");
+        assert_eq!(
+            error.status_code(),
+            actix_web::http::StatusCode::UNAUTHORIZED
+        );
+
+        let extension = AuthorizationClaims { scopes: vec![] };
+        let claims = Claims::new("issuer", "subject", &[], Duration::from_secs(60), extension);
+        req.extensions_mut()
+            .insert(Token::new(Header::default(), claims));
+        let required = RequiredAuthorization::extract(&req)
+            .await
+            .expect("expected authorization");
+        assert_eq!(required.claims().iss, "issuer");
+    }
+
+    #[actix_web::test]
+    async fn extractors_share_the_request_token() {
+        let req = TestRequest::default().to_http_request();
+        let extension = AuthorizationClaims {
+            scopes: vec![crate::Scope::new("read", "projects")],
+        };
+        let claims = Claims::new("issuer", "subject", &[], Duration::from_secs(60), extension);
+        req.extensions_mut()
+            .insert(Token::new(Header::default(), claims));
+        let optional = Authorization::extract(&req).await.expect("infallible");
+        let required = RequiredAuthorization::extract(&req)
+            .await
+            .expect("expected authorization");
+        assert!(Arc::ptr_eq(&optional.0, &required.0));
+        assert_eq!(optional.grants("projects", "read"), Some(true));
     }
 }
@@ -1,9 +1,158 @@
+use std::fmt::{self, Display, Formatter};
+
+use actix_web::{body::BoxBody, http::StatusCode, HttpResponse, ResponseError};
+use serde_json::json;
+
+use crate::{
+    middleware::error_response::{
+        forbidden_error_body, unauthorized_error_body, BearerChallenge, BearerError, ErrorBody,
+        MiddlewareError,
+    },
+    Scope,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeDiff {
+    pub required: Vec<Scope>,
+    pub present: Vec<Scope>,
+}
+
+impl ScopeDiff {
+    pub fn new(required: Vec<Scope>, present: Vec<Scope>) -> Self {
+        Self { required, present }
+    }
+
+    pub fn missing(&self) -> Vec<&Scope> {
+        self.required
+            .iter()
+            .filter(|scope| !self.present.contains(scope))
+            .collect()
+    }
+}
+
+fn join(scopes: &[&Scope]) -> String {
+    let scopes = scopes.iter().map(ToString::to_string).collect::<Vec<_>>();
+    scopes.join(" ")
+}
+
+impl Display for ScopeDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let present = self.present.iter().collect::<Vec<_>>();
+        write!(
+            f,
+            "missing scopes [{}], present scopes [{}]",
+            join(&self.missing()),
+            join(&present)
+        )
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum AuthorizationError {
     #[error("not authorized")]
     Unauthorized,
-    #[error("Resource '{0}' not authorized")]
-    UnauthorizedResource(String),
-    #[error("Action '{0}' not authorized")]
-    UnauthorizedAction(String),
+    #[error("Resource not authorized: {0}")]
+    UnauthorizedResource(ScopeDiff),
+    #[error("Action not authorized: {0}")]
+    UnauthorizedAction(ScopeDiff),
+    #[error("Context not authorized")]
+    UnauthorizedContext,
+}
+
+impl AuthorizationError {
+    pub fn scope_diff(&self) -> Option<&ScopeDiff> {
+        match self {
+            AuthorizationError::UnauthorizedResource(diff)
+            | AuthorizationError::UnauthorizedAction(diff) => Some(diff),
+            _ => None,
+        }
+    }
+
+    // Widens the required scopes when several requirements were checked, so the
+    // diff names everything the client should request.
+    pub(crate) fn requiring(mut self, required: Vec<Scope>) -> Self {
+        if let AuthorizationError::UnauthorizedResource(diff)
+        | AuthorizationError::UnauthorizedAction(diff) = &mut self
+        {
+            diff.required = required;
+        }
+        self
+    }
+}
+
+impl ResponseError for AuthorizationError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthorizationError::Unauthorized => StatusCode::UNAUTHORIZED,
+            _ => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        self.respond(None)
+    }
+}
+
+impl MiddlewareError for AuthorizationError {
+    fn error_body(&self) -> ErrorBody {
+        match self {
+            AuthorizationError::Unauthorized => unauthorized_error_body("UNAUTHORIZED", self),
+            AuthorizationError::UnauthorizedContext => {
+                forbidden_error_body("UNAUTHORIZED_CONTEXT", self)
+            }
+            AuthorizationError::UnauthorizedResource(diff)
+            | AuthorizationError::UnauthorizedAction(diff) => {
+                forbidden_error_body("INSUFFICIENT_SCOPE", self).details(json!({
+                    "required_scopes": diff.required,
+                    "present_scopes": diff.present,
+                    "missing_scopes": diff.missing(),
+                }))
+            }
+        }
+    }
+
+    fn challenge(&self) -> Option<BearerChallenge> {
+        let challenge = BearerChallenge::new();
+        if let AuthorizationError::Unauthorized = self {
+            return Some(challenge);
+        }
+        let challenge = challenge.error(BearerError::InsufficientScope);
+        match self.scope_diff() {
+            Some(diff) => {
+                let required = diff.required.iter().collect::<Vec<_>>();
+                Some(challenge.scope(join(&required)))
+            }
+            None => Some(challenge),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{body::to_bytes, http::header::WWW_AUTHENTICATE};
+    use serde_json::Value;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn insufficient_scope_names_missing_scopes() {
+        let diff = ScopeDiff::new(
+            vec![Scope::new("read", "users"), Scope::new("delete", "users")],
+            vec![Scope::new("read", "users")],
+        );
+        let error = AuthorizationError::UnauthorizedAction(diff);
+        let response = error.error_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let challenge = response.headers().get(WWW_AUTHENTICATE).unwrap();
+        assert_eq!(
+            challenge,
+            "Bearer error=\"insufficient_scope\", error_description=\"Action not authorized: \
+             missing scopes [delete:users], present scopes [read:users]\", \
+             scope=\"read:users delete:users\""
+        );
+        let bytes = to_bytes(response.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["code"], "INSUFFICIENT_SCOPE");
+        assert_eq!(body["details"]["missing_scopes"], json!(["delete:users"]));
+    }
 }
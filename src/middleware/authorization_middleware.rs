@@ -1,49 +1,205 @@
 use std::{
-    future::{ready, Ready},
+    future::{ready, Future, Ready},
     marker::PhantomData,
     rc::Rc,
+    time::Instant,
 };
 
 use actix_web::{
     body::BoxBody,
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
     http::StatusCode,
-    Error, HttpMessage, HttpResponse, HttpResponseBuilder, ResponseError,
+    Error, HttpMessage, HttpRequest, HttpResponse, ResponseError,
 };
 use chrono::Utc;
 use futures::future::LocalBoxFuture;
+use serde_json::json;
 
 use crate::{
-    middleware::error_response::{forbidden_error_body, internal_server_error_body},
-    AccessToken, Claims, Issuer,
+    compare::{secure_contains, secure_eq},
+    issuer::{issuer_variants, normalize_issuer},
+    middleware::{
+        enricher,
+        error_response::{
+            error_context, forbidden_error_body, internal_server_error_body, miswired,
+            status_error_body, unauthorized_error_body, BearerChallenge, BearerError,
+            CorrelatedError, ErrorBody, MiddlewareError,
+        },
+        jwt_middleware::ExpiryGrace,
+        path_requirements::{self, PathRequirements},
+        policy::{PolicyDecision, PolicyEngine, PolicyRequest},
+        ClientBinding, Enricher, EnrichmentError, ExpectedClaims, RuntimeToggle, ScopeDiff,
+        TenantClaim, ValidationOutcome,
+    },
+    AccessToken, AuthEvent, AuthEventKind, AuthEvents, AuthorizationClaims, Claims, ClaimsProfile,
+    ClaimsValidator, Issuer, ScopeRegistry, Tenant, UnknownScopePolicy,
 };
 
-#[derive(Clone, Debug)]
-struct ExpectedClaims {
-    pub expected_audience: String,
-}
+type AuthorizationHook = Rc<
+    dyn Fn(Claims<AuthorizationClaims>, HttpRequest) -> LocalBoxFuture<'static, Result<(), String>>,
+>;
 
 pub struct AuthorizationFactory<I: Issuer> {
-    enabled: bool,
+    enabled: RuntimeToggle,
     expected_claims: ExpectedClaims,
+    scope_registry: Option<ScopeRegistry>,
+    tenant_claim: Option<TenantClaim>,
+    policy_engine: Option<Rc<dyn PolicyEngine>>,
+    authorization_hook: Option<AuthorizationHook>,
+    client_binding: Option<ClientBinding>,
+    validators: Vec<Rc<dyn ClaimsValidator<AuthorizationClaims>>>,
+    enrichers: Vec<Enricher>,
+    path_requirements: Vec<PathRequirements>,
+    events: Option<AuthEvents>,
     phantom: PhantomData<I>,
 }
 
 impl<I: Issuer> AuthorizationFactory<I> {
     pub fn new(expected_audience: String) -> Self {
-        let enabled = true;
-        let expected_claims = ExpectedClaims { expected_audience };
+        Self::with_expected_claims(ExpectedClaims::new().audience(&expected_audience))
+    }
+
+    pub fn with_expected_claims(expected_claims: ExpectedClaims) -> Self {
+        let enabled = RuntimeToggle::default();
         Self {
             expected_claims,
             enabled,
+            scope_registry: None,
+            tenant_claim: None,
+            policy_engine: None,
+            authorization_hook: None,
+            client_binding: None,
+            validators: Vec::new(),
+            enrichers: Vec::new(),
+            path_requirements: Vec::new(),
+            events: None,
             phantom: Default::default(),
         }
     }
 
     pub fn enabled(mut self, value: bool) -> Self {
+        self.enabled = RuntimeToggle::new(value);
+        self
+    }
+
+    // Replaces enabled() with a switch that can be flipped while serving.
+    pub fn toggle(mut self, value: RuntimeToggle) -> Self {
         self.enabled = value;
         self
     }
+
+    pub fn allow_missing_audience(mut self, value: bool) -> Self {
+        self.expected_claims.claims_profile =
+            self.expected_claims.claims_profile.require_audience(!value);
+        self
+    }
+
+    pub fn claims_profile(mut self, value: ClaimsProfile) -> Self {
+        self.expected_claims.claims_profile = value;
+        self
+    }
+
+    // Issuers are compared ignoring scheme/host case and trailing slashes unless
+    // strict matching is enabled.
+    pub fn strict_issuer(mut self, value: bool) -> Self {
+        self.expected_claims.strict_issuer = value;
+        self
+    }
+
+    pub fn accept_authorized_party(mut self, value: bool) -> Self {
+        self.expected_claims.accept_authorized_party = value;
+        self
+    }
+
+    // Requires tokens to carry an "act" claim. When `allowed` is not empty the
+    // current actor's sub must be one of them.
+    pub fn require_actor(mut self, allowed: &[&str]) -> Self {
+        let allowed = allowed.iter().map(ToString::to_string).collect();
+        self.expected_claims.required_actors = Some(allowed);
+        self
+    }
+
+    // For tokens shared by the parts of a composite service: every audience given
+    // must be in aud, on top of the expected audience check.
+    pub fn require_all_audiences(mut self, audiences: &[&str]) -> Self {
+        let audiences = audiences.iter().map(ToString::to_string).collect();
+        self.expected_claims.required_audiences = Some(audiences);
+        self
+    }
+
+    // Only accepts tokens whose client_id is one of `clients`, for APIs that
+    // should be reachable from specific OAuth clients alone.
+    pub fn allowed_clients(mut self, clients: &[&str]) -> Self {
+        let clients = clients.iter().map(ToString::to_string).collect();
+        self.expected_claims.allowed_clients = Some(clients);
+        self
+    }
+
+    // Runs every claim check instead of stopping at the first failure, and lists
+    // all violations in the error body.
+    pub fn report_all_violations(mut self, value: bool) -> Self {
+        self.expected_claims.report_all_violations = value;
+        self
+    }
+
+    pub fn scope_registry(mut self, value: ScopeRegistry) -> Self {
+        self.scope_registry = Some(value);
+        self
+    }
+
+    pub fn tenant_claim(mut self, name: &str, required: bool) -> Self {
+        self.tenant_claim = Some(TenantClaim {
+            name: name.to_string(),
+            required,
+        });
+        self
+    }
+
+    pub fn policy_engine(mut self, value: impl PolicyEngine + 'static) -> Self {
+        self.policy_engine = Some(Rc::new(value));
+        self
+    }
+
+    pub fn authorization_hook<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(Claims<AuthorizationClaims>, HttpRequest) -> Fut + 'static,
+        Fut: Future<Output = Result<(), String>> + 'static,
+    {
+        self.authorization_hook = Some(Rc::new(move |claims, req| Box::pin(f(claims, req))));
+        self
+    }
+
+    pub fn client_binding(mut self, value: ClientBinding) -> Self {
+        self.client_binding = Some(value);
+        self
+    }
+
+    pub fn validator(mut self, value: impl ClaimsValidator<AuthorizationClaims> + 'static) -> Self {
+        self.validators.push(Rc::new(value));
+        self
+    }
+
+    pub fn enrichment<F, Fut, T>(mut self, f: F) -> Self
+    where
+        F: Fn(Claims<AuthorizationClaims>, HttpRequest) -> Fut + 'static,
+        Fut: Future<Output = Result<T, EnrichmentError>> + 'static,
+        T: 'static,
+    {
+        self.enrichers.push(enricher(f));
+        self
+    }
+
+    // The longest matching prefix applies; other requests use the factory's own
+    // issuer and audience.
+    pub fn path_requirements(mut self, value: PathRequirements) -> Self {
+        self.path_requirements.push(value);
+        self
+    }
+
+    pub fn events(mut self, value: AuthEvents) -> Self {
+        self.events = Some(value);
+        self
+    }
 }
 
 impl<I: Issuer> Default for AuthorizationFactory<I> {
@@ -70,8 +226,17 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         let middleware = AuthorizationMiddleware {
             service: Rc::new(service),
-            enabled: Rc::new(self.enabled),
+            enabled: self.enabled.clone(),
             expected_claims: Rc::new(self.expected_claims.clone()),
+            scope_registry: Rc::new(self.scope_registry.clone()),
+            tenant_claim: Rc::new(self.tenant_claim.clone()),
+            policy_engine: self.policy_engine.clone(),
+            authorization_hook: self.authorization_hook.clone(),
+            client_binding: Rc::new(self.client_binding.clone()),
+            validators: Rc::new(self.validators.clone()),
+            enrichers: Rc::new(self.enrichers.clone()),
+            path_requirements: Rc::new(self.path_requirements.clone()),
+            events: self.events.clone(),
             phantom: Default::default(),
         };
         ready(Ok(middleware))
@@ -80,22 +245,311 @@ where
 
 pub struct AuthorizationMiddleware<I, S> {
     service: Rc<S>,
-    enabled: Rc<bool>,
+    enabled: RuntimeToggle,
     expected_claims: Rc<ExpectedClaims>,
+    scope_registry: Rc<Option<ScopeRegistry>>,
+    tenant_claim: Rc<Option<TenantClaim>>,
+    policy_engine: Option<Rc<dyn PolicyEngine>>,
+    authorization_hook: Option<AuthorizationHook>,
+    client_binding: Rc<Option<ClientBinding>>,
+    validators: Rc<Vec<Rc<dyn ClaimsValidator<AuthorizationClaims>>>>,
+    enrichers: Rc<Vec<Enricher>>,
+    path_requirements: Rc<Vec<PathRequirements>>,
+    events: Option<AuthEvents>,
     phantom: PhantomData<I>,
 }
 
+fn check(
+    condition: bool,
+    error: impl FnOnce() -> AuthorizationMiddlewareError,
+) -> Result<(), AuthorizationMiddlewareError> {
+    if condition {
+        return Ok(());
+    }
+    Err(logged(error()))
+}
+
+fn logged(e: AuthorizationMiddlewareError) -> AuthorizationMiddlewareError {
+    log::info!("{}", e);
+    e
+}
+
 fn require(condition: bool, message: &str) -> Result<(), AuthorizationMiddlewareError> {
-    condition
-        .then_some(true)
-        .ok_or(AuthorizationMiddlewareError::InvalidClaims(
-            message.to_string(),
-        ))
-        .map_err(|e| {
-            log::info!("{}", e);
-            e
-        })?;
-    Ok(())
+    check(condition, || {
+        AuthorizationMiddlewareError::InvalidClaims(message.to_string())
+    })
+}
+
+// Claim checks either stop at the first failure or, with report_all_violations,
+// run to the end so the response lists everything wrong with the token.
+struct Violations {
+    report_all: bool,
+    errors: Vec<AuthorizationMiddlewareError>,
+}
+
+impl Violations {
+    fn new(report_all: bool) -> Self {
+        Self {
+            report_all,
+            errors: Vec::new(),
+        }
+    }
+
+    fn check(
+        &mut self,
+        result: Result<(), AuthorizationMiddlewareError>,
+    ) -> Result<(), AuthorizationMiddlewareError> {
+        match result {
+            Err(e) if self.report_all => {
+                self.errors.push(e);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn finish(mut self) -> Result<(), AuthorizationMiddlewareError> {
+        match self.errors.len() {
+            0 => Ok(()),
+            1 => Err(self.errors.remove(0)),
+            _ => Err(AuthorizationMiddlewareError::ClaimViolations(self.errors)),
+        }
+    }
+}
+
+fn check_expiry(exp: u64, now: u64, grace: u64) -> Result<(), AuthorizationMiddlewareError> {
+    check(now <= exp.saturating_add(grace), || {
+        AuthorizationMiddlewareError::Expired { exp, now }
+    })
+}
+
+fn authorized_party_matches(
+    expected_claims: &ExpectedClaims,
+    expected_audiences: &[&str],
+    claims: &Claims<AuthorizationClaims>,
+) -> bool {
+    let azp = claims.get_claim::<String>("azp").ok().flatten();
+    expected_claims.accept_authorized_party
+        && azp.is_some_and(|azp| secure_contains(expected_audiences.iter().copied(), &azp))
+}
+
+fn issuer_matches<I: Issuer + ?Sized>(
+    issuer: &I,
+    expected_issuer: Option<&str>,
+    iss: &str,
+    strict: bool,
+) -> bool {
+    match (expected_issuer, strict) {
+        (Some(expected_issuer), true) => secure_eq(iss, expected_issuer),
+        (Some(expected_issuer), false) => {
+            secure_eq(&normalize_issuer(iss), &normalize_issuer(expected_issuer))
+        }
+        (None, true) => issuer.matches(iss),
+        (None, false) => {
+            secure_eq(&normalize_issuer(iss), &normalize_issuer(&issuer.url()))
+                || issuer.matches(iss)
+                || issuer_variants(iss).iter().any(|iss| issuer.matches(iss))
+        }
+    }
+}
+
+fn check_audiences(
+    required_audiences: &[String],
+    claims: &Claims<AuthorizationClaims>,
+) -> Result<(), AuthorizationMiddlewareError> {
+    let missing = required_audiences
+        .iter()
+        .filter(|audience| {
+            !claims
+                .aud
+                .as_ref()
+                .is_some_and(|aud| aud.contains(audience))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    // The required audiences are configuration, so only the log names them.
+    log::info!("Missing required audiences: {}", missing.join(", "));
+    Err(AuthorizationMiddlewareError::MissingAudiences { missing })
+}
+
+fn check_actor(
+    required_actors: &[String],
+    claims: &Claims<AuthorizationClaims>,
+) -> Result<(), AuthorizationMiddlewareError> {
+    let actor = claims.actor().ok().flatten();
+    require(actor.is_some(), "Actor is missing")?;
+    if required_actors.is_empty() {
+        return Ok(());
+    }
+    let sub = actor.and_then(|actor| actor.sub);
+    let allowed = sub.is_some_and(|sub| {
+        secure_contains(required_actors.iter().map(String::as_str), sub.as_str())
+    });
+    require(allowed, "Actor is not allowed")
+}
+
+fn check_client(
+    allowed_clients: &[String],
+    claims: &Claims<AuthorizationClaims>,
+) -> Result<(), AuthorizationMiddlewareError> {
+    let client_id = claims.client_id().ok().flatten();
+    require(client_id.is_some(), "Client is missing")?;
+    let allowed = client_id.is_some_and(|client_id| {
+        secure_contains(allowed_clients.iter().map(String::as_str), &client_id)
+    });
+    require(allowed, "Client is not allowed")
+}
+
+fn check_scopes(
+    scope_registry: &ScopeRegistry,
+    token: &AccessToken,
+) -> Result<(), AuthorizationMiddlewareError> {
+    let unknown_scopes = scope_registry.unknown_scopes(token.scopes());
+    if unknown_scopes.is_empty() {
+        return Ok(());
+    }
+    let unknown_scopes = unknown_scopes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ");
+    match scope_registry.unknown_scope_policy() {
+        UnknownScopePolicy::Log => {
+            log::warn!("Token contains unknown scopes: {}", unknown_scopes);
+            Ok(())
+        }
+        UnknownScopePolicy::Reject => require(
+            false,
+            &format!("Token contains unknown scopes: {unknown_scopes}"),
+        ),
+    }
+}
+
+// The token checks AuthorizationFactory applies before scopes and policies,
+// shared with authenticators that cannot be wrapped by it.
+pub(crate) fn check_claims<I: Issuer + ?Sized>(
+    issuer: &I,
+    expected_claims: &ExpectedClaims,
+    path_requirements: Option<&PathRequirements>,
+    validators: &[Rc<dyn ClaimsValidator<AuthorizationClaims>>],
+    claims: &Claims<AuthorizationClaims>,
+    grace: u64,
+) -> Result<(), AuthorizationMiddlewareError> {
+    let timestamp = Utc::now().timestamp() as u64;
+    let expected_audiences = match path_requirements.and_then(PathRequirements::expected_audience) {
+        Some(audience) => vec![audience],
+        None => expected_claims
+            .audiences
+            .iter()
+            .map(String::as_str)
+            .collect(),
+    };
+
+    let expected_issuer = path_requirements
+        .and_then(PathRequirements::expected_issuer)
+        .or(expected_claims.issuer.as_deref());
+    let mut violations = Violations::new(expected_claims.report_all_violations);
+    violations.check(check(
+        issuer_matches(
+            issuer,
+            expected_issuer,
+            &claims.iss,
+            expected_claims.strict_issuer,
+        ),
+        || AuthorizationMiddlewareError::IssuerMismatch {
+            expected: expected_issuer
+                .map(str::to_string)
+                .unwrap_or_else(|| issuer.url()),
+            actual: claims.iss.clone(),
+        },
+    ))?;
+    let missing_claims = expected_claims.claims_profile.missing_claims(claims);
+    violations.check(require(
+        missing_claims.is_empty(),
+        &format!("Missing required claims: {}", missing_claims.join(", ")),
+    ))?;
+    let aud = claims.aud.as_ref();
+    if let Some(aud) = aud.filter(|_| !expected_audiences.is_empty()) {
+        violations.check(check(
+            expected_audiences
+                .iter()
+                .any(|audience| aud.contains(audience))
+                || authorized_party_matches(expected_claims, &expected_audiences, claims),
+            || AuthorizationMiddlewareError::AudienceMismatch {
+                expected: expected_audiences.iter().map(ToString::to_string).collect(),
+                actual: aud.iter().cloned().collect(),
+            },
+        ))?;
+    }
+    if let Some(required_audiences) = &expected_claims.required_audiences {
+        violations.check(check_audiences(required_audiences, claims))?;
+    }
+    if let Some(iat) = claims.iat {
+        violations.check(require(timestamp >= iat, "Token issued for invalid time"))?;
+    }
+    violations.check(check_expiry(claims.exp, timestamp, grace))?;
+    violations.check(require(
+        expected_claims.subject_matches(claims),
+        "Subject does not match",
+    ))?;
+    let result = expected_claims
+        .check_matchers(claims)
+        .map_err(AuthorizationMiddlewareError::InvalidClaims);
+    violations.check(result.map_err(logged))?;
+    if let Some(required_actors) = &expected_claims.required_actors {
+        violations.check(check_actor(required_actors, claims))?;
+    }
+    if let Some(allowed_clients) = &expected_claims.allowed_clients {
+        violations.check(check_client(allowed_clients, claims))?;
+    }
+    for validator in validators {
+        let result = validator
+            .validate(claims)
+            .map_err(AuthorizationMiddlewareError::InvalidClaims);
+        violations.check(result.map_err(logged))?;
+    }
+    violations.finish()
+}
+
+// An issuer given as configuration, compared like the one IssuerFactory resolves.
+struct ConfiguredIssuer<'a>(&'a str);
+
+impl Issuer for ConfiguredIssuer<'_> {
+    fn url(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+pub(crate) fn check_configured_claims(
+    issuer: &str,
+    expected_claims: &ExpectedClaims,
+    claims: &Claims<AuthorizationClaims>,
+) -> Result<(), AuthorizationMiddlewareError> {
+    check_claims(
+        &ConfiguredIssuer(issuer),
+        expected_claims,
+        None,
+        &[],
+        claims,
+        0,
+    )
+}
+
+fn reasons(e: &AuthorizationMiddlewareError) -> Vec<String> {
+    match e {
+        AuthorizationMiddlewareError::ClaimViolations(errors) => {
+            errors.iter().map(ToString::to_string).collect()
+        }
+        e => vec![e.to_string()],
+    }
+}
+
+fn join_errors(errors: &[AuthorizationMiddlewareError]) -> String {
+    let errors = errors.iter().map(ToString::to_string).collect::<Vec<_>>();
+    errors.join("; ")
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -106,24 +560,117 @@ pub enum AuthorizationMiddlewareError {
     NoIssuer,
     #[error("invalid claims: {0}")]
     InvalidClaims(String),
+    // The expected values are configuration, so they are kept out of the
+    // message sent to clients and only carried in the fields.
+    #[error("invalid claims: Issuer does not match")]
+    IssuerMismatch { expected: String, actual: String },
+    #[error("invalid claims: Audience does not match")]
+    AudienceMismatch {
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+    #[error("invalid claims: Audience mismatch")]
+    MissingAudiences { missing: Vec<String> },
+    #[error("invalid claims: Token expired")]
+    Expired { exp: u64, now: u64 },
+    #[error("{} claim violations: {}", .0.len(), join_errors(.0))]
+    ClaimViolations(Vec<AuthorizationMiddlewareError>),
+    #[error("{0}")]
+    PolicyError(String),
+    #[error("policy denied request: {0}")]
+    PolicyDenied(String),
+    #[error("request vetoed: {0}")]
+    Vetoed(String),
+    #[error("insufficient scope: {0}")]
+    InsufficientScope(ScopeDiff),
+    #[error("enrichment failed: {0}")]
+    EnrichmentError(EnrichmentError),
 }
 
 impl ResponseError for AuthorizationMiddlewareError {
     fn status_code(&self) -> StatusCode {
         match self {
-            AuthorizationMiddlewareError::InvalidClaims(_) => StatusCode::FORBIDDEN,
+            AuthorizationMiddlewareError::InvalidClaims(_)
+            | AuthorizationMiddlewareError::IssuerMismatch { .. }
+            | AuthorizationMiddlewareError::AudienceMismatch { .. }
+            | AuthorizationMiddlewareError::MissingAudiences { .. }
+            | AuthorizationMiddlewareError::Expired { .. }
+            | AuthorizationMiddlewareError::ClaimViolations(_) => StatusCode::UNAUTHORIZED,
+            AuthorizationMiddlewareError::Vetoed(_) => StatusCode::FORBIDDEN,
+            AuthorizationMiddlewareError::PolicyDenied(_) => StatusCode::FORBIDDEN,
+            AuthorizationMiddlewareError::InsufficientScope(_) => StatusCode::FORBIDDEN,
+            AuthorizationMiddlewareError::EnrichmentError(e) => e.status_code(),
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
     fn error_response(&self) -> HttpResponse<BoxBody> {
-        let error_body = match self {
-            AuthorizationMiddlewareError::InvalidClaims(_) => {
-                forbidden_error_body("INVALID_CLAIMS", self)
+        self.respond(None)
+    }
+}
+
+impl MiddlewareError for AuthorizationMiddlewareError {
+    fn error_body(&self) -> ErrorBody {
+        match self {
+            AuthorizationMiddlewareError::InvalidClaims(_)
+            | AuthorizationMiddlewareError::IssuerMismatch { .. }
+            | AuthorizationMiddlewareError::AudienceMismatch { .. }
+            | AuthorizationMiddlewareError::MissingAudiences { .. }
+            | AuthorizationMiddlewareError::Expired { .. } => {
+                unauthorized_error_body("INVALID_CLAIMS", self)
             }
-            _ => internal_server_error_body("INVALID", self),
-        };
-        HttpResponseBuilder::new(self.status_code()).json(error_body)
+            AuthorizationMiddlewareError::ClaimViolations(_) => {
+                unauthorized_error_body("INVALID_CLAIMS", self).details(json!({
+                    "violations": reasons(self),
+                }))
+            }
+            AuthorizationMiddlewareError::Vetoed(_) => forbidden_error_body("REQUEST_VETOED", self),
+            AuthorizationMiddlewareError::PolicyDenied(_) => {
+                forbidden_error_body("POLICY_DENIED", self)
+            }
+            AuthorizationMiddlewareError::InsufficientScope(diff) => {
+                forbidden_error_body("INSUFFICIENT_SCOPE", self).details(json!({
+                    "required_scopes": diff.required,
+                    "present_scopes": diff.present,
+                    "missing_scopes": diff.missing(),
+                }))
+            }
+            AuthorizationMiddlewareError::PolicyError(_) => {
+                internal_server_error_body("POLICY_ERROR", self)
+            }
+            AuthorizationMiddlewareError::EnrichmentError(e) => {
+                status_error_body(e.status_code(), "ENRICHMENT_FAILED", self)
+            }
+            AuthorizationMiddlewareError::NoToken => internal_server_error_body("NO_TOKEN", self),
+            AuthorizationMiddlewareError::NoIssuer => internal_server_error_body("NO_ISSUER", self),
+        }
+    }
+
+    fn challenge(&self) -> Option<BearerChallenge> {
+        match self {
+            AuthorizationMiddlewareError::InvalidClaims(_)
+            | AuthorizationMiddlewareError::IssuerMismatch { .. }
+            | AuthorizationMiddlewareError::AudienceMismatch { .. }
+            | AuthorizationMiddlewareError::MissingAudiences { .. }
+            | AuthorizationMiddlewareError::Expired { .. }
+            | AuthorizationMiddlewareError::ClaimViolations(_) => {
+                Some(BearerChallenge::new().error(BearerError::InvalidToken))
+            }
+            AuthorizationMiddlewareError::Vetoed(_)
+            | AuthorizationMiddlewareError::PolicyDenied(_) => {
+                Some(BearerChallenge::new().error(BearerError::InsufficientScope))
+            }
+            AuthorizationMiddlewareError::InsufficientScope(diff) => {
+                let required = diff.required.iter().map(ToString::to_string);
+                let required = required.collect::<Vec<_>>().join(" ");
+                Some(
+                    BearerChallenge::new()
+                        .error(BearerError::InsufficientScope)
+                        .scope(required),
+                )
+            }
+            _ => None,
+        }
     }
 }
 
@@ -142,35 +689,141 @@ where
         let service = self.service.clone();
         let enabled = self.enabled.clone();
         let expected_claims = self.expected_claims.clone();
+        let scope_registry = self.scope_registry.clone();
+        let tenant_claim = self.tenant_claim.clone();
+        let policy_engine = self.policy_engine.clone();
+        let authorization_hook = self.authorization_hook.clone();
+        let client_binding = self.client_binding.clone();
+        let validators = self.validators.clone();
+        let enrichers = self.enrichers.clone();
+        let path_requirements = self.path_requirements.clone();
+        let events = self.events.clone();
         Box::pin(async move {
-            if !*enabled {
+            if !enabled.is_enabled() {
                 let res = service.call(req).await?;
                 return Ok(res);
             }
 
-            let extensions = req.extensions();
-            let issuer = extensions
-                .get::<I>()
-                .ok_or(AuthorizationMiddlewareError::NoIssuer)?
-                .url();
-            let token = extensions
-                .get::<AccessToken>()
-                .ok_or(AuthorizationMiddlewareError::NoToken)?
-                .clone();
-            drop(extensions);
-
-            let claims = token.claims().clone();
-            let Claims { iss, aud, .. } = claims;
-            let timestamp = Utc::now().timestamp() as u64;
-
-            require(iss == issuer, "Issuer does not match")?;
-            require(
-                aud.into_iter()
-                    .any(|aud| aud == expected_claims.expected_audience),
-                "Audience does not match",
-            )?;
-            require(timestamp >= claims.iat, "Token issued for invalid time")?;
-            require(timestamp <= claims.exp, "Token is expired")?;
+            let started = Instant::now();
+            let context = error_context(&req);
+            let authorized: Result<(), AuthorizationMiddlewareError> = async {
+                let (issuer, token) = {
+                    let extensions = req.extensions();
+                    let issuer = extensions
+                        .get::<I>()
+                        .ok_or_else(|| {
+                            miswired(
+                                AuthorizationMiddlewareError::NoIssuer,
+                                "IssuerFactory",
+                                "AuthorizationFactory",
+                            )
+                        })?
+                        .clone();
+                    let token = extensions
+                        .get::<AccessToken>()
+                        .ok_or_else(|| {
+                            miswired(
+                                AuthorizationMiddlewareError::NoToken,
+                                "An enabled JWTFactory",
+                                "AuthorizationFactory",
+                            )
+                        })?
+                        .clone();
+                    (issuer, token)
+                };
+
+                let claims = token.claims();
+                // The router matches the decoded path, so "/%61dmin" must be
+                // held to the "/admin" requirements too.
+                let path = req.match_info().as_str();
+                let path_requirements = path_requirements::select(&path_requirements, path);
+                // JWTFactory only sets a grace window on safe methods.
+                let grace = match req.extensions().get::<ExpiryGrace>() {
+                    Some(ExpiryGrace(grace)) => grace.as_secs(),
+                    None => 0,
+                };
+                check_claims(
+                    &issuer,
+                    &expected_claims,
+                    path_requirements,
+                    &validators,
+                    claims,
+                    grace,
+                )?;
+                if let Some(scope_registry) = scope_registry.as_ref() {
+                    check_scopes(scope_registry, &token)?;
+                }
+                if let Some(diff) = path_requirements.and_then(|r| r.missing_scopes(&token)) {
+                    let e = AuthorizationMiddlewareError::InsufficientScope(diff);
+                    log::info!("{}", e);
+                    return Err(e);
+                }
+                if let Some(client_binding) = client_binding.as_ref() {
+                    client_binding
+                        .check(claims, &req)
+                        .map_err(AuthorizationMiddlewareError::InvalidClaims)
+                        .map_err(logged)?;
+                }
+                if let Some(tenant_claim) = tenant_claim.as_ref() {
+                    let tenant = Tenant::from_claims(claims, &tenant_claim.name);
+                    require(
+                        !tenant_claim.required || tenant.is_some(),
+                        "Tenant is missing",
+                    )?;
+                    req.extensions_mut().insert(tenant);
+                }
+                if let Some(policy_engine) = policy_engine.as_ref() {
+                    let request = PolicyRequest {
+                        subject: claims.sub.as_ref().map(|sub| sub.as_str()),
+                        scopes: token.scopes(),
+                        method: req.method().as_str(),
+                        path,
+                        claims,
+                    };
+                    let decision = policy_engine
+                        .decide(&request)
+                        .map_err(|e| AuthorizationMiddlewareError::PolicyError(e.to_string()))?;
+                    if let PolicyDecision::Deny(reason) = decision {
+                        let e = AuthorizationMiddlewareError::PolicyDenied(reason);
+                        log::info!("{}", e);
+                        return Err(e);
+                    }
+                }
+                if let Some(authorization_hook) = authorization_hook.as_ref() {
+                    authorization_hook(claims.clone(), req.request().clone())
+                        .await
+                        .map_err(AuthorizationMiddlewareError::Vetoed)
+                        .map_err(|e| {
+                            log::info!("{}", e);
+                            e
+                        })?;
+                }
+                for enricher in enrichers.iter() {
+                    enricher(claims.clone(), req.request().clone())
+                        .await
+                        .map_err(AuthorizationMiddlewareError::EnrichmentError)
+                        .map_err(|e| {
+                            log::info!("{}", e);
+                            e
+                        })?;
+                }
+                Ok(())
+            }
+            .await;
+            let outcome = ValidationOutcome::new(
+                req.extensions().contains::<AccessToken>(),
+                authorized.is_ok(),
+                authorized.as_ref().err().map(reasons).unwrap_or_default(),
+            );
+            req.extensions_mut().insert(outcome);
+            if let Some(events) = events.as_ref() {
+                let (kind, reason) = match &authorized {
+                    Ok(()) => (AuthEventKind::Authorized, None),
+                    Err(e) => (AuthEventKind::Denied, Some(e.to_string())),
+                };
+                events.emit(AuthEvent::new(kind, &req, reason, started));
+            }
+            authorized.map_err(|e| CorrelatedError::new(e, &context))?;
             let res = service.call(req).await?;
             Ok(res)
         })
@@ -178,3 +831,491 @@ where
 
     forward_ready!(service);
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use actix_web::{
+        dev::Service as _,
+        test::{call_and_read_body, init_service, TestRequest},
+        web, App,
+    };
+    use jsonwebtoken::Header;
+
+    use super::*;
+    use crate::{token::Token, Scope};
+
+    #[derive(Clone)]
+    struct TestIssuer;
+
+    impl Issuer for TestIssuer {
+        fn url(&self) -> String {
+            "issuer".to_string()
+        }
+    }
+
+    fn token() -> AccessToken {
+        let aud = vec!["audience".to_string()];
+        let extension = AuthorizationClaims {
+            scopes: vec![Scope::new("read", "projects")],
+        };
+        let mut claims = Claims::new(
+            "issuer",
+            "subject",
+            &aud,
+            Duration::from_secs(60),
+            extension,
+        );
+        claims
+            .additional
+            .insert("azp".to_string(), serde_json::json!("client"));
+        claims
+            .additional
+            .insert("client_id".to_string(), serde_json::json!("client"));
+        Token::new(Header::default(), claims)
+    }
+
+    async fn call(factory: AuthorizationFactory<TestIssuer>, path: &str) -> StatusCode {
+        let app = init_service(
+            App::new()
+                .wrap(factory)
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(TestIssuer);
+                    req.extensions_mut().insert(token());
+                    srv.call(req)
+                })
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+        let req = TestRequest::get().uri(path).to_request();
+        match app.call(req).await {
+            Ok(res) => res.status(),
+            Err(e) => e.as_response_error().status_code(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn missing_token_keeps_wiring_hints_out_of_the_body() {
+        let app = init_service(
+            App::new()
+                .wrap(AuthorizationFactory::<TestIssuer>::new(
+                    "audience".to_string(),
+                ))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(TestIssuer);
+                    srv.call(req)
+                })
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+        let req = TestRequest::get().uri("/projects").to_request();
+        let error = app.call(req).await.expect_err("expected error");
+        let response = error.error_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .expect("expected body");
+        assert!(!String::from_utf8_lossy(&body).contains("JWTFactory"));
+    }
+
+    #[actix_web::test]
+    async fn valid_token_is_authorized() {
+        let factory = AuthorizationFactory::new("audience".to_string());
+        assert_eq!(call(factory, "/projects").await, StatusCode::OK);
+    }
+
+    struct DenyAdmin;
+
+    impl PolicyEngine for DenyAdmin {
+        fn decide(
+            &self,
+            request: &PolicyRequest<'_>,
+        ) -> Result<PolicyDecision, crate::middleware::policy::PolicyError> {
+            match request.path == "/admin" {
+                true => Ok(PolicyDecision::Deny("admins only".to_string())),
+                false => Ok(PolicyDecision::Allow),
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn policy_denial_is_forbidden() {
+        let factory = AuthorizationFactory::new("audience".to_string()).policy_engine(DenyAdmin);
+        assert_eq!(call(factory, "/projects").await, StatusCode::OK);
+        let factory = AuthorizationFactory::new("audience".to_string()).policy_engine(DenyAdmin);
+        assert_eq!(call(factory, "/admin").await, StatusCode::FORBIDDEN);
+        let factory = AuthorizationFactory::new("audience".to_string()).policy_engine(DenyAdmin);
+        assert_eq!(call(factory, "/%61dmin").await, StatusCode::FORBIDDEN);
+        let response =
+            AuthorizationMiddlewareError::PolicyDenied("admins only".to_string()).error_response();
+        let challenge = response
+            .headers()
+            .get(actix_web::http::header::WWW_AUTHENTICATE)
+            .expect("expected challenge");
+        assert_eq!(
+            challenge,
+            "Bearer error=\"insufficient_scope\", error_description=\"policy denied request: admins only\""
+        );
+    }
+
+    #[actix_web::test]
+    async fn wrong_audience_is_unauthorized() {
+        let factory = AuthorizationFactory::new("other".to_string());
+        assert_eq!(call(factory, "/projects").await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn toggle_switches_enforcement_at_runtime() {
+        let toggle = RuntimeToggle::new(true);
+        let factory =
+            AuthorizationFactory::<TestIssuer>::new("other".to_string()).toggle(toggle.clone());
+        let app = init_service(
+            App::new()
+                .wrap(factory)
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(TestIssuer);
+                    req.extensions_mut().insert(token());
+                    srv.call(req)
+                })
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+        let status = |res: Result<ServiceResponse, Error>| match res {
+            Ok(res) => res.status(),
+            Err(e) => e.as_response_error().status_code(),
+        };
+        let req = TestRequest::get().uri("/projects").to_request();
+        assert_eq!(status(app.call(req).await), StatusCode::UNAUTHORIZED);
+        toggle.set(false);
+        let req = TestRequest::get().uri("/projects").to_request();
+        assert_eq!(status(app.call(req).await), StatusCode::OK);
+    }
+
+    #[test]
+    fn issuer_comparison_is_normalized_unless_strict() {
+        struct SlashedIssuer;
+
+        impl Issuer for SlashedIssuer {
+            fn url(&self) -> String {
+                "https://issuer.example.com/".to_string()
+            }
+        }
+
+        struct MixedCaseIssuer;
+
+        impl Issuer for MixedCaseIssuer {
+            fn url(&self) -> String {
+                "https://Login.Example.com/".to_string()
+            }
+        }
+
+        let iss = "HTTPS://Issuer.example.com";
+        assert!(issuer_matches(&SlashedIssuer, None, iss, false));
+        assert!(!issuer_matches(&SlashedIssuer, None, iss, true));
+        let iss = "https://login.example.com";
+        assert!(issuer_matches(&MixedCaseIssuer, None, iss, false));
+        assert!(!issuer_matches(&MixedCaseIssuer, None, iss, true));
+        let expected = Some("https://issuer.example.com");
+        assert!(issuer_matches(
+            &SlashedIssuer,
+            expected,
+            "https://issuer.example.com/",
+            false
+        ));
+        assert!(!issuer_matches(
+            &SlashedIssuer,
+            expected,
+            "https://issuer.example.com/",
+            true
+        ));
+    }
+
+    #[actix_web::test]
+    async fn issuer_templates_match_the_issuers_of_their_tenants() {
+        let template = crate::IssuerTemplate::new("https://login.example.com/{tenantid}/v2.0");
+        let iss = "https://login.example.com/tenant-a/v2.0/";
+        assert!(issuer_matches(&template, None, iss, false));
+        assert!(!issuer_matches(&template, None, iss, true));
+        let iss = "https://login.example.com/a/b/v2.0";
+        assert!(!issuer_matches(&template, None, iss, false));
+    }
+
+    #[actix_web::test]
+    async fn all_required_audiences_must_be_present() {
+        let factory =
+            AuthorizationFactory::new("audience".to_string()).require_all_audiences(&["audience"]);
+        assert_eq!(call(factory, "/projects").await, StatusCode::OK);
+        let factory = AuthorizationFactory::new("audience".to_string())
+            .require_all_audiences(&["audience", "billing"]);
+        assert_eq!(call(factory, "/projects").await, StatusCode::UNAUTHORIZED);
+        let error = AuthorizationMiddlewareError::MissingAudiences {
+            missing: vec!["billing".to_string()],
+        };
+        assert!(!error.to_string().contains("billing"));
+    }
+
+    #[actix_web::test]
+    async fn expected_claims_check_subject_and_claim_matchers() {
+        let expected_claims = ExpectedClaims::new()
+            .audiences(&["other", "audience"])
+            .issuer("issuer")
+            .subject_pattern(regex::Regex::new("^subject$").unwrap())
+            .claim_equals("azp", "client");
+        let factory = AuthorizationFactory::with_expected_claims(expected_claims);
+        assert_eq!(call(factory, "/projects").await, StatusCode::OK);
+        let expected_claims = ExpectedClaims::new()
+            .audience("audience")
+            .claim_equals("gty", "client-credentials");
+        let factory = AuthorizationFactory::with_expected_claims(expected_claims);
+        assert_eq!(call(factory, "/projects").await, StatusCode::UNAUTHORIZED);
+        let expected_claims = ExpectedClaims::new().issuer("https://other.example.com");
+        let factory = AuthorizationFactory::with_expected_claims(expected_claims);
+        assert_eq!(call(factory, "/projects").await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn audience_mismatch_carries_claim_values() {
+        let app = init_service(
+            App::new()
+                .wrap(AuthorizationFactory::<TestIssuer>::new("other".to_string()))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(TestIssuer);
+                    req.extensions_mut().insert(token());
+                    srv.call(req)
+                })
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+        let req = TestRequest::get().uri("/projects").to_request();
+        let error = app.call(req).await.expect_err("expected error");
+        let response = error.error_response();
+        let challenge = response
+            .headers()
+            .get(actix_web::http::header::WWW_AUTHENTICATE);
+        assert!(!format!("{challenge:?}").contains("other"));
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .expect("expected body");
+        assert!(!String::from_utf8_lossy(&body).contains("other"));
+        let error = error
+            .as_error::<CorrelatedError<AuthorizationMiddlewareError>>()
+            .expect("expected authorization error");
+        assert!(matches!(
+            error.error(),
+            AuthorizationMiddlewareError::AudienceMismatch { expected, actual }
+                if expected == &["other"] && actual == &["audience"]
+        ));
+    }
+
+    #[test]
+    fn violations_are_collected_only_when_reporting_all() {
+        let invalid = || require(false, "Subject does not match");
+        let mut violations = Violations::new(false);
+        assert!(violations.check(invalid()).is_err());
+
+        let mut violations = Violations::new(true);
+        assert!(violations.check(invalid()).is_ok());
+        assert!(violations.check(check_expiry(100, 200, 0)).is_ok());
+        assert!(violations.check(Ok(())).is_ok());
+        let error = violations.finish().expect_err("expected violations");
+        assert!(matches!(
+            &error,
+            AuthorizationMiddlewareError::ClaimViolations(errors) if errors.len() == 2
+        ));
+        assert!(error.to_string().starts_with("2 claim violations"));
+        let body = serde_json::to_value(error.error_body()).expect("expected body");
+        let violations = body["details"]["violations"].as_array();
+        assert_eq!(violations.map(Vec::len), Some(2));
+    }
+
+    #[actix_web::test]
+    async fn outcome_is_visible_to_outer_middleware_on_failure() {
+        let observed = Rc::new(std::cell::RefCell::new(None));
+        let recorder = observed.clone();
+        let app = init_service(
+            App::new()
+                .wrap(AuthorizationFactory::<TestIssuer>::new("other".to_string()))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(TestIssuer);
+                    req.extensions_mut().insert(token());
+                    srv.call(req)
+                })
+                .wrap_fn(move |req, srv| {
+                    let request = req.request().clone();
+                    let recorder = recorder.clone();
+                    let response = srv.call(req);
+                    async move {
+                        let response = response.await;
+                        let outcome = request.extensions().get::<ValidationOutcome>().cloned();
+                        *recorder.borrow_mut() = outcome;
+                        response
+                    }
+                })
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+        let req = TestRequest::get().uri("/projects").to_request();
+        assert!(app.call(req).await.is_err());
+        let outcome = observed.borrow_mut().take().expect("expected outcome");
+        assert!(outcome.decoded);
+        assert!(!outcome.validated);
+        assert!(outcome.reasons[0].contains("Audience"));
+    }
+
+    #[test]
+    fn expiry_reports_exp_and_now() {
+        assert!(check_expiry(100, 100, 0).is_ok());
+        assert!(check_expiry(100, 130, 30).is_ok());
+        assert!(matches!(
+            check_expiry(100, 131, 30),
+            Err(AuthorizationMiddlewareError::Expired { exp: 100, now: 131 })
+        ));
+    }
+
+    #[actix_web::test]
+    async fn decisions_are_broadcast_as_events() {
+        let events = AuthEvents::new(8);
+        let mut receiver = events.subscribe();
+        let factory = AuthorizationFactory::new("other".to_string()).events(events);
+        assert_eq!(call(factory, "/projects").await, StatusCode::UNAUTHORIZED);
+        let event = receiver.recv().await.expect("expected event");
+        assert_eq!(event.kind, AuthEventKind::Denied);
+        assert_eq!(event.sub.as_deref(), Some("subject"));
+        assert_eq!(event.iss.as_deref(), Some("issuer"));
+        assert!(event
+            .reason
+            .is_some_and(|reason| reason.contains("Audience")));
+    }
+
+    #[actix_web::test]
+    async fn only_allowed_clients_are_authorized() {
+        let factory =
+            AuthorizationFactory::new("audience".to_string()).allowed_clients(&["client"]);
+        assert_eq!(call(factory, "/projects").await, StatusCode::OK);
+        let factory = AuthorizationFactory::new("audience".to_string()).allowed_clients(&["other"]);
+        assert_eq!(call(factory, "/projects").await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn authorized_party_can_stand_in_for_audience() {
+        let factory = AuthorizationFactory::new("client".to_string()).accept_authorized_party(true);
+        assert_eq!(call(factory, "/projects").await, StatusCode::OK);
+        let factory = AuthorizationFactory::new("other".to_string()).accept_authorized_party(true);
+        assert_eq!(call(factory, "/projects").await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn authorization_hook_can_veto_requests() {
+        let factory = AuthorizationFactory::new("audience".to_string()).authorization_hook(
+            |claims, req| async move {
+                let owner = req.path().trim_start_matches("/users/").to_string();
+                match claims.sub.map(|sub| sub.to_string()) == Some(owner) {
+                    true => Ok(()),
+                    false => Err("not the owner".to_string()),
+                }
+            },
+        );
+        assert_eq!(call(factory, "/users/subject").await, StatusCode::OK);
+        let factory = AuthorizationFactory::new("audience".to_string())
+            .authorization_hook(|_, _| async move { Err("never".to_string()) });
+        assert_eq!(call(factory, "/users/other").await, StatusCode::FORBIDDEN);
+    }
+
+    #[actix_web::test]
+    async fn path_requirements_override_audience_and_add_scopes() {
+        let admin = PathRequirements::new("/admin")
+            .audience("admin")
+            .scope(Scope::new("manage", "users"));
+        let factory = AuthorizationFactory::new("audience".to_string()).path_requirements(admin);
+        assert_eq!(call(factory, "/projects").await, StatusCode::OK);
+        let admin = PathRequirements::new("/admin").audience("admin");
+        let factory = AuthorizationFactory::new("audience".to_string()).path_requirements(admin);
+        assert_eq!(
+            call(factory, "/admin/users").await,
+            StatusCode::UNAUTHORIZED
+        );
+        let admin = PathRequirements::new("/admin").scope(Scope::new("manage", "users"));
+        let factory = AuthorizationFactory::new("audience".to_string()).path_requirements(admin);
+        assert_eq!(call(factory, "/admin/users").await, StatusCode::FORBIDDEN);
+        let admin = PathRequirements::new("/admin").scope(Scope::new("read", "projects"));
+        let factory = AuthorizationFactory::new("audience".to_string()).path_requirements(admin);
+        assert_eq!(call(factory, "/admin/users").await, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn path_requirements_match_percent_encoded_paths() {
+        let admin = PathRequirements::new("/admin").audience("admin");
+        let factory = AuthorizationFactory::new("audience".to_string()).path_requirements(admin);
+        assert_eq!(
+            call(factory, "/%61dmin/users").await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    struct RequireClaim(&'static str);
+
+    impl ClaimsValidator<AuthorizationClaims> for RequireClaim {
+        fn validate(&self, claims: &Claims<AuthorizationClaims>) -> Result<(), String> {
+            match claims.additional.contains_key(self.0) {
+                true => Ok(()),
+                false => Err(format!("Missing claim {}", self.0)),
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn claims_validators_can_reject_tokens() {
+        let factory = AuthorizationFactory::new("audience".to_string()).validator(
+            |claims: &Claims<AuthorizationClaims>| match claims.sub.is_some() {
+                true => Ok(()),
+                false => Err("Missing subject".to_string()),
+            },
+        );
+        assert_eq!(call(factory, "/projects").await, StatusCode::OK);
+        let factory =
+            AuthorizationFactory::new("audience".to_string()).validator(RequireClaim("department"));
+        assert_eq!(call(factory, "/projects").await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct UserRecord(String);
+
+    #[actix_web::test]
+    async fn enrichment_output_is_inserted_into_extensions() {
+        let factory = AuthorizationFactory::<TestIssuer>::new("audience".to_string()).enrichment(
+            |claims, _| async move {
+                let sub = claims.sub.map(|sub| sub.to_string()).unwrap_or_default();
+                Ok(UserRecord(sub))
+            },
+        );
+        let app = init_service(
+            App::new()
+                .wrap(factory)
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(TestIssuer);
+                    req.extensions_mut().insert(token());
+                    srv.call(req)
+                })
+                .default_service(web::to(|req: HttpRequest| async move {
+                    let extensions = req.extensions();
+                    let user = extensions
+                        .get::<UserRecord>()
+                        .expect("expected user record");
+                    HttpResponse::Ok().body(user.0.clone())
+                })),
+        )
+        .await;
+        let req = TestRequest::get().uri("/").to_request();
+        let body = call_and_read_body(&app, req).await;
+        assert_eq!(body, "subject");
+    }
+
+    #[actix_web::test]
+    async fn enrichment_failure_uses_configured_status() {
+        let factory = AuthorizationFactory::new("audience".to_string()).enrichment(|_, _| async {
+            Err::<UserRecord, _>(EnrichmentError::new("unknown user").status(StatusCode::NOT_FOUND))
+        });
+        assert_eq!(call(factory, "/projects").await, StatusCode::NOT_FOUND);
+    }
+}
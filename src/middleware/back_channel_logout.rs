@@ -0,0 +1,268 @@
+use std::{future::Future, rc::Rc};
+
+use actix_web::{
+    body::BoxBody, http::StatusCode, web, HttpMessage, HttpRequest, HttpResponse, ResponseError,
+};
+use futures::future::LocalBoxFuture;
+use jsonwebtoken::jwk::JwkSet;
+use serde_json::{Map, Value};
+
+use crate::{
+    middleware::error_response::{
+        internal_server_error_body, miswired, status_error_body, ErrorBody, MiddlewareError,
+    },
+    Claims, EncodedToken, Issuer,
+};
+
+const BACK_CHANNEL_LOGOUT_EVENT: &str = "http://schemas.openid.net/event/backchannel-logout";
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LogoutClaims {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    #[serde(default)]
+    pub events: Map<String, Value>,
+}
+
+// The session the issuer ended. At least one of sub and sid is present; jti lets
+// the callback reject replayed logout tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogoutSession {
+    pub iss: String,
+    pub sub: Option<String>,
+    pub sid: Option<String>,
+    pub jti: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackChannelLogoutError {
+    #[error("BackChannelLogout must be registered with App::app_data")]
+    NotConfigured,
+    #[error("no issuer")]
+    NoIssuer,
+    #[error("no JWK set")]
+    NoJwkSet,
+    #[error("invalid logout token: {0}")]
+    InvalidToken(String),
+    #[error("session invalidation failed: {0}")]
+    InvalidationFailed(String),
+}
+
+impl ResponseError for BackChannelLogoutError {
+    // The spec asks for 400 whenever the logout failed, including when the
+    // session could not be invalidated.
+    fn status_code(&self) -> StatusCode {
+        match self {
+            BackChannelLogoutError::InvalidToken(_)
+            | BackChannelLogoutError::InvalidationFailed(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        self.respond(None)
+    }
+}
+
+impl MiddlewareError for BackChannelLogoutError {
+    fn error_body(&self) -> ErrorBody {
+        match self {
+            BackChannelLogoutError::InvalidToken(_) => {
+                status_error_body(StatusCode::BAD_REQUEST, "INVALID_LOGOUT_TOKEN", self)
+            }
+            BackChannelLogoutError::InvalidationFailed(_) => {
+                status_error_body(StatusCode::BAD_REQUEST, "LOGOUT_FAILED", self)
+            }
+            BackChannelLogoutError::NotConfigured => {
+                internal_server_error_body("NO_LOGOUT_HANDLER", self)
+            }
+            BackChannelLogoutError::NoIssuer => internal_server_error_body("NO_ISSUER", self),
+            BackChannelLogoutError::NoJwkSet => internal_server_error_body("NO_JWK_SET", self),
+        }
+    }
+}
+
+type InvalidateSession = Rc<dyn Fn(LogoutSession) -> LocalBoxFuture<'static, Result<(), String>>>;
+
+// Registered with App::app_data for back_channel_logout. `client_id` is the
+// audience logout tokens must be issued to; `invalidate` ends the local session,
+// e.g. by revoking the tokens issued to it.
+#[derive(Clone)]
+pub struct BackChannelLogout {
+    client_id: String,
+    invalidate: InvalidateSession,
+}
+
+impl BackChannelLogout {
+    pub fn new<F, Fut>(client_id: &str, invalidate: F) -> Self
+    where
+        F: Fn(LogoutSession) -> Fut + 'static,
+        Fut: Future<Output = Result<(), String>> + 'static,
+    {
+        Self {
+            client_id: client_id.to_string(),
+            invalidate: Rc::new(move |session| Box::pin(invalidate(session))),
+        }
+    }
+
+    pub fn validate(
+        &self,
+        logout_token: &str,
+        issuer: &impl Issuer,
+        jwk_set: &JwkSet,
+    ) -> Result<LogoutSession, BackChannelLogoutError> {
+        let encoded: EncodedToken<LogoutClaims> = logout_token.to_string().into();
+        let token = encoded
+            .decode(jwk_set)
+            .map_err(|e| BackChannelLogoutError::InvalidToken(e.to_string()))?;
+        logout_session(token.claims(), issuer, &self.client_id)
+    }
+}
+
+// OpenID Connect Back-Channel Logout 1.0 section 2.6.
+fn logout_session(
+    claims: &Claims<LogoutClaims>,
+    issuer: &impl Issuer,
+    client_id: &str,
+) -> Result<LogoutSession, BackChannelLogoutError> {
+    let require = |condition: bool, message: &str| match condition {
+        true => Ok(()),
+        false => Err(BackChannelLogoutError::InvalidToken(message.to_string())),
+    };
+    require(issuer.matches(&claims.iss), "Issuer does not match")?;
+    let aud = claims.aud.as_ref();
+    require(
+        aud.is_some_and(|aud| aud.contains(client_id)),
+        "Audience does not match",
+    )?;
+    require(claims.iat.is_some(), "Missing required claims: iat")?;
+    let event = claims.extension.events.get(BACK_CHANNEL_LOGOUT_EVENT);
+    require(
+        event.is_some_and(Value::is_object),
+        "Missing back-channel logout event",
+    )?;
+    require(
+        !claims.additional.contains_key("nonce"),
+        "Logout tokens must not contain a nonce",
+    )?;
+    let sub = claims.sub.as_ref().map(|sub| sub.as_str().to_string());
+    let sid = claims.extension.sid.clone();
+    require(
+        sub.is_some() || sid.is_some(),
+        "Missing required claims: sub or sid",
+    )?;
+    let jti = claims.extension.jti.clone().unwrap_or_default();
+    require(!jti.is_empty(), "Missing required claims: jti")?;
+    Ok(LogoutSession {
+        iss: claims.iss.clone(),
+        sub,
+        sid,
+        jti,
+    })
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct LogoutRequest {
+    pub logout_token: String,
+}
+
+// Route handler for back-channel logout requests, for a route wrapped by
+// IssuerFactory and JwkSetFactory:
+// `.route("/backchannel-logout", web::post().to(back_channel_logout::<MyIssuer>))`.
+pub async fn back_channel_logout<I: Issuer + 'static>(
+    req: HttpRequest,
+    form: web::Form<LogoutRequest>,
+) -> Result<HttpResponse, BackChannelLogoutError> {
+    let result = async {
+        let logout = req
+            .app_data::<BackChannelLogout>()
+            .ok_or(BackChannelLogoutError::NotConfigured)?;
+        let session = {
+            let extensions = req.extensions();
+            let issuer = extensions.get::<I>().ok_or_else(|| {
+                miswired(
+                    BackChannelLogoutError::NoIssuer,
+                    "IssuerFactory",
+                    "the logout handler",
+                )
+            })?;
+            let jwk_set = extensions.get::<JwkSet>().ok_or_else(|| {
+                miswired(
+                    BackChannelLogoutError::NoJwkSet,
+                    "JwkSetFactory",
+                    "the logout handler",
+                )
+            })?;
+            logout.validate(&form.logout_token, issuer, jwk_set)?
+        };
+        (logout.invalidate)(session)
+            .await
+            .map_err(BackChannelLogoutError::InvalidationFailed)
+    }
+    .await;
+    result.map_err(|e| {
+        log::info!("{}", e);
+        e
+    })?;
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "no-store"))
+        .finish())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+
+    struct TestIssuer;
+
+    impl Issuer for TestIssuer {
+        fn url(&self) -> String {
+            "issuer".to_string()
+        }
+    }
+
+    fn claims() -> Claims<LogoutClaims> {
+        let mut events = Map::new();
+        events.insert(BACK_CHANNEL_LOGOUT_EVENT.to_string(), json!({}));
+        let extension = LogoutClaims {
+            jti: Some("jti".to_string()),
+            sid: Some("sid".to_string()),
+            events,
+        };
+        let aud = vec!["client".to_string()];
+        Claims::new(
+            "issuer",
+            "subject",
+            &aud,
+            Duration::from_secs(60),
+            extension,
+        )
+    }
+
+    #[test]
+    fn valid_logout_token_names_the_session() {
+        let session = logout_session(&claims(), &TestIssuer, "client").expect("expected session");
+        assert_eq!(session.sub.as_deref(), Some("subject"));
+        assert_eq!(session.sid.as_deref(), Some("sid"));
+        assert_eq!(session.jti, "jti");
+    }
+
+    #[test]
+    fn logout_tokens_require_event_audience_and_no_nonce() {
+        assert!(logout_session(&claims(), &TestIssuer, "other").is_err());
+        let mut without_event = claims();
+        without_event.extension.events.clear();
+        assert!(logout_session(&without_event, &TestIssuer, "client").is_err());
+        let mut with_nonce = claims();
+        with_nonce
+            .additional
+            .insert("nonce".to_string(), json!("nonce"));
+        assert!(logout_session(&with_nonce, &TestIssuer, "client").is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use actix_web::guard::{Guard, GuardContext};
+use serde_json::Value;
+
+use crate::{middleware::expected_claims::values_match, AccessToken};
+
+#[derive(Debug, Clone, PartialEq)]
+enum ClaimCondition {
+    Equals(Value),
+    Contains(Value),
+}
+
+// Routes on any registered or additional claim of the decoded access token.
+// Requests without a token, or without the claim, never match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaimGuard {
+    name: String,
+    condition: ClaimCondition,
+}
+
+impl ClaimGuard {
+    pub fn eq(name: &str, value: impl Into<Value>) -> Self {
+        Self {
+            name: name.to_string(),
+            condition: ClaimCondition::Equals(value.into()),
+        }
+    }
+
+    // Matches array claims with the value as an element.
+    pub fn contains(name: &str, value: impl Into<Value>) -> Self {
+        Self {
+            name: name.to_string(),
+            condition: ClaimCondition::Contains(value.into()),
+        }
+    }
+
+    fn matches(&self, token: &AccessToken) -> bool {
+        let claims = token.claims();
+        let claim = claims
+            .registered_claim(&self.name)
+            .or_else(|| claims.additional.get(&self.name).cloned());
+        match (&self.condition, claim) {
+            (ClaimCondition::Equals(expected), Some(value)) => values_match(&value, expected),
+            (ClaimCondition::Contains(expected), Some(Value::Array(values))) => values
+                .iter()
+                .fold(false, |found, value| found | values_match(value, expected)),
+            _ => false,
+        }
+    }
+}
+
+impl Guard for ClaimGuard {
+    fn check(&self, ctx: &GuardContext<'_>) -> bool {
+        ctx.req_data()
+            .get::<AccessToken>()
+            .is_some_and(|token| self.matches(token))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use actix_web::{
+        dev::Service as _,
+        http::StatusCode,
+        test::{init_service, TestRequest},
+        web, App, HttpMessage, HttpResponse,
+    };
+    use jsonwebtoken::Header;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{token::Token, AuthorizationClaims, Claims};
+
+    fn token(tier: &str, groups: &[&str]) -> AccessToken {
+        let extension = AuthorizationClaims { scopes: vec![] };
+        let mut claims = Claims::new("issuer", "subject", &[], Duration::from_secs(60), extension);
+        claims.additional.insert("tier".to_string(), json!(tier));
+        claims
+            .additional
+            .insert("groups".to_string(), json!(groups));
+        Token::new(Header::default(), claims)
+    }
+
+    #[test]
+    fn guards_compare_registered_and_additional_claims() {
+        let token = token("enterprise", &["admins", "staff"]);
+        assert!(ClaimGuard::eq("tier", "enterprise").matches(&token));
+        assert!(!ClaimGuard::eq("tier", "free").matches(&token));
+        assert!(ClaimGuard::eq("iss", "issuer").matches(&token));
+        assert!(ClaimGuard::contains("groups", "admins").matches(&token));
+        assert!(!ClaimGuard::contains("groups", "owners").matches(&token));
+        assert!(!ClaimGuard::contains("tier", "enterprise").matches(&token));
+        assert!(!ClaimGuard::eq("missing", "enterprise").matches(&token));
+    }
+
+    #[actix_web::test]
+    async fn guard_routes_by_claim() {
+        let app = init_service(
+            App::new()
+                .wrap_fn(|req, srv| {
+                    if let Some(tier) = req.headers().get("x-tier") {
+                        let tier = tier.to_str().expect("Expected an ASCII tier").to_string();
+                        req.extensions_mut().insert(token(&tier, &[]));
+                    }
+                    srv.call(req)
+                })
+                .service(
+                    web::scope("/api")
+                        .guard(ClaimGuard::eq("tier", "enterprise"))
+                        .default_service(web::to(|| async { HttpResponse::Ok().finish() })),
+                ),
+        )
+        .await;
+        let req = TestRequest::get()
+            .uri("/api/reports")
+            .insert_header(("x-tier", "enterprise"))
+            .to_request();
+        let res = app.call(req).await.expect("expected response");
+        assert_eq!(res.status(), StatusCode::OK);
+        for req in [
+            TestRequest::get()
+                .uri("/api/reports")
+                .insert_header(("x-tier", "free")),
+            TestRequest::get().uri("/api/reports"),
+        ] {
+            let res = app.call(req.to_request()).await.expect("expected response");
+            assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        }
+    }
+}
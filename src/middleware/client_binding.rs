@@ -0,0 +1,110 @@
+use actix_web::dev::ServiceRequest;
+use serde_json::Value;
+
+use crate::{compare::secure_eq, Claims};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientBindingSource {
+    PeerIp,
+    Header(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientBinding {
+    claim_path: Vec<String>,
+    source: ClientBindingSource,
+}
+
+impl ClientBinding {
+    pub fn new(claim_path: &[&str], source: ClientBindingSource) -> Self {
+        Self {
+            claim_path: claim_path.iter().map(|part| part.to_string()).collect(),
+            source,
+        }
+    }
+
+    fn bound_value<Extension>(&self, claims: &Claims<Extension>) -> Option<String> {
+        let (first, rest) = self.claim_path.split_first()?;
+        let value = rest
+            .iter()
+            .try_fold(claims.additional.get(first)?, |value, part| value.get(part))?;
+        match value {
+            Value::String(value) => Some(value.clone()),
+            Value::Number(value) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+
+    fn presented_value(&self, req: &ServiceRequest) -> Option<String> {
+        match &self.source {
+            ClientBindingSource::PeerIp => req.peer_addr().map(|addr| addr.ip().to_string()),
+            ClientBindingSource::Header(name) => req
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(ToString::to_string),
+        }
+    }
+
+    pub(crate) fn check<Extension>(
+        &self,
+        claims: &Claims<Extension>,
+        req: &ServiceRequest,
+    ) -> Result<(), String> {
+        let claim = self.claim_path.join(".");
+        let bound = self
+            .bound_value(claims)
+            .ok_or_else(|| format!("Client binding claim {claim} is missing"))?;
+        let presented = self.presented_value(req);
+        match presented.is_some_and(|presented| secure_eq(&presented, &bound)) {
+            true => Ok(()),
+            false => Err(format!("Client does not match binding claim {claim}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn claims(string: &str) -> Claims<Value> {
+        serde_json::from_str(string).expect("Expected deserialize")
+    }
+
+    #[test]
+    fn peer_ip_must_match_nested_claim() {
+        let binding = ClientBinding::new(&["cnf", "ip"], ClientBindingSource::PeerIp);
+        let claims = claims(r#"{"iss":"issuer","exp":1000,"cnf":{"ip":"10.0.0.1"}}"#);
+        let addr: SocketAddr = "10.0.0.1:4000".parse().expect("expected address");
+        let req = TestRequest::default().peer_addr(addr).to_srv_request();
+        assert!(binding.check(&claims, &req).is_ok());
+        let addr: SocketAddr = "10.0.0.2:4000".parse().expect("expected address");
+        let req = TestRequest::default().peer_addr(addr).to_srv_request();
+        assert!(binding.check(&claims, &req).is_err());
+    }
+
+    #[test]
+    fn header_must_match_claim() {
+        let source = ClientBindingSource::Header("x-client-fingerprint".to_string());
+        let binding = ClientBinding::new(&["fp"], source);
+        let claims = claims(r#"{"iss":"issuer","exp":1000,"fp":"abc"}"#);
+        let req = TestRequest::default()
+            .insert_header(("x-client-fingerprint", "abc"))
+            .to_srv_request();
+        assert!(binding.check(&claims, &req).is_ok());
+        let req = TestRequest::default().to_srv_request();
+        assert!(binding.check(&claims, &req).is_err());
+    }
+
+    #[test]
+    fn missing_claim_is_rejected() {
+        let binding = ClientBinding::new(&["cnf", "ip"], ClientBindingSource::PeerIp);
+        let claims = claims(r#"{"iss":"issuer","exp":1000}"#);
+        let req = TestRequest::default().to_srv_request();
+        assert!(binding.check(&claims, &req).is_err());
+    }
+}
@@ -0,0 +1,308 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix_web::dev::ServiceRequest;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use jsonwebtoken::{jwk::Jwk, Algorithm, DecodingKey, Validation};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::{compare::secure_eq, issuer::normalize_issuer, AccessToken};
+
+#[derive(Debug, thiserror::Error)]
+pub enum DPoPError {
+    #[error("DPoP proof is missing")]
+    MissingProof,
+    #[error("DPoP proof is invalid: {0}")]
+    InvalidProof(String),
+    #[error("DPoP proof was already used")]
+    ReplayedProof,
+    #[error("DPoP proof key does not match the token's cnf.jkt")]
+    KeyMismatch,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ProofClaims {
+    jti: String,
+    htm: String,
+    htu: String,
+    iat: i64,
+    ath: Option<String>,
+}
+
+// Verifies the DPoP proof sent with tokens presented under the DPoP scheme
+// (RFC 9449 section 4.3). Clones share the jti replay cache, so create one
+// outside the HttpServer factory closure to detect replays on every worker.
+#[derive(Debug, Clone)]
+pub struct DPoPVerifier {
+    max_age: Duration,
+    seen: Arc<Mutex<HashMap<String, i64>>>,
+}
+
+impl Default for DPoPVerifier {
+    fn default() -> Self {
+        Self {
+            max_age: Duration::from_secs(60),
+            seen: Default::default(),
+        }
+    }
+}
+
+impl DPoPVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // How far iat may lie from now, and how long a jti is remembered.
+    pub fn max_age(mut self, value: Duration) -> Self {
+        self.max_age = value;
+        self
+    }
+
+    pub(crate) fn verify(
+        &self,
+        req: &ServiceRequest,
+        access_token: &str,
+        token: &AccessToken,
+    ) -> Result<(), DPoPError> {
+        let mut proofs = req.headers().get_all("DPoP");
+        let proof = match (proofs.next(), proofs.next()) {
+            (Some(proof), None) => proof.to_str().map_err(invalid)?,
+            (None, _) => return Err(DPoPError::MissingProof),
+            (Some(_), Some(_)) => return Err(invalid("more than one DPoP header")),
+        };
+        let header = jsonwebtoken::decode_header(proof).map_err(invalid)?;
+        if !header
+            .typ
+            .as_deref()
+            .is_some_and(|typ| typ.eq_ignore_ascii_case("dpop+jwt"))
+        {
+            return Err(invalid("typ is not dpop+jwt"));
+        }
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(invalid("proofs must use an asymmetric algorithm"));
+        }
+        let jwk = header.jwk.ok_or_else(|| invalid("jwk header is missing"))?;
+        let key = DecodingKey::from_jwk(&jwk).map_err(invalid)?;
+        let mut validation = Validation::new(header.alg);
+        validation.validate_exp = false;
+        validation.required_spec_claims.clear();
+        let claims = jsonwebtoken::decode::<ProofClaims>(proof, &key, &validation)
+            .map_err(invalid)?
+            .claims;
+
+        if !claims.htm.eq_ignore_ascii_case(req.method().as_str()) {
+            return Err(invalid("htm does not match the request method"));
+        }
+        if normalize_issuer(target_uri(&claims.htu)) != normalize_issuer(&request_uri(req)) {
+            return Err(invalid("htu does not match the request URI"));
+        }
+        let ath = URL_SAFE_NO_PAD.encode(Sha256::digest(access_token.as_bytes()));
+        if !claims.ath.is_some_and(|claim| secure_eq(&claim, &ath)) {
+            return Err(invalid("ath does not match the access token"));
+        }
+        let jkt = token
+            .claims()
+            .additional
+            .get("cnf")
+            .and_then(|cnf| cnf.get("jkt"))
+            .and_then(Value::as_str)
+            .ok_or(DPoPError::KeyMismatch)?;
+        let thumbprint = thumbprint(&jwk).ok_or_else(|| invalid("unsupported jwk"))?;
+        if !secure_eq(&thumbprint, jkt) {
+            return Err(DPoPError::KeyMismatch);
+        }
+        self.remember(claims.jti, claims.iat)
+    }
+
+    fn remember(&self, jti: String, iat: i64) -> Result<(), DPoPError> {
+        let now = Utc::now().timestamp();
+        let max_age = self.max_age.as_secs() as i64;
+        if (now - iat).abs() > max_age {
+            return Err(invalid("iat is outside the accepted window"));
+        }
+        let mut seen = self.seen.lock().expect("DPoP replay cache poisoned");
+        seen.retain(|_, expires| *expires >= now);
+        if seen.contains_key(&jti) {
+            return Err(DPoPError::ReplayedProof);
+        }
+        seen.insert(jti, iat + max_age);
+        Ok(())
+    }
+}
+
+fn invalid(e: impl ToString) -> DPoPError {
+    DPoPError::InvalidProof(e.to_string())
+}
+
+// htu is compared without its query and fragment (RFC 9449 section 4.3).
+fn target_uri(htu: &str) -> &str {
+    htu.split(['?', '#']).next().unwrap_or(htu)
+}
+
+fn request_uri(req: &ServiceRequest) -> String {
+    let info = req.connection_info();
+    format!("{}://{}{}", info.scheme(), info.host(), req.path())
+}
+
+// JWK SHA-256 thumbprint over the required members in lexicographic order
+// (RFC 7638).
+pub(crate) fn thumbprint(jwk: &Jwk) -> Option<String> {
+    let jwk = serde_json::to_value(jwk).ok()?;
+    let members: &[&str] = match jwk.get("kty")?.as_str()? {
+        "RSA" => &["e", "kty", "n"],
+        "EC" => &["crv", "kty", "x", "y"],
+        "OKP" => &["crv", "kty", "x"],
+        _ => return None,
+    };
+    let members = members
+        .iter()
+        .map(|member| {
+            let value = jwk.get(*member)?.as_str()?;
+            Some(format!("\"{member}\":{}", Value::from(value)))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let canonical = format!("{{{}}}", members.join(","));
+    Some(URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes())))
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use actix_web::test::TestRequest;
+    use jsonwebtoken::{jwk::JwkSet, EncodingKey, Header};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        token::tests::{JWKS_JSON, PEM},
+        AuthorizationClaims, Claims, EncodedAccessToken,
+    };
+
+    fn jwk() -> Jwk {
+        let jwk_set: JwkSet = serde_json::from_str(JWKS_JSON).expect("expected JWK set");
+        jwk_set.keys[0].clone()
+    }
+
+    fn key() -> EncodingKey {
+        EncodingKey::from_rsa_pem(PEM.as_ref()).expect("expected encoding key from PEM")
+    }
+
+    fn bound_token() -> (String, AccessToken) {
+        let header = Header {
+            alg: Algorithm::RS256,
+            kid: jwk().common.key_id,
+            ..Default::default()
+        };
+        let extension = AuthorizationClaims { scopes: vec![] };
+        let aud = vec!["audience".to_string()];
+        let mut claims = Claims::new(
+            "issuer",
+            "subject",
+            &aud,
+            Duration::from_secs(60),
+            extension,
+        );
+        let jkt = thumbprint(&jwk()).expect("expected thumbprint");
+        claims
+            .additional
+            .insert("cnf".to_string(), json!({ "jkt": jkt }));
+        let encoded = EncodedAccessToken::new(header, claims, key()).expect("expected token");
+        let raw = encoded.to_string();
+        let jwk_set: JwkSet = serde_json::from_str(JWKS_JSON).expect("expected JWK set");
+        let token = encoded
+            .decode_with_scope_prefix(&jwk_set, "")
+            .expect("expected token");
+        (raw, token)
+    }
+
+    fn sign_proof(method: &str, htu: &str, access_token: &str, jti: &str) -> String {
+        let header = Header {
+            typ: Some("dpop+jwt".to_string()),
+            alg: Algorithm::RS256,
+            jwk: Some(jwk()),
+            ..Default::default()
+        };
+        let claims = json!({
+            "jti": jti,
+            "htm": method,
+            "htu": htu,
+            "iat": Utc::now().timestamp(),
+            "ath": URL_SAFE_NO_PAD.encode(Sha256::digest(access_token.as_bytes())),
+        });
+        jsonwebtoken::encode(&header, &claims, &key()).expect("expected proof")
+    }
+
+    fn request(proof: &str) -> ServiceRequest {
+        TestRequest::get()
+            .uri("http://localhost:8080/projects?page=2")
+            .insert_header(("DPoP", proof))
+            .to_srv_request()
+    }
+
+    #[test]
+    fn valid_proof_is_accepted_once() {
+        let (encoded, token) = bound_token();
+        let verifier = DPoPVerifier::new();
+        let proof = sign_proof("GET", "http://localhost:8080/projects", &encoded, "a");
+        assert!(verifier.verify(&request(&proof), &encoded, &token).is_ok());
+        let error = verifier
+            .clone()
+            .verify(&request(&proof), &encoded, &token)
+            .expect_err("expected replay");
+        assert!(matches!(error, DPoPError::ReplayedProof));
+    }
+
+    #[test]
+    fn proof_must_match_request_and_token() {
+        let (encoded, token) = bound_token();
+        let verifier = DPoPVerifier::new();
+        let req = TestRequest::get().to_srv_request();
+        let error = verifier
+            .verify(&req, &encoded, &token)
+            .expect_err("expected error");
+        assert!(matches!(error, DPoPError::MissingProof));
+        let proof = sign_proof("POST", "http://localhost:8080/projects", &encoded, "b");
+        let error = verifier
+            .verify(&request(&proof), &encoded, &token)
+            .expect_err("expected error");
+        assert!(matches!(error, DPoPError::InvalidProof(_)));
+        let proof = sign_proof("GET", "http://localhost:8080/other", &encoded, "c");
+        assert!(verifier.verify(&request(&proof), &encoded, &token).is_err());
+        let proof = sign_proof("GET", "http://localhost:8080/projects", "other", "d");
+        assert!(verifier.verify(&request(&proof), &encoded, &token).is_err());
+
+        let mut claims = token.claims().clone();
+        claims.additional.remove("cnf");
+        let unbound = AccessToken::new(token.header().clone(), claims);
+        let proof = sign_proof("GET", "http://localhost:8080/projects", &encoded, "e");
+        let error = verifier
+            .verify(&request(&proof), &encoded, &unbound)
+            .expect_err("expected error");
+        assert!(matches!(error, DPoPError::KeyMismatch));
+    }
+
+    #[test]
+    fn thumbprint_matches_rfc_7638_example() {
+        let jwk: Jwk = serde_json::from_value(json!({
+            "kty": "RSA",
+            "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+            "e": "AQAB",
+            "alg": "RS256",
+            "kid": "2011-04-29",
+        }))
+        .expect("expected JWK");
+        assert_eq!(
+            thumbprint(&jwk).as_deref(),
+            Some("NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs")
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use std::{future::Future, rc::Rc};
+
+use actix_web::{http::StatusCode, HttpMessage, HttpRequest};
+use futures::future::LocalBoxFuture;
+
+use crate::{AuthorizationClaims, Claims};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{message}")]
+pub struct EnrichmentError {
+    status: StatusCode,
+    message: String,
+}
+
+impl EnrichmentError {
+    pub fn new(message: &str) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn status(mut self, value: StatusCode) -> Self {
+        self.status = value;
+        self
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        self.status
+    }
+}
+
+pub(crate) type Enricher = Rc<
+    dyn Fn(
+        Claims<AuthorizationClaims>,
+        HttpRequest,
+    ) -> LocalBoxFuture<'static, Result<(), EnrichmentError>>,
+>;
+
+pub(crate) fn enricher<F, Fut, T>(f: F) -> Enricher
+where
+    F: Fn(Claims<AuthorizationClaims>, HttpRequest) -> Fut + 'static,
+    Fut: Future<Output = Result<T, EnrichmentError>> + 'static,
+    T: 'static,
+{
+    let f = Rc::new(f);
+    Rc::new(move |claims, req| {
+        let f = f.clone();
+        Box::pin(async move {
+            let value = f(claims, req.clone()).await?;
+            req.extensions_mut().insert(value);
+            Ok(())
+        })
+    })
+}
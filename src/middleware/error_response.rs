@@ -1,21 +1,437 @@
+use std::fmt;
+
+use actix_web::{
+    body::BoxBody,
+    dev::ServiceRequest,
+    http::{
+        header::{HeaderValue, WWW_AUTHENTICATE},
+        StatusCode,
+    },
+    HttpResponse, HttpResponseBuilder, ResponseError,
+};
+use chrono::{SecondsFormat, Utc};
+use serde_json::Value;
+
+// The last `.wrap()` call runs first, so the factories are registered innermost
+// first. AuthStack registers them this way; apps wrapping them one by one find
+// it in the log when a factory finds an earlier one missing.
+pub(crate) const MIDDLEWARE_ORDER: &str =
+    ".wrap(AuthorizationFactory).wrap(JWTFactory).wrap(JwkSetFactory).wrap(IssuerFactory)";
+
+// The wiring hint is for operators, so it is logged rather than put in the
+// response body.
+pub(crate) fn miswired<E: std::fmt::Display>(error: E, missing: &str, by: &str) -> E {
+    log::error!(
+        "{}: {} must run before {}, expected {}",
+        error,
+        missing,
+        by,
+        MIDDLEWARE_ORDER
+    );
+    error
+}
+
 #[derive(serde::Serialize)]
 pub struct ErrorBody {
     code: String,
     message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    timestamp: String,
+}
+
+impl ErrorBody {
+    fn new(code: &str, message: String) -> Self {
+        Self {
+            code: code.to_string(),
+            message,
+            error: None,
+            error_description: None,
+            details: None,
+            request_id: None,
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        }
+    }
+
+    pub(crate) fn details(mut self, value: Value) -> Self {
+        self.details = Some(value);
+        self
+    }
+}
+
+// 401
+pub fn unauthorized_error_body(code: &str, e: impl std::error::Error) -> ErrorBody {
+    ErrorBody::new(code, format!("Unauthorized: {e}"))
 }
 
 // 403
 pub fn forbidden_error_body(code: &str, e: impl std::error::Error) -> ErrorBody {
-    ErrorBody {
-        code: code.to_string(),
-        message: format!("Forbidden: {e}"),
-    }
+    ErrorBody::new(code, format!("Forbidden: {e}"))
+}
+
+// 429
+pub fn too_many_requests_error_body(code: &str, e: impl std::error::Error) -> ErrorBody {
+    ErrorBody::new(code, format!("Too many requests: {e}"))
+}
+
+pub fn status_error_body(status: StatusCode, code: &str, e: impl std::error::Error) -> ErrorBody {
+    let reason = status.canonical_reason().unwrap_or("Error");
+    ErrorBody::new(code, format!("{reason}: {e}"))
 }
 
 // 500
 pub fn internal_server_error_body(code: &str, e: impl std::error::Error) -> ErrorBody {
-    ErrorBody {
-        code: code.to_string(),
-        message: format!("An internal error occurred: {e}"),
+    ErrorBody::new(code, format!("An internal error occurred: {e}"))
+}
+
+// RFC 6750 section 3.1 error codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BearerError {
+    InvalidRequest,
+    InvalidToken,
+    InsufficientScope,
+}
+
+impl BearerError {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BearerError::InvalidRequest => "invalid_request",
+            BearerError::InvalidToken => "invalid_token",
+            BearerError::InsufficientScope => "insufficient_scope",
+        }
+    }
+}
+
+// A challenge without an error code is sent when no credentials were presented,
+// as RFC 6750 asks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct BearerChallenge {
+    error: Option<BearerError>,
+    scope: Option<String>,
+}
+
+impl BearerChallenge {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn error(mut self, value: BearerError) -> Self {
+        self.error = Some(value);
+        self
+    }
+
+    pub(crate) fn scope(mut self, value: String) -> Self {
+        self.scope = Some(value);
+        self
+    }
+
+    fn header_value(&self, description: &str) -> Option<HeaderValue> {
+        let mut params = Vec::new();
+        if let Some(error) = self.error {
+            // Quoted strings may not carry quotes or backslashes unescaped.
+            let description = description.replace(['"', '\\'], "'");
+            params.push(format!("error=\"{}\"", error.as_str()));
+            params.push(format!("error_description=\"{description}\""));
+        }
+        if let Some(scope) = &self.scope {
+            params.push(format!("scope=\"{scope}\""));
+        }
+        let value = match params.is_empty() {
+            true => "Bearer".to_string(),
+            false => format!("Bearer {}", params.join(", ")),
+        };
+        HeaderValue::from_str(&value).ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestIdHeader(String);
+
+impl RequestIdHeader {
+    pub fn new(name: &str) -> Self {
+        Self(name.to_string())
+    }
+}
+
+impl Default for RequestIdHeader {
+    fn default() -> Self {
+        Self::new("x-request-id")
+    }
+}
+
+// Missing or invalid credentials are answered with 401 and insufficient ones with
+// 403. Register a mapping as app data to answer differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusMapping {
+    unauthenticated: StatusCode,
+    forbidden: StatusCode,
+}
+
+impl StatusMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn unauthenticated(mut self, value: StatusCode) -> Self {
+        self.unauthenticated = value;
+        self
+    }
+
+    pub fn forbidden(mut self, value: StatusCode) -> Self {
+        self.forbidden = value;
+        self
+    }
+
+    fn map(&self, status: StatusCode) -> StatusCode {
+        match status {
+            StatusCode::UNAUTHORIZED => self.unauthenticated,
+            StatusCode::FORBIDDEN => self.forbidden,
+            _ => status,
+        }
+    }
+}
+
+impl Default for StatusMapping {
+    fn default() -> Self {
+        Self {
+            unauthenticated: StatusCode::UNAUTHORIZED,
+            forbidden: StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ErrorContext {
+    request_id: Option<String>,
+    status_mapping: StatusMapping,
+}
+
+pub(crate) fn error_context(req: &ServiceRequest) -> ErrorContext {
+    ErrorContext {
+        request_id: request_id(req),
+        status_mapping: req.app_data::<StatusMapping>().copied().unwrap_or_default(),
+    }
+}
+
+fn request_id(req: &ServiceRequest) -> Option<String> {
+    let default = RequestIdHeader::default();
+    let header = req.app_data::<RequestIdHeader>().unwrap_or(&default);
+    req.headers()
+        .get(header.0.as_str())
+        .and_then(|value| value.to_str().ok())
+        .map(ToString::to_string)
+}
+
+pub(crate) trait MiddlewareError: ResponseError {
+    fn error_body(&self) -> ErrorBody;
+
+    fn challenge(&self) -> Option<BearerChallenge> {
+        None
+    }
+
+    fn decorate(&self, _response: &mut HttpResponse) {}
+
+    fn respond(&self, request_id: Option<String>) -> HttpResponse<BoxBody> {
+        self.respond_as(self.status_code(), request_id)
+    }
+
+    fn respond_as(&self, status: StatusCode, request_id: Option<String>) -> HttpResponse<BoxBody> {
+        let mut error_body = self.error_body();
+        error_body.request_id = request_id;
+        let challenge = self.challenge();
+        let description = self.to_string();
+        if let Some(error) = challenge.as_ref().and_then(|challenge| challenge.error) {
+            error_body.error = Some(error.as_str());
+            error_body.error_description = Some(description.clone());
+        }
+        let mut response = HttpResponseBuilder::new(status).json(error_body);
+        if let Some(value) = challenge.and_then(|challenge| challenge.header_value(&description)) {
+            response.headers_mut().insert(WWW_AUTHENTICATE, value);
+        }
+        self.decorate(&mut response);
+        response
+    }
+}
+
+// Wraps the errors the middlewares return, adding the request id and status
+// mapping. Applications can downcast with
+// `error.as_error::<CorrelatedError<AuthorizationMiddlewareError>>()` and match
+// on `error()`.
+#[derive(Debug)]
+pub struct CorrelatedError<E> {
+    error: E,
+    context: ErrorContext,
+}
+
+impl<E> CorrelatedError<E> {
+    pub(crate) fn new(error: E, context: &ErrorContext) -> Self {
+        Self {
+            error,
+            context: context.clone(),
+        }
+    }
+
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for CorrelatedError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<E: MiddlewareError> ResponseError for CorrelatedError<E> {
+    fn status_code(&self) -> StatusCode {
+        self.context.status_mapping.map(self.error.status_code())
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        let request_id = self.context.request_id.clone();
+        self.error.respond_as(self.status_code(), request_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{body::to_bytes, test::TestRequest};
+
+    use super::*;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("test error")]
+    struct TestError;
+
+    impl ResponseError for TestError {
+        fn status_code(&self) -> StatusCode {
+            StatusCode::FORBIDDEN
+        }
+    }
+
+    impl MiddlewareError for TestError {
+        fn error_body(&self) -> ErrorBody {
+            forbidden_error_body("TEST", self)
+        }
+    }
+
+    async fn body(error: impl ResponseError) -> Value {
+        let bytes = to_bytes(error.error_response().into_body())
+            .await
+            .expect("expected body");
+        serde_json::from_slice(&bytes).expect("expected json")
+    }
+
+    #[actix_web::test]
+    async fn request_id_is_read_from_default_header() {
+        let req = TestRequest::default()
+            .insert_header(("x-request-id", "abc-123"))
+            .to_srv_request();
+        let error = CorrelatedError::new(TestError, &error_context(&req));
+        let body = body(error).await;
+        assert_eq!(body["code"], "TEST");
+        assert_eq!(body["request_id"], "abc-123");
+        assert!(body["timestamp"].is_string());
+    }
+
+    #[actix_web::test]
+    async fn request_id_header_is_configurable() {
+        let req = TestRequest::default()
+            .app_data(RequestIdHeader::new("x-correlation-id"))
+            .insert_header(("x-request-id", "ignored"))
+            .insert_header(("x-correlation-id", "def-456"))
+            .to_srv_request();
+        let error = CorrelatedError::new(TestError, &error_context(&req));
+        let body = body(error).await;
+        assert_eq!(body["request_id"], "def-456");
+    }
+
+    #[actix_web::test]
+    async fn request_id_is_omitted_when_absent() {
+        let req = TestRequest::default().to_srv_request();
+        let error = CorrelatedError::new(TestError, &error_context(&req));
+        let body = body(error).await;
+        assert!(body.get("request_id").is_none());
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("unauthenticated")]
+    struct Unauthenticated;
+
+    impl ResponseError for Unauthenticated {
+        fn status_code(&self) -> StatusCode {
+            StatusCode::UNAUTHORIZED
+        }
+
+        fn error_response(&self) -> HttpResponse<BoxBody> {
+            self.respond(None)
+        }
+    }
+
+    impl MiddlewareError for Unauthenticated {
+        fn error_body(&self) -> ErrorBody {
+            unauthorized_error_body("UNAUTHENTICATED", self)
+        }
+
+        fn challenge(&self) -> Option<BearerChallenge> {
+            Some(BearerChallenge::new().error(BearerError::InvalidToken))
+        }
+    }
+
+    #[actix_web::test]
+    async fn status_mapping_overrides_defaults() {
+        let req = TestRequest::default().to_srv_request();
+        let context = error_context(&req);
+        let error = CorrelatedError::new(Unauthenticated, &context);
+        assert_eq!(error.status_code(), StatusCode::UNAUTHORIZED);
+        let error = CorrelatedError::new(TestError, &context);
+        assert_eq!(error.status_code(), StatusCode::FORBIDDEN);
+
+        let mapping = StatusMapping::new()
+            .unauthenticated(StatusCode::FORBIDDEN)
+            .forbidden(StatusCode::NOT_FOUND);
+        let req = TestRequest::default().app_data(mapping).to_srv_request();
+        let context = error_context(&req);
+        let error = CorrelatedError::new(Unauthenticated, &context);
+        assert_eq!(error.error_response().status(), StatusCode::FORBIDDEN);
+        let error = CorrelatedError::new(TestError, &context);
+        assert_eq!(error.error_response().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn bearer_errors_are_sent_in_body_and_challenge() {
+        let response = Unauthenticated.error_response();
+        let challenge = response.headers().get(WWW_AUTHENTICATE).unwrap();
+        assert_eq!(
+            challenge,
+            "Bearer error=\"invalid_token\", error_description=\"unauthenticated\""
+        );
+        let body = body(Unauthenticated).await;
+        assert_eq!(body["error"], "invalid_token");
+        assert_eq!(body["error_description"], "unauthenticated");
+
+        let response = TestError.respond(None);
+        assert!(response.headers().get(WWW_AUTHENTICATE).is_none());
+    }
+
+    #[test]
+    fn challenge_without_error_carries_no_parameters() {
+        let value = BearerChallenge::new().header_value("ignored").unwrap();
+        assert_eq!(value, "Bearer");
+        let value = BearerChallenge::new()
+            .error(BearerError::InsufficientScope)
+            .scope("read:users".to_string())
+            .header_value("needs \"read\"")
+            .unwrap();
+        assert_eq!(
+            value,
+            "Bearer error=\"insufficient_scope\", error_description=\"needs 'read'\", scope=\"read:users\""
+        );
     }
 }
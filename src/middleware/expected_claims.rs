@@ -0,0 +1,179 @@
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{compare::secure_eq, AuthorizationClaims, Claims, ClaimsProfile};
+
+pub(crate) fn values_match(value: &Value, expected: &Value) -> bool {
+    match (value, expected) {
+        (Value::String(value), Value::String(expected)) => secure_eq(value, expected),
+        _ => value == expected,
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ClaimMatcher {
+    Equals(String, Value),
+    Matches(String, Regex),
+}
+
+// The expected values and patterns are configuration, so errors only name the
+// claim.
+impl ClaimMatcher {
+    fn check(&self, claims: &Claims<AuthorizationClaims>) -> Result<(), String> {
+        let (name, matches) = match self {
+            ClaimMatcher::Equals(name, expected) => {
+                let value = claims.get_claim::<Value>(name).ok().flatten();
+                (
+                    name,
+                    value.is_some_and(|value| values_match(&value, expected)),
+                )
+            }
+            ClaimMatcher::Matches(name, pattern) => {
+                let value = claims.get_claim::<Value>(name).ok().flatten();
+                let matches = match value {
+                    Some(Value::String(value)) => pattern.is_match(&value),
+                    _ => false,
+                };
+                (name, matches)
+            }
+        };
+        match matches {
+            true => Ok(()),
+            false => Err(format!("Claim {name} does not match")),
+        }
+    }
+}
+
+// Claims AuthorizationFactory checks on every token. Patterns are unanchored,
+// so write "^...$" to match a whole value.
+#[derive(Debug, Clone, Default)]
+pub struct ExpectedClaims {
+    pub(crate) issuer: Option<String>,
+    pub(crate) audiences: Vec<String>,
+    pub(crate) subject_pattern: Option<Regex>,
+    pub(crate) claims_profile: ClaimsProfile,
+    pub(crate) accept_authorized_party: bool,
+    pub(crate) required_actors: Option<Vec<String>>,
+    pub(crate) required_audiences: Option<Vec<String>>,
+    pub(crate) allowed_clients: Option<Vec<String>>,
+    pub(crate) strict_issuer: bool,
+    pub(crate) report_all_violations: bool,
+    matchers: Vec<ClaimMatcher>,
+}
+
+impl ExpectedClaims {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Overrides the issuer resolved by IssuerFactory for the comparison only;
+    // keys still come from that issuer.
+    pub fn issuer(mut self, value: &str) -> Self {
+        self.issuer = Some(value.to_string());
+        self
+    }
+
+    // Tokens are accepted when aud contains any of the audiences given.
+    pub fn audience(mut self, value: &str) -> Self {
+        self.audiences.push(value.to_string());
+        self
+    }
+
+    pub fn audiences(mut self, values: &[&str]) -> Self {
+        self.audiences
+            .extend(values.iter().map(ToString::to_string));
+        self
+    }
+
+    pub fn subject_pattern(mut self, value: Regex) -> Self {
+        self.subject_pattern = Some(value);
+        self
+    }
+
+    pub fn claim_equals(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.matchers
+            .push(ClaimMatcher::Equals(name.to_string(), value.into()));
+        self
+    }
+
+    pub fn claim_matches(mut self, name: &str, pattern: Regex) -> Self {
+        self.matchers
+            .push(ClaimMatcher::Matches(name.to_string(), pattern));
+        self
+    }
+
+    // For authenticators without an IssuerFactory, which must be given the
+    // issuer and at least one audience to compare against.
+    pub(crate) fn configured_issuer<'a>(
+        &'a self,
+        issuer: Option<&'a str>,
+    ) -> Result<&'a str, &'static str> {
+        if self.audiences.is_empty() {
+            return Err("audience");
+        }
+        issuer.or(self.issuer.as_deref()).ok_or("issuer")
+    }
+
+    pub(crate) fn subject_matches(&self, claims: &Claims<AuthorizationClaims>) -> bool {
+        match &self.subject_pattern {
+            Some(pattern) => claims
+                .sub
+                .as_ref()
+                .is_some_and(|sub| pattern.is_match(sub.as_str())),
+            None => true,
+        }
+    }
+
+    pub(crate) fn check_matchers(
+        &self,
+        claims: &Claims<AuthorizationClaims>,
+    ) -> Result<(), String> {
+        self.matchers
+            .iter()
+            .try_for_each(|matcher| matcher.check(claims))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn claims() -> Claims<AuthorizationClaims> {
+        let mut claims = Claims::new(
+            "issuer",
+            "client@clients",
+            &[],
+            Duration::from_secs(60),
+            AuthorizationClaims { scopes: vec![] },
+        );
+        claims
+            .additional
+            .insert("gty".to_string(), serde_json::json!("client-credentials"));
+        claims
+    }
+
+    #[test]
+    fn claim_matchers_compare_registered_and_additional_claims() {
+        let expected = ExpectedClaims::new()
+            .claim_equals("gty", "client-credentials")
+            .claim_matches("iss", Regex::new("^issuer$").unwrap());
+        assert!(expected.check_matchers(&claims()).is_ok());
+        let expected = ExpectedClaims::new().claim_equals("gty", "password");
+        assert_eq!(
+            expected.check_matchers(&claims()),
+            Err("Claim gty does not match".to_string())
+        );
+        let expected = ExpectedClaims::new().claim_matches("missing", Regex::new(".*").unwrap());
+        assert!(expected.check_matchers(&claims()).is_err());
+    }
+
+    #[test]
+    fn subject_pattern_requires_matching_sub() {
+        let expected = ExpectedClaims::new().subject_pattern(Regex::new("@clients$").unwrap());
+        assert!(expected.subject_matches(&claims()));
+        let expected = ExpectedClaims::new().subject_pattern(Regex::new("^auth0\\|").unwrap());
+        assert!(!expected.subject_matches(&claims()));
+    }
+}
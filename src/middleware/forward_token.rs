@@ -0,0 +1,84 @@
+use std::future::{ready, Ready};
+
+use actix_web::{FromRequest, HttpMessage};
+use reqwest::RequestBuilder;
+
+use crate::{middleware::AuthenticatedError, AccessToken};
+
+// The validated inbound token in its encoded form, for gateways that pass the
+// caller's credentials through to upstream services unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForwardToken(String);
+
+impl ForwardToken {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn authorization_header(&self) -> String {
+        format!("Bearer {}", self.0)
+    }
+
+    pub fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        request.bearer_auth(&self.0)
+    }
+}
+
+impl FromRequest for ForwardToken {
+    type Error = AuthenticatedError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        _payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        let result = req
+            .extensions()
+            .get::<AccessToken>()
+            .and_then(|token| token.raw())
+            .map(|raw| ForwardToken(raw.to_string()))
+            .ok_or(AuthenticatedError::Unauthenticated)
+            .map_err(|e| {
+                log::info!("{}", e);
+                e
+            });
+        ready(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use actix_web::test::TestRequest;
+    use jsonwebtoken::Header;
+
+    use super::*;
+    use crate::{token::Token, AuthorizationClaims, Claims};
+
+    fn token() -> AccessToken {
+        let extension = AuthorizationClaims { scopes: vec![] };
+        let claims = Claims::new("issuer", "subject", &[], Duration::from_secs(60), extension);
+        Token::new(Header::default(), claims)
+    }
+
+    #[actix_web::test]
+    async fn forward_token_requires_an_encoded_token() {
+        let req = TestRequest::default().to_http_request();
+        assert!(ForwardToken::extract(&req).await.is_err());
+
+        req.extensions_mut().insert(token());
+        assert!(ForwardToken::extract(&req).await.is_err());
+
+        req.extensions_mut()
+            .insert(token().with_raw("header.payload.signature".into()));
+        let forward = ForwardToken::extract(&req)
+            .await
+            .expect("expected forward token");
+        assert_eq!(forward.as_str(), "header.payload.signature");
+        assert_eq!(
+            forward.authorization_header(),
+            "Bearer header.payload.signature"
+        );
+    }
+}
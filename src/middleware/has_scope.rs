@@ -0,0 +1,110 @@
+use std::{
+    future::{ready, Ready},
+    marker::PhantomData,
+    ops::Deref,
+};
+
+use actix_web::{FromRequest, HttpRequest};
+
+use crate::middleware::{verify, Authorization, AuthorizationError};
+
+// A scope named at the type level, usually declared with scope_requirement!.
+pub trait ScopeRequirement {
+    const ACTION: &'static str;
+    const RESOURCE: &'static str;
+}
+
+// Declares a ScopeRequirement for use with HasScope:
+// `scope_requirement!(pub ReadUsers, "read", "users");`.
+#[macro_export]
+macro_rules! scope_requirement {
+    ($vis:vis $name:ident, $action:expr, $resource:expr) => {
+        $vis struct $name;
+
+        impl $crate::ScopeRequirement for $name {
+            const ACTION: &'static str = $action;
+            const RESOURCE: &'static str = $resource;
+        }
+    };
+}
+
+// Extracts only when the token grants S, so handlers state their scope in the
+// signature: `async fn users(_: HasScope<ReadUsers>)`. Fails like verify(), with
+// 401 without a token and 403 without the scope.
+pub struct HasScope<S: ScopeRequirement> {
+    authorization: Authorization,
+    phantom: PhantomData<S>,
+}
+
+impl<S: ScopeRequirement> HasScope<S> {
+    pub fn into_inner(self) -> Authorization {
+        self.authorization
+    }
+}
+
+impl<S: ScopeRequirement> FromRequest for HasScope<S> {
+    type Error = AuthorizationError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+        let authorization = Authorization::of(req);
+        let result = verify(&authorization, S::RESOURCE, S::ACTION)
+            .map(|()| HasScope {
+                authorization,
+                phantom: PhantomData,
+            })
+            .map_err(|e| {
+                log::info!("{}", e);
+                e
+            });
+        ready(result)
+    }
+}
+
+impl<S: ScopeRequirement> Deref for HasScope<S> {
+    type Target = Authorization;
+
+    fn deref(&self) -> &Self::Target {
+        &self.authorization
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use actix_web::{http::StatusCode, test::TestRequest, HttpMessage, ResponseError};
+    use jsonwebtoken::Header;
+
+    use super::*;
+    use crate::{token::Token, AuthorizationClaims, Claims, Scope};
+
+    scope_requirement!(ReadUsers, "read", "users");
+    scope_requirement!(WriteUsers, "write", "users");
+
+    #[actix_web::test]
+    async fn extracts_only_with_the_named_scope() {
+        let req = TestRequest::default().to_http_request();
+        let error = HasScope::<ReadUsers>::extract(&req)
+            .await
+            .err()
+            .expect("expected error");
+        assert_eq!(error.status_code(), StatusCode::UNAUTHORIZED);
+
+        let extension = AuthorizationClaims {
+            scopes: vec![Scope::new("read", "users")],
+        };
+        let claims = Claims::new("issuer", "subject", &[], Duration::from_secs(60), extension);
+        req.extensions_mut()
+            .insert(Token::new(Header::default(), claims));
+        let granted = HasScope::<ReadUsers>::extract(&req)
+            .await
+            .expect("expected scope");
+        assert!(granted.is_some());
+        let error = HasScope::<WriteUsers>::extract(&req)
+            .await
+            .err()
+            .expect("expected error");
+        assert_eq!(error.status_code(), StatusCode::FORBIDDEN);
+    }
+}
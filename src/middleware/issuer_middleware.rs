@@ -0,0 +1,254 @@
+use std::{
+    collections::HashMap,
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    body::BoxBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{header::HOST, StatusCode},
+    Error, HttpMessage, HttpResponse, ResponseError,
+};
+use futures::future::LocalBoxFuture;
+
+use crate::{
+    middleware::error_response::{
+        error_context, status_error_body, CorrelatedError, ErrorBody, MiddlewareError,
+    },
+    Issuer,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum IssuerError {
+    #[error("no issuer is configured for host \"{0}\"")]
+    UnknownHost(String),
+}
+
+impl ResponseError for IssuerError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::MISDIRECTED_REQUEST
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        self.respond(None)
+    }
+}
+
+impl MiddlewareError for IssuerError {
+    fn error_body(&self) -> ErrorBody {
+        status_error_body(self.status_code(), "UNKNOWN_HOST", self)
+    }
+}
+
+// Hosts are matched case-insensitively and without their port.
+#[derive(Debug, Clone)]
+pub struct HostIssuerResolver<I> {
+    issuers: HashMap<String, I>,
+    fallback: Option<I>,
+}
+
+fn normalize_host(host: &str) -> String {
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+impl<I: Issuer + Clone> HostIssuerResolver<I> {
+    pub fn new() -> Self {
+        Self {
+            issuers: HashMap::new(),
+            fallback: None,
+        }
+    }
+
+    pub fn host(mut self, host: &str, issuer: I) -> Self {
+        self.issuers.insert(normalize_host(host), issuer);
+        self
+    }
+
+    pub fn fallback(mut self, issuer: I) -> Self {
+        self.fallback = Some(issuer);
+        self
+    }
+
+    pub fn resolve(&self, host: &str) -> Option<&I> {
+        self.issuers
+            .get(&normalize_host(host))
+            .or(self.fallback.as_ref())
+    }
+
+    // Forwarded headers are ignored because clients can set them freely, which
+    // would let a token for one domain be validated against another's issuer.
+    fn resolve_request(&self, req: &ServiceRequest) -> Result<I, IssuerError> {
+        let host = req
+            .headers()
+            .get(HOST)
+            .and_then(|host| host.to_str().ok())
+            .or_else(|| req.uri().host())
+            .unwrap_or_default();
+        self.resolve(host)
+            .cloned()
+            .ok_or_else(|| IssuerError::UnknownHost(host.to_string()))
+    }
+}
+
+impl<I: Issuer + Clone> Default for HostIssuerResolver<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone)]
+enum IssuerSource<I> {
+    Fixed(I),
+    Host(HostIssuerResolver<I>),
+}
+
+pub struct IssuerFactory<I: Issuer> {
+    source: IssuerSource<I>,
+}
+
+impl<I: Issuer> IssuerFactory<I> {
+    pub fn new(issuer: I) -> Self {
+        Self {
+            source: IssuerSource::Fixed(issuer),
+        }
+    }
+
+    pub fn from_host(resolver: HostIssuerResolver<I>) -> Self {
+        Self {
+            source: IssuerSource::Host(resolver),
+        }
+    }
+}
+
+impl<I, S, B> Transform<S, ServiceRequest> for IssuerFactory<I>
+where
+    I: Issuer + Clone + 'static,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = IssuerMiddleware<I, S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let middleware = IssuerMiddleware {
+            service: Rc::new(service),
+            source: Rc::new(self.source.clone()),
+        };
+        ready(Ok(middleware))
+    }
+}
+
+pub struct IssuerMiddleware<I, S> {
+    service: Rc<S>,
+    source: Rc<IssuerSource<I>>,
+}
+
+impl<I, S, B> Service<ServiceRequest> for IssuerMiddleware<I, S>
+where
+    I: Issuer + Clone + 'static,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let issuer = match self.source.as_ref() {
+            IssuerSource::Fixed(issuer) => issuer.clone(),
+            IssuerSource::Host(resolver) => match resolver.resolve_request(&req) {
+                Ok(issuer) => issuer,
+                Err(e) => {
+                    log::info!("{}", e);
+                    let e = CorrelatedError::new(e, &error_context(&req));
+                    return Box::pin(ready(Err(e.into())));
+                }
+            },
+        };
+        req.extensions_mut().insert(issuer);
+        Box::pin(self.service.call(req))
+    }
+
+    forward_ready!(service);
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{
+        dev::Service as _,
+        test::{call_and_read_body, init_service, TestRequest},
+        web, App, HttpRequest,
+    };
+
+    use super::*;
+    use crate::providers::auth0::Auth0Issuer;
+
+    #[actix_web::test]
+    async fn issuer_is_inserted_into_extensions() {
+        let issuer = Auth0Issuer::new("issuer.example.com");
+        let app = init_service(App::new().wrap(IssuerFactory::new(issuer)).default_service(
+            web::to(|req: HttpRequest| async move {
+                let url = req.extensions().get::<Auth0Issuer>().map(|i| i.url());
+                HttpResponse::Ok().body(url.unwrap_or_default())
+            }),
+        ))
+        .await;
+        let req = TestRequest::get().uri("/").to_request();
+        let body = call_and_read_body(&app, req).await;
+        assert_eq!(body, "https://issuer.example.com/");
+    }
+
+    #[test]
+    fn resolver_ignores_case_and_port() {
+        let resolver = HostIssuerResolver::new()
+            .host("foo.example.com", Auth0Issuer::new("foo.auth0.com"))
+            .host("bar.example.com", Auth0Issuer::new("bar.auth0.com"));
+        let issuer = resolver.resolve("Foo.Example.com:8443").unwrap();
+        assert_eq!(issuer.url(), "https://foo.auth0.com/");
+        assert!(resolver.resolve("baz.example.com").is_none());
+        let resolver = resolver.fallback(Auth0Issuer::new("default.auth0.com"));
+        let issuer = resolver.resolve("baz.example.com").unwrap();
+        assert_eq!(issuer.url(), "https://default.auth0.com/");
+    }
+
+    #[actix_web::test]
+    async fn issuer_is_selected_by_host() {
+        let resolver = HostIssuerResolver::new()
+            .host("foo.example.com", Auth0Issuer::new("foo.auth0.com"))
+            .host("bar.example.com", Auth0Issuer::new("bar.auth0.com"));
+        let app = init_service(
+            App::new()
+                .wrap(IssuerFactory::from_host(resolver))
+                .default_service(web::to(|req: HttpRequest| async move {
+                    let url = req.extensions().get::<Auth0Issuer>().map(|i| i.url());
+                    HttpResponse::Ok().body(url.unwrap_or_default())
+                })),
+        )
+        .await;
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header(("Host", "bar.example.com"))
+            .to_request();
+        let body = call_and_read_body(&app, req).await;
+        assert_eq!(body, "https://bar.auth0.com/");
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header(("Host", "evil.example.com"))
+            .to_request();
+        let error = app.call(req).await.expect_err("expected error");
+        assert_eq!(
+            error.as_response_error().status_code(),
+            StatusCode::MISDIRECTED_REQUEST
+        );
+    }
+}
@@ -1,43 +1,149 @@
 use std::{
+    collections::{HashMap, HashSet},
     future::{ready, Ready},
     marker::PhantomData,
     rc::Rc,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use actix_web::{
     body::BoxBody,
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
     http::StatusCode,
-    Error, HttpMessage, HttpResponse, HttpResponseBuilder, ResponseError,
+    Error, HttpMessage, HttpResponse, ResponseError,
 };
 use futures::future::LocalBoxFuture;
-use http_cache_reqwest::{CACacheManager, Cache, CacheMode, HttpCache, HttpCacheOptions};
 use jsonwebtoken::jwk::JwkSet;
-use reqwest::Client;
-use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 
-use crate::{middleware::error_response::internal_server_error_body, Issuer};
+use crate::{
+    cache::CacheBackend,
+    middleware::{
+        error_response::{
+            error_context, internal_server_error_body, miswired, CorrelatedError, ErrorBody,
+            MiddlewareError,
+        },
+        jwks_http_cache::JwksHttpCache,
+    },
+    stats::StatsCollector,
+    x509::{jwk_set_from_certificates, CertificateError},
+    Issuer, KeySetFormat,
+};
+
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRotation {
+    pub jwks_url: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+type KeyRotationHook = Arc<dyn Fn(&KeyRotation) + Send + Sync>;
+
+// Remembers the kids last seen per key set URL and calls the hook when a
+// refetched key set changes them. The first observation of a URL only records
+// its kids, so the hook fires on changes rather than on startup. Clones share
+// the kids, so create one outside the HttpServer factory closure and hand it
+// to the factory of every worker to be told of each rotation once.
+#[derive(Clone)]
+pub struct KeyRotationTracker {
+    known_kids: Arc<Mutex<HashMap<String, HashSet<String>>>>,
+    hook: KeyRotationHook,
+}
+
+impl KeyRotationTracker {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&KeyRotation) + Send + Sync + 'static,
+    {
+        Self {
+            known_kids: Default::default(),
+            hook: Arc::new(f),
+        }
+    }
+
+    fn observe(&self, jwks_url: &str, jwk_set: &JwkSet) -> Option<KeyRotation> {
+        let kids = jwk_set
+            .keys
+            .iter()
+            .filter_map(|jwk| jwk.common.key_id.clone())
+            .collect::<HashSet<_>>();
+        let mut known_kids = self.known_kids.lock().expect("key rotation lock poisoned");
+        let previous = known_kids.insert(jwks_url.to_string(), kids.clone())?;
+        let mut added = kids.difference(&previous).cloned().collect::<Vec<_>>();
+        let mut removed = previous.difference(&kids).cloned().collect::<Vec<_>>();
+        if added.is_empty() && removed.is_empty() {
+            return None;
+        }
+        added.sort();
+        removed.sort();
+        Some(KeyRotation {
+            jwks_url: jwks_url.to_string(),
+            added,
+            removed,
+        })
+    }
+}
 
 pub struct JwkSetFactory<I: Issuer> {
-    client: Rc<ClientWithMiddleware>,
+    client: JwksHttpCache,
+    cache: Option<Rc<dyn CacheBackend>>,
+    cache_ttl: Duration,
+    key_rotation: Option<KeyRotationTracker>,
     phantom: PhantomData<I>,
 }
 
 impl<I: Issuer> JwkSetFactory<I> {
     pub fn new() -> Self {
-        let client = ClientBuilder::new(Client::new())
-            .with(Cache(HttpCache {
-                mode: CacheMode::Default,
-                manager: CACacheManager::default(),
-                options: HttpCacheOptions::default(),
-            }))
-            .build();
-        let client = Rc::new(client);
+        let client = JwksHttpCache::new();
         Self {
             client,
+            cache: None,
+            cache_ttl: DEFAULT_CACHE_TTL,
+            key_rotation: None,
             phantom: Default::default(),
         }
     }
+
+    pub fn cache(mut self, value: impl CacheBackend + 'static) -> Self {
+        self.cache = Some(Rc::new(value));
+        self
+    }
+
+    pub fn cache_ttl(mut self, value: Duration) -> Self {
+        self.cache_ttl = value;
+        self
+    }
+
+    pub fn http_cache(mut self, enabled: bool) -> Self {
+        self.client = self.client.enabled(enabled);
+        self
+    }
+
+    // Once the issuer's max-age has elapsed, the previous key set keeps being served
+    // for up to `value` while a refresh runs in the background.
+    pub fn stale_while_revalidate(mut self, value: Duration) -> Self {
+        self.client = self.client.stale_while_revalidate(value);
+        self
+    }
+
+    pub fn stats(mut self, value: StatsCollector) -> Self {
+        self.client = self.client.stats(value);
+        self
+    }
+
+    // Stores fetched key sets where `value` can read and refresh them. Hand the
+    // same handle to the factory of every worker.
+    pub fn handle(mut self, value: &JwkSetHandle) -> Self {
+        self.client = self.client.share_entries(&value.client);
+        self
+    }
+
+    pub fn on_key_rotation(mut self, value: &KeyRotationTracker) -> Self {
+        self.key_rotation = Some(value.clone());
+        self
+    }
 }
 
 impl<I: Issuer> Default for JwkSetFactory<I> {
@@ -64,6 +170,9 @@ where
             phantom: Default::default(),
             service: Rc::new(service),
             client: self.client.clone(),
+            cache: self.cache.clone(),
+            cache_ttl: self.cache_ttl,
+            key_rotation: self.key_rotation.clone(),
         };
         ready(Ok(middleware))
     }
@@ -73,7 +182,10 @@ pub struct JwkSetMiddleware<I: Issuer, S> {
     phantom: PhantomData<I>,
     service: Rc<S>,
     // well_known_url: Rc<String>,
-    client: Rc<ClientWithMiddleware>,
+    client: JwksHttpCache,
+    cache: Option<Rc<dyn CacheBackend>>,
+    cache_ttl: Duration,
+    key_rotation: Option<KeyRotationTracker>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -84,6 +196,8 @@ pub enum JwkSetError {
     FetchError(String),
     #[error("unable to deserialize JWK set")]
     DeserializeError,
+    #[error(transparent)]
+    CertificateError(#[from] CertificateError),
 }
 
 impl ResponseError for JwkSetError {
@@ -92,11 +206,163 @@ impl ResponseError for JwkSetError {
     }
 
     fn error_response(&self) -> HttpResponse<BoxBody> {
-        let error_body = internal_server_error_body("NO_JWK_SET", self);
-        HttpResponseBuilder::new(self.status_code()).json(error_body)
+        self.respond(None)
+    }
+}
+
+impl MiddlewareError for JwkSetError {
+    fn error_body(&self) -> ErrorBody {
+        internal_server_error_body("NO_JWK_SET", self)
     }
 }
 
+// Reads and refreshes the in-memory key set of one issuer, for operational
+// endpoints that rotate keys without a restart. Clones share the key set, and
+// only factories given the handle through JwkSetFactory::handle use it. The shared
+// CacheBackend, if any, is bypassed and keeps its entries until their TTL expires.
+#[derive(Debug, Clone)]
+pub struct JwkSetHandle {
+    client: JwksHttpCache,
+    jwks_url: String,
+    key_set_format: KeySetFormat,
+}
+
+impl JwkSetHandle {
+    pub fn new(issuer: &impl Issuer) -> Self {
+        Self {
+            client: JwksHttpCache::new(),
+            jwks_url: issuer.jwks_url(),
+            key_set_format: issuer.key_set_format(),
+        }
+    }
+
+    // The key set last fetched, even past its max-age, or None before the first
+    // fetch or when JwkSetFactory::http_cache is disabled.
+    pub fn current(&self) -> Option<JwkSet> {
+        let body = self.client.cached(&self.jwks_url)?;
+        parse_jwk_set(&body, self.key_set_format)
+            .map_err(|e| log::info!("{}", e))
+            .ok()
+    }
+
+    pub async fn force_refresh(&self) -> Result<JwkSet, JwkSetError> {
+        let body = self
+            .client
+            .refresh(&self.jwks_url)
+            .await
+            .map_err(|e| JwkSetError::FetchError(e.to_string()))?;
+        parse_jwk_set(&body, self.key_set_format)
+    }
+}
+
+fn parse_jwk_set(body: &[u8], key_set_format: KeySetFormat) -> Result<JwkSet, JwkSetError> {
+    match key_set_format {
+        KeySetFormat::Jwks => {
+            serde_json::from_slice::<JwkSet>(body).map_err(|_| JwkSetError::DeserializeError)
+        }
+        KeySetFormat::X509Certificates => {
+            match serde_json::from_slice::<HashMap<String, String>>(body) {
+                Ok(certificates) => jwk_set_from_certificates(&certificates).map_err(Into::into),
+                Err(_) => Err(JwkSetError::DeserializeError),
+            }
+        }
+    }
+}
+
+fn cache_key(jwk_set_url: &str) -> String {
+    format!("jwks:{}", jwk_set_url)
+}
+
+// Cache failures are logged and otherwise ignored so that an unavailable shared
+// backend degrades to fetching from the issuer rather than rejecting requests.
+async fn cached_jwk_set(cache: &dyn CacheBackend, jwk_set_url: &str) -> Option<JwkSet> {
+    let value = cache
+        .get(&cache_key(jwk_set_url))
+        .await
+        .map_err(|e| log::info!("{}", e))
+        .ok()??;
+    serde_json::from_slice(&value)
+        .map_err(|e| log::info!("unable to deserialize cached JWK set: {}", e))
+        .ok()
+}
+
+async fn cache_jwk_set(
+    cache: &dyn CacheBackend,
+    jwk_set_url: &str,
+    jwk_set: &JwkSet,
+    ttl: Duration,
+) {
+    let Ok(value) = serde_json::to_vec(jwk_set) else {
+        return;
+    };
+    if let Err(e) = cache.set(&cache_key(jwk_set_url), value, ttl).await {
+        log::info!("{}", e);
+    }
+}
+
+// Also tells whether the key set was fetched from the issuer since it was last
+// loaded, which is when key rotations are looked for.
+async fn load_jwk_set(
+    jwk_set_url: &str,
+    key_set_format: KeySetFormat,
+    client: &JwksHttpCache,
+    cache: Option<&dyn CacheBackend>,
+    cache_ttl: Duration,
+) -> Result<(JwkSet, bool), JwkSetError> {
+    if let Some(cache) = cache {
+        if let Some(jwk_set) = cached_jwk_set(cache, jwk_set_url).await {
+            client.record_hit();
+            return Ok((jwk_set, false));
+        }
+    }
+    let response = client
+        .get(jwk_set_url)
+        .await
+        .map_err(|e| JwkSetError::FetchError(e.to_string()))
+        .map_err(|e| {
+            log::info!("{}", e);
+            e
+        })?;
+    let jwk_set = parse_jwk_set(&response.body, key_set_format).map_err(|e| {
+        log::info!("{}", e);
+        e
+    })?;
+    if let Some(cache) = cache {
+        cache_jwk_set(cache, jwk_set_url, &jwk_set, cache_ttl).await;
+    }
+    Ok((jwk_set, response.refetched))
+}
+
+async fn fetch_jwk_set<I: Issuer + 'static>(
+    req: &ServiceRequest,
+    client: &JwksHttpCache,
+    cache: Option<&dyn CacheBackend>,
+    cache_ttl: Duration,
+    key_rotation: Option<&KeyRotationTracker>,
+) -> Result<JwkSet, JwkSetError> {
+    let (jwk_set_url, key_set_format) = {
+        let extensions = req.extensions();
+        let issuer = extensions
+            .get::<I>()
+            .ok_or_else(|| miswired(JwkSetError::NoIssuer, "IssuerFactory", "JwkSetFactory"))?;
+        (issuer.jwks_url(), issuer.key_set_format())
+    };
+    let (jwk_set, refetched) =
+        load_jwk_set(&jwk_set_url, key_set_format, client, cache, cache_ttl).await?;
+    if let Some(key_rotation) = key_rotation.filter(|_| refetched) {
+        if let Some(rotation) = key_rotation.observe(&jwk_set_url, &jwk_set) {
+            log::info!(
+                "JWK set {} rotated: added {:?}, removed {:?}",
+                rotation.jwks_url,
+                rotation.added,
+                rotation.removed
+            );
+            (key_rotation.hook)(&rotation);
+        }
+    }
+    Ok(jwk_set)
+}
+
 impl<I, S, B> Service<ServiceRequest> for JwkSetMiddleware<I, S>
 where
     I: Issuer + 'static,
@@ -111,28 +377,20 @@ where
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let service = self.service.clone();
         let client = self.client.clone();
+        let cache = self.cache.clone();
+        let cache_ttl = self.cache_ttl;
+        let key_rotation = self.key_rotation.clone();
         Box::pin(async move {
-            let extensions = req.extensions();
-            let issuer = extensions.get::<I>().ok_or(JwkSetError::NoIssuer)?;
-            let url = issuer.url();
-            let jwk_set_url = format!("{url}/.well-known/jwks.json");
-            let jwk_set = client
-                .get(jwk_set_url)
-                .send()
-                .await
-                .map_err(|e| JwkSetError::FetchError(e.to_string()))
-                .map_err(|e| {
-                    log::info!("{}", e);
-                    e
-                })?
-                .json::<JwkSet>()
-                .await
-                .map_err(|_| JwkSetError::DeserializeError)
-                .map_err(|e| {
-                    log::info!("{}", e);
-                    e
-                })?;
-            drop(extensions);
+            let context = error_context(&req);
+            let jwk_set = fetch_jwk_set::<I>(
+                &req,
+                &client,
+                cache.as_deref(),
+                cache_ttl,
+                key_rotation.as_ref(),
+            )
+            .await
+            .map_err(|e| CorrelatedError::new(e, &context))?;
             req.extensions_mut().insert(jwk_set);
             let res = service.call(req).await?;
             Ok(res)
@@ -141,3 +399,85 @@ where
 
     forward_ready!(service);
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn jwk_set(kids: &[&str]) -> JwkSet {
+        let keys = kids
+            .iter()
+            .map(|kid| serde_json::json!({ "kty": "oct", "k": "c2VjcmV0", "kid": kid }))
+            .collect::<Vec<_>>();
+        serde_json::from_value(serde_json::json!({ "keys": keys })).unwrap()
+    }
+
+    fn tracker() -> KeyRotationTracker {
+        KeyRotationTracker::new(|_| {})
+    }
+
+    struct TestIssuer;
+
+    impl Issuer for TestIssuer {
+        fn url(&self) -> String {
+            "https://issuer".to_string()
+        }
+    }
+
+    #[test]
+    fn handle_has_no_key_set_before_the_first_fetch() {
+        let handle = JwkSetHandle::new(&TestIssuer);
+        assert_eq!(handle.jwks_url, "https://issuer/.well-known/jwks.json");
+        assert!(handle.current().is_none());
+    }
+
+    #[test]
+    fn key_sets_parse_by_format() {
+        let body = serde_json::to_vec(&jwk_set(&["a"])).unwrap();
+        let parsed = parse_jwk_set(&body, KeySetFormat::Jwks).unwrap();
+        assert_eq!(parsed.keys[0].common.key_id.as_deref(), Some("a"));
+        assert!(parse_jwk_set(&body, KeySetFormat::X509Certificates).is_err());
+    }
+
+    #[test]
+    fn first_observation_is_not_a_rotation() {
+        let tracker = tracker();
+        assert_eq!(
+            tracker.observe("https://issuer/jwks", &jwk_set(&["a"])),
+            None
+        );
+        assert_eq!(
+            tracker.observe("https://issuer/jwks", &jwk_set(&["a"])),
+            None
+        );
+    }
+
+    #[test]
+    fn clones_report_each_rotation_once() {
+        let tracker = tracker();
+        let worker = tracker.clone();
+        tracker.observe("https://issuer/jwks", &jwk_set(&["a"]));
+        assert!(worker
+            .observe("https://issuer/jwks", &jwk_set(&["a", "b"]))
+            .is_some());
+        assert_eq!(
+            tracker.observe("https://issuer/jwks", &jwk_set(&["a", "b"])),
+            None
+        );
+    }
+
+    #[test]
+    fn rotation_reports_added_and_removed_kids() {
+        let tracker = tracker();
+        tracker.observe("https://issuer/jwks", &jwk_set(&["a", "b"]));
+        let rotation = tracker.observe("https://issuer/jwks", &jwk_set(&["b", "d", "c"]));
+        assert_eq!(
+            rotation,
+            Some(KeyRotation {
+                jwks_url: "https://issuer/jwks".to_string(),
+                added: vec!["c".to_string(), "d".to_string()],
+                removed: vec!["a".to_string()],
+            })
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use reqwest::{
+    header::{HeaderMap, AGE, CACHE_CONTROL, ETAG, IF_NONE_MATCH},
+    Client, StatusCode,
+};
+
+use crate::stats::StatsCollector;
+
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    body: Vec<u8>,
+    etag: Option<String>,
+    expires_at: Instant,
+    revalidating: bool,
+    // Set by every fetch and cleared by the first `get` that serves the body.
+    refetched: bool,
+}
+
+impl CachedResponse {
+    fn is_fresh(&self, now: Instant) -> bool {
+        now < self.expires_at
+    }
+
+    fn is_servable_stale(&self, now: Instant, stale_while_revalidate: Duration) -> bool {
+        now < self.expires_at + stale_while_revalidate
+    }
+}
+
+// A body, and whether it was fetched from the issuer since it was last served.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct JwksResponse {
+    pub body: Vec<u8>,
+    pub refetched: bool,
+}
+
+// Responses are kept in memory only, so nothing is written to disk in read-only
+// containers. Clones share their entries.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct JwksHttpCache {
+    client: Client,
+    entries: Arc<Mutex<HashMap<String, CachedResponse>>>,
+    disabled: bool,
+    stale_while_revalidate: Duration,
+    stats: Option<StatsCollector>,
+}
+
+fn max_age(headers: &HeaderMap) -> Duration {
+    let directives = headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|directive| directive.trim().to_ascii_lowercase());
+    let mut max_age = None;
+    for directive in directives {
+        match directive.split_once('=') {
+            _ if directive == "no-cache" || directive == "no-store" => return Duration::ZERO,
+            Some(("max-age", seconds)) => max_age = seconds.trim_matches('"').parse().ok(),
+            _ => {}
+        }
+    }
+    let age = headers
+        .get(AGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0);
+    Duration::from_secs(max_age.unwrap_or(0u64).saturating_sub(age))
+}
+
+fn etag(headers: &HeaderMap) -> Option<String> {
+    let etag = headers.get(ETAG)?.to_str().ok()?;
+    Some(etag.to_string())
+}
+
+impl JwksHttpCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enabled(mut self, value: bool) -> Self {
+        self.disabled = !value;
+        self
+    }
+
+    pub fn stale_while_revalidate(mut self, value: Duration) -> Self {
+        self.stale_while_revalidate = value;
+        self
+    }
+
+    pub fn stats(mut self, value: StatsCollector) -> Self {
+        self.stats = Some(value);
+        self
+    }
+
+    // Reads and writes `other`'s entries from now on, keeping this cache's settings.
+    pub fn share_entries(mut self, other: &JwksHttpCache) -> Self {
+        self.entries = other.entries.clone();
+        self
+    }
+
+    pub fn record_hit(&self) {
+        if let Some(stats) = &self.stats {
+            stats.jwks_hit();
+        }
+    }
+
+    fn entry(&self, url: &str) -> Option<CachedResponse> {
+        if self.disabled {
+            return None;
+        }
+        let entries = self.entries.lock().expect("expected JWKS cache lock");
+        entries.get(url).cloned()
+    }
+
+    fn store(
+        &self,
+        url: &str,
+        body: Vec<u8>,
+        etag: Option<String>,
+        headers: &HeaderMap,
+        now: Instant,
+    ) -> Vec<u8> {
+        if self.disabled {
+            return body;
+        }
+        let entry = CachedResponse {
+            body: body.clone(),
+            etag,
+            expires_at: now + max_age(headers),
+            revalidating: false,
+            refetched: true,
+        };
+        let mut entries = self.entries.lock().expect("expected JWKS cache lock");
+        entries.insert(url.to_string(), entry);
+        body
+    }
+
+    // Claims the stale entry for revalidation so that concurrent requests keep
+    // serving it instead of spawning a refresh each.
+    fn begin_revalidation(&self, url: &str, now: Instant) -> Option<Vec<u8>> {
+        let mut entries = self.entries.lock().expect("expected JWKS cache lock");
+        let entry = entries.get_mut(url)?;
+        if !entry.is_servable_stale(now, self.stale_while_revalidate) {
+            return None;
+        }
+        let spawn = !entry.revalidating;
+        entry.revalidating = true;
+        if spawn {
+            let cache = self.clone();
+            let url = url.to_string();
+            actix_web::rt::spawn(async move {
+                if let Err(e) = cache.fetch(&url).await {
+                    log::info!("unable to revalidate JWK set: {}", e);
+                    cache.end_revalidation(&url);
+                }
+            });
+        }
+        Some(entry.body.clone())
+    }
+
+    fn end_revalidation(&self, url: &str) {
+        let mut entries = self.entries.lock().expect("expected JWKS cache lock");
+        if let Some(entry) = entries.get_mut(url) {
+            entry.revalidating = false;
+        }
+    }
+
+    // Background revalidations store their body without serving it, so the
+    // flag is read when the body is next served rather than when it is fetched.
+    fn take_refetched(&self, url: &str) -> bool {
+        let mut entries = self.entries.lock().expect("expected JWKS cache lock");
+        entries
+            .get_mut(url)
+            .is_some_and(|entry| std::mem::take(&mut entry.refetched))
+    }
+
+    // The last body stored for `url`, fresh or not.
+    pub fn cached(&self, url: &str) -> Option<Vec<u8>> {
+        self.entry(url).map(|cached| cached.body)
+    }
+
+    // Fetches `url` regardless of the cached entry's freshness. The request is
+    // still conditional on the stored ETag.
+    pub async fn refresh(&self, url: &str) -> Result<Vec<u8>, reqwest::Error> {
+        self.fetch(url).await
+    }
+
+    pub async fn get(&self, url: &str) -> Result<JwksResponse, reqwest::Error> {
+        let now = Instant::now();
+        let cached = match self.entry(url).filter(|cached| cached.is_fresh(now)) {
+            Some(cached) => Some(cached.body),
+            None if !self.disabled && !self.stale_while_revalidate.is_zero() => {
+                self.begin_revalidation(url, now)
+            }
+            None => None,
+        };
+        if let Some(body) = cached {
+            self.record_hit();
+            let refetched = self.take_refetched(url);
+            return Ok(JwksResponse { body, refetched });
+        }
+        let body = self.fetch(url).await?;
+        self.take_refetched(url);
+        Ok(JwksResponse {
+            body,
+            refetched: true,
+        })
+    }
+
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>, reqwest::Error> {
+        let body = self.request(url).await;
+        if let Some(stats) = &self.stats {
+            stats.jwks_fetch(body.is_ok());
+        }
+        body
+    }
+
+    async fn request(&self, url: &str) -> Result<Vec<u8>, reqwest::Error> {
+        let cached = self.entry(url);
+        let mut request = self.client.get(url);
+        if let Some(etag) = cached.as_ref().and_then(|cached| cached.etag.as_ref()) {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await?;
+        let now = Instant::now();
+        let headers = response.headers().clone();
+        match (response.status(), cached) {
+            (StatusCode::NOT_MODIFIED, Some(cached)) => {
+                let etag = etag(&headers).or(cached.etag);
+                Ok(self.store(url, cached.body, etag, &headers, now))
+            }
+            _ => {
+                let response = response.error_for_status()?;
+                let body = response.bytes().await?.to_vec();
+                Ok(self.store(url, body, etag(&headers), &headers, now))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    fn headers(values: &[(reqwest::header::HeaderName, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in values {
+            headers.append(name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn max_age_reads_cache_control() {
+        let headers = headers(&[(CACHE_CONTROL, "public, max-age=3600, must-revalidate")]);
+        assert_eq!(max_age(&headers), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn max_age_subtracts_age() {
+        let headers = headers(&[(CACHE_CONTROL, "max-age=3600"), (AGE, "600")]);
+        assert_eq!(max_age(&headers), Duration::from_secs(3000));
+    }
+
+    #[test]
+    fn max_age_is_zero_when_caching_is_disallowed() {
+        let no_cache = headers(&[(CACHE_CONTROL, "max-age=3600, no-cache")]);
+        assert_eq!(max_age(&no_cache), Duration::ZERO);
+        let missing = headers(&[]);
+        assert_eq!(max_age(&missing), Duration::ZERO);
+    }
+
+    #[test]
+    fn store_records_etag_and_expiry() {
+        let cache = JwksHttpCache::new();
+        let now = Instant::now();
+        let headers = headers(&[(CACHE_CONTROL, "max-age=60"), (ETAG, "\"abc\"")]);
+        cache.store(
+            "https://issuer/jwks",
+            b"{}".to_vec(),
+            etag(&headers),
+            &headers,
+            now,
+        );
+        let entry = cache.entry("https://issuer/jwks").unwrap();
+        assert_eq!(entry.etag.as_deref(), Some("\"abc\""));
+        assert!(entry.is_fresh(now + Duration::from_secs(59)));
+        assert!(!entry.is_fresh(now + Duration::from_secs(60)));
+    }
+
+    #[actix_web::test]
+    async fn fetched_bodies_are_reported_once() {
+        let cache = JwksHttpCache::new();
+        let headers = headers(&[(CACHE_CONTROL, "max-age=60")]);
+        let now = Instant::now();
+        cache.store("https://issuer/jwks", b"{}".to_vec(), None, &headers, now);
+        let response = cache.get("https://issuer/jwks").await.unwrap();
+        assert!(response.refetched);
+        let response = cache.get("https://issuer/jwks").await.unwrap();
+        assert_eq!(response.body, b"{}".to_vec());
+        assert!(!response.refetched);
+    }
+
+    #[test]
+    fn disabled_cache_stores_nothing() {
+        let cache = JwksHttpCache::new().enabled(false);
+        let headers = headers(&[(CACHE_CONTROL, "max-age=60")]);
+        let now = Instant::now();
+        cache.store("https://issuer/jwks", b"{}".to_vec(), None, &headers, now);
+        assert!(cache.entry("https://issuer/jwks").is_none());
+    }
+
+    #[test]
+    fn shared_entries_are_visible_regardless_of_freshness() {
+        let cache = JwksHttpCache::new();
+        let shared = JwksHttpCache::new().share_entries(&cache);
+        let headers = headers(&[(CACHE_CONTROL, "no-store")]);
+        shared.store(
+            "https://issuer/jwks",
+            b"{}".to_vec(),
+            None,
+            &headers,
+            Instant::now(),
+        );
+        assert_eq!(cache.cached("https://issuer/jwks"), Some(b"{}".to_vec()));
+        assert!(cache.cached("https://other/jwks").is_none());
+    }
+
+    #[test]
+    fn stale_entries_are_served_within_revalidation_window() {
+        let now = Instant::now();
+        let entry = CachedResponse {
+            body: Vec::new(),
+            etag: None,
+            expires_at: now,
+            revalidating: false,
+            refetched: false,
+        };
+        let window = Duration::from_secs(30);
+        assert!(!entry.is_fresh(now));
+        assert!(entry.is_servable_stale(now + Duration::from_secs(29), window));
+        assert!(!entry.is_servable_stale(now + Duration::from_secs(30), window));
+    }
+}
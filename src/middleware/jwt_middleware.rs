@@ -1,36 +1,154 @@
 use std::{
+    collections::HashMap,
     future::{ready, Ready},
     rc::Rc,
+    time::{Duration, Instant},
 };
 
 use actix_web::{
     body::BoxBody,
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    http::StatusCode,
-    Error, HttpMessage, HttpResponse, HttpResponseBuilder, ResponseError,
+    http::{Method, StatusCode},
+    web, Error, HttpMessage, HttpResponse, ResponseError,
 };
 use futures::future::LocalBoxFuture;
 use jsonwebtoken::jwk::JwkSet;
 
+#[cfg(feature = "cookies")]
+use crate::middleware::TokenCookie;
+#[cfg(feature = "dpop")]
+use crate::middleware::{DPoPError, DPoPVerifier};
 use crate::{
     claims::AuthorizationClaims,
-    middleware::error_response::{forbidden_error_body, internal_server_error_body},
-    EncodedToken,
+    middleware::{
+        error_response::{
+            error_context, internal_server_error_body, miswired, unauthorized_error_body,
+            BearerChallenge, BearerError, CorrelatedError, ErrorBody, MiddlewareError,
+        },
+        is_dpop_bound, AuthScheme, RuntimeToggle, ValidationOutcome,
+    },
+    stats::StatsCollector,
+    AccessToken, AuthEvent, AuthEventKind, AuthEvents, DecodeOptions, EncodedToken,
 };
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenSource {
+    Authorization,
+    Header(String),
+    Cookie(String),
+    Query(String),
+    // The cookie configured by the TokenCookie registered with App::app_data.
+    #[cfg(feature = "cookies")]
+    EncryptedCookie,
+}
+
 pub struct JWTFactory {
-    enabled: bool,
+    enabled: RuntimeToggle,
+    scope_prefix: Option<String>,
+    decode_options: DecodeOptions,
+    auth_schemes: Vec<AuthScheme>,
+    token_sources: Vec<TokenSource>,
+    strict_token_sources: bool,
+    stats: Option<StatsCollector>,
+    events: Option<AuthEvents>,
+    expired_grace: Option<Duration>,
+    #[cfg(feature = "dpop")]
+    dpop: DPoPVerifier,
+}
+
+// How long past exp AuthorizationFactory still accepts the request's token;
+// present only on requests the grace window applies to.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ExpiryGrace(pub Duration);
+
+// Only safe methods get the grace window, so a retried read succeeds with a
+// token that expired in flight while writes keep strict expiry.
+fn expiry_grace(method: &Method, grace: Option<Duration>) -> Option<ExpiryGrace> {
+    let safe = *method == Method::GET || *method == Method::HEAD;
+    grace.filter(|_| safe).map(ExpiryGrace)
 }
 
 impl JWTFactory {
     pub fn new() -> Self {
-        Self { enabled: true }
+        Self {
+            enabled: RuntimeToggle::default(),
+            scope_prefix: None,
+            decode_options: DecodeOptions::default(),
+            auth_schemes: vec![AuthScheme::Bearer],
+            token_sources: vec![TokenSource::Authorization],
+            strict_token_sources: false,
+            stats: None,
+            events: None,
+            expired_grace: None,
+            #[cfg(feature = "dpop")]
+            dpop: DPoPVerifier::new(),
+        }
     }
 
     pub fn enabled(mut self, value: bool) -> Self {
+        self.enabled = RuntimeToggle::new(value);
+        self
+    }
+
+    // Replaces enabled() with a switch that can be flipped while serving.
+    pub fn toggle(mut self, value: RuntimeToggle) -> Self {
         self.enabled = value;
         self
     }
+
+    pub fn scope_prefix(mut self, value: &str) -> Self {
+        self.scope_prefix = Some(value.to_string());
+        self
+    }
+
+    pub fn decode_options(mut self, value: DecodeOptions) -> Self {
+        self.decode_options = value;
+        self
+    }
+
+    // AuthScheme::DPoP is only accepted with the dpop feature, which verifies
+    // the DPoP proof sent alongside the token.
+    pub fn auth_schemes(mut self, value: &[AuthScheme]) -> Self {
+        self.auth_schemes = value.to_vec();
+        self
+    }
+
+    #[cfg(feature = "dpop")]
+    pub fn dpop(mut self, value: DPoPVerifier) -> Self {
+        self.dpop = value;
+        self
+    }
+
+    // Sources are tried in order and the first one holding a token wins.
+    pub fn token_sources(mut self, value: Vec<TokenSource>) -> Self {
+        self.token_sources = value;
+        self
+    }
+
+    // Rejects requests where several sources hold different tokens instead of
+    // silently picking the first.
+    pub fn strict_token_sources(mut self, value: bool) -> Self {
+        self.strict_token_sources = value;
+        self
+    }
+
+    pub fn stats(mut self, value: StatsCollector) -> Self {
+        self.stats = Some(value);
+        self
+    }
+
+    // Emits an Unauthenticated event for every rejected token.
+    pub fn events(mut self, value: AuthEvents) -> Self {
+        self.events = Some(value);
+        self
+    }
+
+    // Accepts GET and HEAD requests whose token expired at most `value` ago,
+    // on top of the decode leeway.
+    pub fn expired_grace(mut self, value: Duration) -> Self {
+        self.expired_grace = Some(value);
+        self
+    }
 }
 
 impl Default for JWTFactory {
@@ -54,7 +172,17 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         let middleware = JWTMiddleware {
             service: Rc::new(service),
-            enabled: Rc::new(self.enabled),
+            enabled: self.enabled.clone(),
+            scope_prefix: Rc::new(self.scope_prefix.clone()),
+            decode_options: Rc::new(self.decode_options.clone()),
+            auth_schemes: Rc::new(self.auth_schemes.clone()),
+            token_sources: Rc::new(self.token_sources.clone()),
+            strict_token_sources: self.strict_token_sources,
+            stats: self.stats.clone(),
+            events: self.events.clone(),
+            expired_grace: self.expired_grace,
+            #[cfg(feature = "dpop")]
+            dpop: self.dpop.clone(),
         };
         ready(Ok(middleware))
     }
@@ -62,7 +190,17 @@ where
 
 pub struct JWTMiddleware<S> {
     service: Rc<S>,
-    enabled: Rc<bool>,
+    enabled: RuntimeToggle,
+    scope_prefix: Rc<Option<String>>,
+    decode_options: Rc<DecodeOptions>,
+    auth_schemes: Rc<Vec<AuthScheme>>,
+    token_sources: Rc<Vec<TokenSource>>,
+    strict_token_sources: bool,
+    stats: Option<StatsCollector>,
+    events: Option<AuthEvents>,
+    expired_grace: Option<Duration>,
+    #[cfg(feature = "dpop")]
+    dpop: DPoPVerifier,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -71,6 +209,15 @@ pub enum JWTMiddlewareError {
     NoAuthorizationHeader,
     #[error("authorization header is invalid")]
     InvalidAuthorizationHeader,
+    #[error("authorization scheme {0} is not accepted")]
+    UnsupportedScheme(AuthScheme),
+    #[error("DPoP-bound token must be presented with the DPoP scheme")]
+    DPoPBoundToken,
+    #[cfg(feature = "dpop")]
+    #[error("{0}")]
+    InvalidDPoPProof(DPoPError),
+    #[error("request carries conflicting tokens")]
+    ConflictingTokens,
     #[error("no JWK set available")]
     NoJWKSet,
     #[error("encoded token is not valid")]
@@ -81,25 +228,164 @@ impl ResponseError for JWTMiddlewareError {
     fn status_code(&self) -> StatusCode {
         match self {
             JWTMiddlewareError::NoJWKSet => StatusCode::INTERNAL_SERVER_ERROR,
-            _ => StatusCode::FORBIDDEN,
+            _ => StatusCode::UNAUTHORIZED,
         }
     }
 
     fn error_response(&self) -> HttpResponse<BoxBody> {
-        let error_body = match self {
+        self.respond(None)
+    }
+}
+
+impl MiddlewareError for JWTMiddlewareError {
+    fn error_body(&self) -> ErrorBody {
+        match self {
             JWTMiddlewareError::NoJWKSet => internal_server_error_body("NO_JWK_SET", self),
             JWTMiddlewareError::NoAuthorizationHeader => {
-                forbidden_error_body("NO_AUTHORIZATION_HEADER", self)
+                unauthorized_error_body("NO_AUTHORIZATION_HEADER", self)
             }
             JWTMiddlewareError::InvalidAuthorizationHeader => {
-                forbidden_error_body("INVALID_AUTHORIZATION_HEADER", self)
+                unauthorized_error_body("INVALID_AUTHORIZATION_HEADER", self)
+            }
+            JWTMiddlewareError::UnsupportedScheme(_) => {
+                unauthorized_error_body("UNSUPPORTED_AUTH_SCHEME", self)
+            }
+            JWTMiddlewareError::DPoPBoundToken => unauthorized_error_body("DPOP_BOUND_TOKEN", self),
+            #[cfg(feature = "dpop")]
+            JWTMiddlewareError::InvalidDPoPProof(_) => {
+                unauthorized_error_body("INVALID_DPOP_PROOF", self)
+            }
+            JWTMiddlewareError::ConflictingTokens => {
+                unauthorized_error_body("CONFLICTING_TOKENS", self)
             }
             JWTMiddlewareError::InvalidEncodedToken => {
-                forbidden_error_body("INVALID_ENCODED_TOKEN", self)
+                unauthorized_error_body("INVALID_ENCODED_TOKEN", self)
             }
-        };
-        HttpResponseBuilder::new(self.status_code()).json(error_body)
+        }
+    }
+
+    fn challenge(&self) -> Option<BearerChallenge> {
+        let challenge = BearerChallenge::new();
+        match self {
+            JWTMiddlewareError::NoJWKSet => None,
+            JWTMiddlewareError::NoAuthorizationHeader => Some(challenge),
+            JWTMiddlewareError::InvalidAuthorizationHeader
+            | JWTMiddlewareError::UnsupportedScheme(_)
+            | JWTMiddlewareError::ConflictingTokens => {
+                Some(challenge.error(BearerError::InvalidRequest))
+            }
+            JWTMiddlewareError::DPoPBoundToken | JWTMiddlewareError::InvalidEncodedToken => {
+                Some(challenge.error(BearerError::InvalidToken))
+            }
+            #[cfg(feature = "dpop")]
+            JWTMiddlewareError::InvalidDPoPProof(_) => {
+                Some(challenge.error(BearerError::InvalidToken))
+            }
+        }
+    }
+}
+
+// Tokens found outside the Authorization header carry no scheme and are treated
+// as bearer tokens.
+fn find_token(
+    req: &ServiceRequest,
+    source: &TokenSource,
+) -> Option<Result<(AuthScheme, String), JWTMiddlewareError>> {
+    match source {
+        TokenSource::Authorization => {
+            let value = req.headers().get("Authorization")?;
+            let credentials = value
+                .to_str()
+                .ok()
+                .and_then(AuthScheme::parse)
+                .map(|(scheme, credentials)| (scheme, credentials.to_string()))
+                .ok_or(JWTMiddlewareError::InvalidAuthorizationHeader);
+            Some(credentials)
+        }
+        TokenSource::Header(name) => {
+            let value = req.headers().get(name)?.to_str().ok()?;
+            Some(Ok((AuthScheme::Bearer, value.trim().to_string())))
+        }
+        TokenSource::Cookie(name) => {
+            let cookie = req.cookie(name)?;
+            Some(Ok((AuthScheme::Bearer, cookie.value().to_string())))
+        }
+        TokenSource::Query(name) => {
+            let query = web::Query::<HashMap<String, String>>::from_query(req.query_string());
+            let token = query.ok()?.get(name)?.clone();
+            Some(Ok((AuthScheme::Bearer, token)))
+        }
+        #[cfg(feature = "cookies")]
+        TokenSource::EncryptedCookie => {
+            let Some(token_cookie) = req.app_data::<TokenCookie>() else {
+                log::warn!("TokenSource::EncryptedCookie requires a TokenCookie in app_data");
+                return None;
+            };
+            let cookie = req.cookie(token_cookie.name())?;
+            let token = token_cookie
+                .decrypt(cookie)
+                .ok_or(JWTMiddlewareError::InvalidEncodedToken);
+            Some(token.map(|token| (AuthScheme::Bearer, token)))
+        }
+    }
+}
+
+fn extract_token(
+    req: &ServiceRequest,
+    sources: &[TokenSource],
+    strict: bool,
+    auth_schemes: &[AuthScheme],
+) -> Result<(AuthScheme, String), JWTMiddlewareError> {
+    let mut found = sources.iter().filter_map(|source| find_token(req, source));
+    let (scheme, credentials) = found
+        .next()
+        .ok_or(JWTMiddlewareError::NoAuthorizationHeader)??;
+    if strict {
+        for other in found {
+            let (_, other) = other?;
+            if other != credentials {
+                return Err(JWTMiddlewareError::ConflictingTokens);
+            }
+        }
+    }
+    // Without the dpop feature there is no proof verification to back the scheme.
+    let supported = cfg!(feature = "dpop") || scheme != AuthScheme::DPoP;
+    if !supported || !auth_schemes.contains(&scheme) {
+        return Err(JWTMiddlewareError::UnsupportedScheme(scheme));
+    }
+    Ok((scheme, credentials))
+}
+
+pub(crate) fn authenticate(
+    req: &ServiceRequest,
+    scheme: AuthScheme,
+    credentials: String,
+    scope_prefix: Option<&str>,
+    decode_options: &DecodeOptions,
+    stats: Option<&StatsCollector>,
+) -> Result<AccessToken, JWTMiddlewareError> {
+    let jwk_set = req
+        .extensions()
+        .get::<JwkSet>()
+        .ok_or_else(|| miswired(JWTMiddlewareError::NoJWKSet, "JwkSetFactory", "JWTFactory"))?
+        .clone();
+    let encoded_token: EncodedToken<AuthorizationClaims> = credentials.into();
+    let token = encoded_token.decode_with(&jwk_set, scope_prefix, decode_options);
+    if let Some(stats) = stats {
+        stats.decode(token.is_ok());
+    }
+    let token = token
+        .map_err(|_| JWTMiddlewareError::InvalidEncodedToken)
+        .map_err(|e| {
+            log::info!("{}", e);
+            e
+        })?;
+    if scheme == AuthScheme::Bearer && is_dpop_bound(&token) {
+        let e = JWTMiddlewareError::DPoPBoundToken;
+        log::info!("{}", e);
+        return Err(e);
     }
+    Ok(token)
 }
 
 impl<S, B> Service<ServiceRequest> for JWTMiddleware<S>
@@ -115,44 +401,76 @@ where
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let service = self.service.clone();
         let enabled = self.enabled.clone();
+        let scope_prefix = self.scope_prefix.clone();
+        let decode_options = self.decode_options.clone();
+        let auth_schemes = self.auth_schemes.clone();
+        let token_sources = self.token_sources.clone();
+        let strict_token_sources = self.strict_token_sources;
+        let stats = self.stats.clone();
+        let events = self.events.clone();
+        let expired_grace = self.expired_grace;
+        #[cfg(feature = "dpop")]
+        let dpop = self.dpop.clone();
         Box::pin(async move {
-            if !*enabled {
+            // A token stored by an earlier authenticator, such as an
+            // actix-web-httpauth validator, is not decoded a second time.
+            if !enabled.is_enabled() || req.extensions().contains::<AccessToken>() {
                 let res = service.call(req).await?;
                 return Ok(res);
             }
 
-            let headers = req.headers();
-            let auth = headers
-                .get("Authorization")
-                .ok_or(JWTMiddlewareError::NoAuthorizationHeader)
-                .map_err(|e| {
-                    log::info!("{}", e);
-                    e
-                })?
-                .to_str()
-                .map_err(|_| JWTMiddlewareError::InvalidAuthorizationHeader)
-                .map_err(|e| {
-                    log::info!("{}", e);
-                    e
-                })?;
-            let jwk_set = req
-                .extensions()
-                .get::<JwkSet>()
-                .ok_or(JWTMiddlewareError::NoJWKSet)
-                .map_err(|e| {
-                    log::info!("{}", e);
-                    e
-                })?
-                .clone();
-            let encoded_token: EncodedToken<AuthorizationClaims> = auth.into();
-            let token = encoded_token
-                .decode(&jwk_set)
-                .map_err(|_| JWTMiddlewareError::InvalidEncodedToken)
-                .map_err(|e| {
-                    log::info!("{}", e);
-                    e
-                })?;
+            let started = Instant::now();
+            let context = error_context(&req);
+            let grace = expiry_grace(req.method(), expired_grace);
+            let decode_options = match grace {
+                Some(ExpiryGrace(grace)) => {
+                    let leeway = decode_options.expiry_leeway() + grace;
+                    Rc::new(decode_options.as_ref().clone().leeway(leeway))
+                }
+                None => decode_options,
+            };
+            let authenticated =
+                extract_token(&req, &token_sources, strict_token_sources, &auth_schemes)
+                    .map_err(|e| {
+                        log::info!("{}", e);
+                        e
+                    })
+                    .and_then(|(scheme, credentials)| {
+                        let token = authenticate(
+                            &req,
+                            scheme,
+                            credentials.clone(),
+                            scope_prefix.as_deref(),
+                            &decode_options,
+                            stats.as_ref(),
+                        )?;
+                        #[cfg(feature = "dpop")]
+                        if scheme == AuthScheme::DPoP {
+                            dpop.verify(&req, &credentials, &token)
+                                .map_err(JWTMiddlewareError::InvalidDPoPProof)
+                                .map_err(|e| {
+                                    log::info!("{}", e);
+                                    e
+                                })?;
+                        }
+                        Ok((scheme, token))
+                    });
+            let outcome = match &authenticated {
+                Ok(_) => ValidationOutcome::new(true, false, Vec::new()),
+                Err(e) => ValidationOutcome::new(false, false, vec![e.to_string()]),
+            };
+            req.extensions_mut().insert(outcome);
+            if let (Some(events), Err(e)) = (events.as_ref(), &authenticated) {
+                let reason = Some(e.to_string());
+                let event = AuthEvent::new(AuthEventKind::Unauthenticated, &req, reason, started);
+                events.emit(event);
+            }
+            let (scheme, token) = authenticated.map_err(|e| CorrelatedError::new(e, &context))?;
+            req.extensions_mut().insert(scheme);
             req.extensions_mut().insert(token);
+            if let Some(grace) = grace {
+                req.extensions_mut().insert(grace);
+            }
             let res = service.call(req).await?;
             Ok(res)
         })
@@ -160,3 +478,105 @@ where
 
     forward_ready!(service);
 }
+
+#[cfg(test)]
+mod test {
+    use actix_web::{cookie::Cookie, test::TestRequest};
+
+    use super::*;
+
+    fn sources() -> Vec<TokenSource> {
+        vec![
+            TokenSource::Authorization,
+            TokenSource::Cookie("access_token".to_string()),
+            TokenSource::Query("access_token".to_string()),
+        ]
+    }
+
+    #[test]
+    fn expiry_grace_only_applies_to_safe_methods() {
+        let grace = Some(Duration::from_secs(30));
+        assert!(expiry_grace(&Method::GET, grace).is_some());
+        assert!(expiry_grace(&Method::HEAD, grace).is_some());
+        assert!(expiry_grace(&Method::POST, grace).is_none());
+        assert!(expiry_grace(&Method::GET, None).is_none());
+    }
+
+    #[test]
+    fn first_matching_source_wins() {
+        let req = TestRequest::default()
+            .uri("/?access_token=query")
+            .cookie(Cookie::new("access_token", "cookie"))
+            .to_srv_request();
+        let (scheme, token) =
+            extract_token(&req, &sources(), false, &[AuthScheme::Bearer]).expect("expected token");
+        assert_eq!((scheme, token.as_str()), (AuthScheme::Bearer, "cookie"));
+    }
+
+    #[test]
+    fn strict_mode_rejects_conflicting_tokens() {
+        let req = TestRequest::default()
+            .uri("/?access_token=query")
+            .insert_header(("Authorization", "Bearer header"))
+            .to_srv_request();
+        let result = extract_token(&req, &sources(), true, &[AuthScheme::Bearer]);
+        assert!(matches!(result, Err(JWTMiddlewareError::ConflictingTokens)));
+
+        let req = TestRequest::default()
+            .uri("/?access_token=header")
+            .insert_header(("Authorization", "Bearer header"))
+            .to_srv_request();
+        assert!(extract_token(&req, &sources(), true, &[AuthScheme::Bearer]).is_ok());
+    }
+
+    #[test]
+    fn unaccepted_scheme_is_rejected() {
+        let req = TestRequest::default()
+            .insert_header(("Authorization", "DPoP token"))
+            .to_srv_request();
+        let result = extract_token(&req, &sources(), false, &[AuthScheme::Bearer]);
+        assert!(matches!(
+            result,
+            Err(JWTMiddlewareError::UnsupportedScheme(AuthScheme::DPoP))
+        ));
+    }
+
+    #[test]
+    fn authorization_without_scheme_is_invalid() {
+        let req = TestRequest::default()
+            .insert_header(("Authorization", "header.payload.signature"))
+            .to_srv_request();
+        let result = extract_token(&req, &sources(), false, &[AuthScheme::Bearer]);
+        assert!(matches!(
+            result,
+            Err(JWTMiddlewareError::InvalidAuthorizationHeader)
+        ));
+    }
+
+    #[cfg(not(feature = "dpop"))]
+    #[test]
+    fn dpop_scheme_requires_the_dpop_feature() {
+        let req = TestRequest::default()
+            .insert_header(("Authorization", "DPoP token"))
+            .to_srv_request();
+        let schemes = [AuthScheme::Bearer, AuthScheme::DPoP];
+        let result = extract_token(&req, &sources(), false, &schemes);
+        assert!(matches!(
+            result,
+            Err(JWTMiddlewareError::UnsupportedScheme(AuthScheme::DPoP))
+        ));
+    }
+
+    #[test]
+    fn credential_errors_are_unauthorized() {
+        let req = TestRequest::default().to_srv_request();
+        let error = extract_token(&req, &sources(), false, &[AuthScheme::Bearer])
+            .expect_err("expected error");
+        assert!(matches!(error, JWTMiddlewareError::NoAuthorizationHeader));
+        assert_eq!(error.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            JWTMiddlewareError::NoJWKSet.status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
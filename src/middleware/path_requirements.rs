@@ -0,0 +1,92 @@
+use crate::{compare::secure_scope_contains, middleware::ScopeDiff, AccessToken, Scope};
+
+// Requirements applied by AuthorizationFactory to requests under a path prefix,
+// replacing its issuer and audience checks and adding required scopes. The JWK
+// set still comes from IssuerFactory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathRequirements {
+    prefix: String,
+    issuer: Option<String>,
+    audience: Option<String>,
+    scopes: Vec<Scope>,
+}
+
+impl PathRequirements {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches('/').to_string(),
+            issuer: None,
+            audience: None,
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn issuer(mut self, value: &str) -> Self {
+        self.issuer = Some(value.to_string());
+        self
+    }
+
+    pub fn audience(mut self, value: &str) -> Self {
+        self.audience = Some(value.to_string());
+        self
+    }
+
+    pub fn scope(mut self, value: Scope) -> Self {
+        self.scopes.push(value);
+        self
+    }
+
+    pub(crate) fn expected_issuer(&self) -> Option<&str> {
+        self.issuer.as_deref()
+    }
+
+    pub(crate) fn expected_audience(&self) -> Option<&str> {
+        self.audience.as_deref()
+    }
+
+    // Prefixes match whole path segments, so "/admin" does not cover
+    // "/administrators".
+    fn matches(&self, path: &str) -> bool {
+        match path.strip_prefix(&self.prefix) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/'),
+            None => false,
+        }
+    }
+
+    pub(crate) fn missing_scopes(&self, token: &AccessToken) -> Option<ScopeDiff> {
+        let present = token.scopes();
+        let satisfied = self
+            .scopes
+            .iter()
+            .all(|scope| secure_scope_contains(present, scope));
+        (!satisfied).then(|| ScopeDiff::new(self.scopes.clone(), present.clone()))
+    }
+}
+
+pub(crate) fn select<'a>(
+    requirements: &'a [PathRequirements],
+    path: &str,
+) -> Option<&'a PathRequirements> {
+    requirements
+        .iter()
+        .filter(|requirements| requirements.matches(path))
+        .max_by_key(|requirements| requirements.prefix.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn longest_matching_prefix_is_selected() {
+        let requirements = vec![
+            PathRequirements::new("/api").audience("api"),
+            PathRequirements::new("/api/admin/").audience("admin"),
+        ];
+        let selected = select(&requirements, "/api/admin/users").unwrap();
+        assert_eq!(selected.expected_audience(), Some("admin"));
+        let selected = select(&requirements, "/api/administrators").unwrap();
+        assert_eq!(selected.expected_audience(), Some("api"));
+        assert!(select(&requirements, "/health").is_none());
+    }
+}
@@ -0,0 +1,36 @@
+#[cfg(feature = "casbin")]
+mod casbin_engine;
+#[cfg(feature = "cedar")]
+mod cedar_engine;
+
+#[cfg(feature = "casbin")]
+pub use casbin_engine::CasbinPolicyEngine;
+#[cfg(feature = "cedar")]
+pub use cedar_engine::CedarPolicyEngine;
+
+use crate::{AuthorizationClaims, Claims, Scope};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyRequest<'a> {
+    pub subject: Option<&'a str>,
+    pub scopes: &'a [Scope],
+    pub method: &'a str,
+    pub path: &'a str,
+    pub claims: &'a Claims<AuthorizationClaims>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyDecision {
+    Allow,
+    Deny(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PolicyError {
+    #[error("policy evaluation failed: {0}")]
+    EvaluationError(String),
+}
+
+pub trait PolicyEngine {
+    fn decide(&self, request: &PolicyRequest<'_>) -> Result<PolicyDecision, PolicyError>;
+}
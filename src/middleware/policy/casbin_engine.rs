@@ -0,0 +1,172 @@
+use casbin::{CoreApi, Enforcer};
+
+use crate::middleware::policy::{PolicyDecision, PolicyEngine, PolicyError, PolicyRequest};
+
+// Enforces `r = sub, obj, act` with the subject, path and method.
+pub struct CasbinPolicyEngine {
+    enforcer: Enforcer,
+    scopes: bool,
+}
+
+impl CasbinPolicyEngine {
+    pub fn new(enforcer: Enforcer) -> Self {
+        Self {
+            enforcer,
+            scopes: false,
+        }
+    }
+
+    // For models whose request definition takes the token scopes as a fourth
+    // value, e.g. `r = sub, obj, act, scopes` matched with `p.scope in r.scopes`.
+    pub fn with_scopes(enforcer: Enforcer) -> Self {
+        Self {
+            enforcer,
+            scopes: true,
+        }
+    }
+}
+
+impl PolicyEngine for CasbinPolicyEngine {
+    fn decide(&self, request: &PolicyRequest<'_>) -> Result<PolicyDecision, PolicyError> {
+        let subject = request.subject.unwrap_or_default();
+        let allowed = match self.scopes {
+            true => {
+                let scopes = request.scopes.iter().map(ToString::to_string);
+                let scopes = scopes.collect::<Vec<_>>();
+                self.enforcer
+                    .enforce((subject, request.path, request.method, scopes))
+            }
+            false => self
+                .enforcer
+                .enforce((subject, request.path, request.method)),
+        }
+        .map_err(|e| PolicyError::EvaluationError(e.to_string()))?;
+        let decision = match allowed {
+            true => PolicyDecision::Allow,
+            false => PolicyDecision::Deny(format!(
+                "casbin denied {} {} for subject \"{}\"",
+                request.method, request.path, subject
+            )),
+        };
+        Ok(decision)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use casbin::{DefaultModel, StringAdapter};
+
+    use super::*;
+    use crate::{AuthorizationClaims, Claims, Scope};
+
+    const MODEL: &str = r#"
+        [request_definition]
+        r = sub, obj, act
+
+        [policy_definition]
+        p = sub, obj, act
+
+        [policy_effect]
+        e = some(where (p.eft == allow))
+
+        [matchers]
+        m = r.sub == p.sub && r.obj == p.obj && r.act == p.act
+    "#;
+
+    const SCOPED_MODEL: &str = r#"
+        [request_definition]
+        r = sub, obj, act, scopes
+
+        [policy_definition]
+        p = sub, obj, act, scope
+
+        [policy_effect]
+        e = some(where (p.eft == allow))
+
+        [matchers]
+        m = (r.sub == p.sub || p.sub == "*") && r.obj == p.obj && r.act == p.act && (p.scope == "*" || p.scope in r.scopes)
+    "#;
+
+    const POLICIES: &str = r#"
+        p, *, /projects, GET, read:projects
+        p, admin, /admin, GET, *
+    "#;
+
+    async fn enforcer(model: &str, policies: &str) -> Enforcer {
+        let model = DefaultModel::from_str(model).await.expect("expected model");
+        Enforcer::new(model, StringAdapter::new(policies))
+            .await
+            .expect("expected enforcer")
+    }
+
+    fn claims(scopes: Vec<Scope>) -> Claims<AuthorizationClaims> {
+        let extension = AuthorizationClaims { scopes };
+        Claims::new("issuer", "subject", &[], Duration::from_secs(60), extension)
+    }
+
+    async fn decide(
+        claims: &Claims<AuthorizationClaims>,
+        subject: &str,
+        path: &str,
+    ) -> PolicyDecision {
+        let request = PolicyRequest {
+            subject: Some(subject),
+            scopes: claims.scopes(),
+            method: "GET",
+            path,
+            claims,
+        };
+        let engine = CasbinPolicyEngine::with_scopes(enforcer(SCOPED_MODEL, POLICIES).await);
+        engine.decide(&request).expect("expected decision")
+    }
+
+    #[actix_web::test]
+    async fn casbin_enforces_three_value_models_by_default() {
+        let engine = CasbinPolicyEngine::new(enforcer(MODEL, "p, admin, /admin, GET").await);
+        let claims = claims(vec![Scope::new("read", "projects")]);
+        let request = |subject| PolicyRequest {
+            subject: Some(subject),
+            scopes: claims.scopes(),
+            method: "GET",
+            path: "/admin",
+            claims: &claims,
+        };
+        let decision = engine.decide(&request("admin")).expect("expected decision");
+        assert_eq!(decision, PolicyDecision::Allow);
+        let decision = engine
+            .decide(&request("subject"))
+            .expect("expected decision");
+        assert!(matches!(decision, PolicyDecision::Deny(_)));
+    }
+
+    #[actix_web::test]
+    async fn casbin_permits_matching_scope() {
+        let claims = claims(vec![Scope::new("read", "projects")]);
+        assert_eq!(
+            decide(&claims, "subject", "/projects").await,
+            PolicyDecision::Allow
+        );
+    }
+
+    #[actix_web::test]
+    async fn casbin_denies_without_scope() {
+        let claims = claims(vec![Scope::new("write", "projects")]);
+        assert_eq!(
+            decide(&claims, "subject", "/projects").await,
+            PolicyDecision::Deny("casbin denied GET /projects for subject \"subject\"".to_string())
+        );
+    }
+
+    #[actix_web::test]
+    async fn casbin_matches_subject() {
+        let claims = claims(vec![]);
+        assert_eq!(
+            decide(&claims, "admin", "/admin").await,
+            PolicyDecision::Allow
+        );
+        let decision = decide(&claims, "subject", "/admin").await;
+        assert!(matches!(decision, PolicyDecision::Deny(_)));
+    }
+}
@@ -0,0 +1,176 @@
+use std::str::FromStr;
+
+use cedar_policy::{
+    Authorizer, Context, Decision, Entities, EntityId, EntityTypeName, EntityUid, PolicySet,
+    Request,
+};
+use serde_json::{json, Value};
+
+use crate::middleware::policy::{PolicyDecision, PolicyEngine, PolicyError, PolicyRequest};
+
+pub struct CedarPolicyEngine {
+    authorizer: Authorizer,
+    policies: PolicySet,
+    entities: Entities,
+}
+
+impl CedarPolicyEngine {
+    pub fn new(policies: PolicySet) -> Self {
+        Self {
+            authorizer: Authorizer::new(),
+            policies,
+            entities: Entities::empty(),
+        }
+    }
+
+    pub fn entities(mut self, value: Entities) -> Self {
+        self.entities = value;
+        self
+    }
+}
+
+fn entity_uid(type_name: &str, id: &str) -> Result<EntityUid, PolicyError> {
+    let type_name = EntityTypeName::from_str(type_name)
+        .map_err(|e| PolicyError::EvaluationError(e.to_string()))?;
+    let id = EntityId::from_str(id).map_err(|e| PolicyError::EvaluationError(e.to_string()))?;
+    Ok(EntityUid::from_type_name_and_id(type_name, id))
+}
+
+// Cedar has no null or floating point values, so those claims are left out of
+// the context.
+fn cedar_value(value: &Value) -> Option<Value> {
+    match value {
+        Value::Null => None,
+        Value::Number(number) => number.as_i64().map(Value::from),
+        Value::Array(values) => Some(Value::Array(
+            values.iter().filter_map(cedar_value).collect(),
+        )),
+        Value::Object(map) => Some(Value::Object(
+            map.iter()
+                .filter_map(|(key, value)| cedar_value(value).map(|value| (key.clone(), value)))
+                .collect(),
+        )),
+        value => Some(value.clone()),
+    }
+}
+
+fn context(request: &PolicyRequest<'_>) -> Result<Context, PolicyError> {
+    let claims = request.claims;
+    let scopes = request
+        .scopes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    let aud = claims
+        .aud
+        .iter()
+        .flat_map(|aud| aud.iter().cloned())
+        .collect::<Vec<_>>();
+    let additional = cedar_value(&Value::Object(claims.additional.clone()));
+    let context = json!({
+        "iss": claims.iss,
+        "aud": aud,
+        "scopes": scopes,
+        "claims": additional,
+    });
+    Context::from_json_value(context, None).map_err(|e| PolicyError::EvaluationError(e.to_string()))
+}
+
+impl PolicyEngine for CedarPolicyEngine {
+    fn decide(&self, request: &PolicyRequest<'_>) -> Result<PolicyDecision, PolicyError> {
+        let principal = entity_uid("User", request.subject.unwrap_or_default())?;
+        let action = entity_uid("Action", request.method)?;
+        let resource = entity_uid("Route", request.path)?;
+        let context = context(request)?;
+        let cedar_request = Request::new(principal, action, resource, context, None)
+            .map_err(|e| PolicyError::EvaluationError(e.to_string()))?;
+        let response =
+            self.authorizer
+                .is_authorized(&cedar_request, &self.policies, &self.entities);
+        if response.decision() == Decision::Allow {
+            return Ok(PolicyDecision::Allow);
+        }
+        let diagnostics = response.diagnostics();
+        let errors = diagnostics
+            .errors()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return Err(PolicyError::EvaluationError(errors.join("; ")));
+        }
+        let policies = diagnostics
+            .reason()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let reason = match policies.is_empty() {
+            true => "no Cedar policy permits the request".to_string(),
+            false => format!("forbidden by Cedar policies {}", policies.join(", ")),
+        };
+        Ok(PolicyDecision::Deny(reason))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{AuthorizationClaims, Claims, Scope};
+
+    const POLICIES: &str = r#"
+        permit(principal, action == Action::"GET", resource)
+        when { context.scopes.contains("read:projects") };
+
+        forbid(principal, action, resource == Route::"/admin")
+        when { context.claims.tier != "enterprise" };
+    "#;
+
+    fn claims(scopes: Vec<Scope>) -> Claims<AuthorizationClaims> {
+        let aud = vec!["audience".to_string()];
+        let extension = AuthorizationClaims { scopes };
+        let mut claims = Claims::new(
+            "issuer",
+            "subject",
+            &aud,
+            Duration::from_secs(60),
+            extension,
+        );
+        claims.additional.insert("tier".to_string(), json!("free"));
+        claims
+    }
+
+    fn decide(claims: &Claims<AuthorizationClaims>, method: &str, path: &str) -> PolicyDecision {
+        let engine = CedarPolicyEngine::new(POLICIES.parse().expect("expected policies"));
+        let request = PolicyRequest {
+            subject: Some("subject"),
+            scopes: claims.scopes(),
+            method,
+            path,
+            claims,
+        };
+        engine.decide(&request).expect("expected decision")
+    }
+
+    #[test]
+    fn cedar_permits_matching_scope() {
+        let claims = claims(vec![Scope::new("read", "projects")]);
+        assert_eq!(decide(&claims, "GET", "/projects"), PolicyDecision::Allow);
+    }
+
+    #[test]
+    fn cedar_denies_without_permit() {
+        let claims = claims(vec![]);
+        let decision = decide(&claims, "GET", "/projects");
+        assert!(matches!(decision, PolicyDecision::Deny(_)));
+    }
+
+    #[test]
+    fn cedar_reports_forbidding_policy() {
+        let claims = claims(vec![Scope::new("read", "projects")]);
+        let decision = decide(&claims, "GET", "/admin");
+        assert_eq!(
+            decision,
+            PolicyDecision::Deny("forbidden by Cedar policies policy1".to_string())
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use std::{
+    collections::HashMap,
+    future::{ready, Ready},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    body::BoxBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER},
+        StatusCode,
+    },
+    Error, HttpMessage, HttpResponse, ResponseError,
+};
+use futures::future::LocalBoxFuture;
+use serde_json::Value;
+
+use crate::{
+    middleware::error_response::{
+        error_context, internal_server_error_body, too_many_requests_error_body, CorrelatedError,
+        ErrorBody, MiddlewareError,
+    },
+    AccessToken,
+};
+
+const PRUNE_THRESHOLD: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitHit {
+    pub count: u64,
+    pub reset: Duration,
+}
+
+pub trait RateLimitStore {
+    fn hit(
+        &self,
+        key: &str,
+        window: Duration,
+    ) -> LocalBoxFuture<'static, Result<RateLimitHit, RateLimitError>>;
+}
+
+// Clones share their counters, so a single store can be handed to every worker.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryRateLimitStore {
+    entries: Arc<Mutex<HashMap<String, (Instant, u64)>>>,
+}
+
+impl InMemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn hit_at(&self, key: &str, window: Duration, now: Instant) -> RateLimitHit {
+        let mut entries = self.entries.lock().expect("expected rate limit store lock");
+        if entries.len() >= PRUNE_THRESHOLD {
+            entries.retain(|_, (start, _)| now.duration_since(*start) < window);
+        }
+        let entry = entries.entry(key.to_string()).or_insert((now, 0));
+        if now.duration_since(entry.0) >= window {
+            *entry = (now, 0);
+        }
+        entry.1 += 1;
+        RateLimitHit {
+            count: entry.1,
+            reset: window.saturating_sub(now.duration_since(entry.0)),
+        }
+    }
+}
+
+impl RateLimitStore for InMemoryRateLimitStore {
+    fn hit(
+        &self,
+        key: &str,
+        window: Duration,
+    ) -> LocalBoxFuture<'static, Result<RateLimitHit, RateLimitError>> {
+        let hit = self.hit_at(key, window, Instant::now());
+        Box::pin(ready(Ok(hit)))
+    }
+}
+
+// Limits requests per key claim across all workers: create the store outside the
+// HttpServer factory closure and hand clones of it to every worker's factory.
+pub struct RateLimitFactory {
+    limit: u64,
+    window: Duration,
+    key_claim: String,
+    store: Rc<dyn RateLimitStore>,
+}
+
+impl RateLimitFactory {
+    pub fn new(limit: u64, window: Duration, store: impl RateLimitStore + 'static) -> Self {
+        Self {
+            limit,
+            window,
+            key_claim: "sub".to_string(),
+            store: Rc::new(store),
+        }
+    }
+
+    pub fn key_claim(mut self, value: &str) -> Self {
+        self.key_claim = value.to_string();
+        self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimitFactory
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let middleware = RateLimitMiddleware {
+            service: Rc::new(service),
+            limit: self.limit,
+            window: self.window,
+            key_claim: Rc::new(self.key_claim.clone()),
+            store: self.store.clone(),
+        };
+        ready(Ok(middleware))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    limit: u64,
+    window: Duration,
+    key_claim: Rc<String>,
+    store: Rc<dyn RateLimitStore>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RateLimitError {
+    #[error("rate limit of {limit} requests exceeded")]
+    Exceeded { limit: u64, reset: Duration },
+    #[error("rate limit store failed: {0}")]
+    StoreError(String),
+}
+
+fn insert_rate_limit_headers(headers: &mut HeaderMap, limit: u64, remaining: u64, reset: Duration) {
+    let values = [
+        ("ratelimit-limit", limit),
+        ("ratelimit-remaining", remaining),
+        ("ratelimit-reset", reset.as_secs()),
+    ];
+    for (name, value) in values {
+        headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+    }
+}
+
+impl ResponseError for RateLimitError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            RateLimitError::Exceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+            RateLimitError::StoreError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        self.respond(None)
+    }
+}
+
+impl MiddlewareError for RateLimitError {
+    fn error_body(&self) -> ErrorBody {
+        match self {
+            RateLimitError::Exceeded { .. } => too_many_requests_error_body("RATE_LIMITED", self),
+            RateLimitError::StoreError(_) => internal_server_error_body("RATE_LIMIT_STORE", self),
+        }
+    }
+
+    fn decorate(&self, response: &mut HttpResponse) {
+        if let RateLimitError::Exceeded { limit, reset } = self {
+            let headers = response.headers_mut();
+            insert_rate_limit_headers(headers, *limit, 0, *reset);
+            headers.insert(RETRY_AFTER, HeaderValue::from(reset.as_secs()));
+        }
+    }
+}
+
+// Any claim can be the key, including registered ones and those the extension
+// consumes. Values other than strings are keyed by their JSON text.
+fn rate_limit_key(token: &AccessToken, key_claim: &str) -> Option<String> {
+    match token
+        .claims()
+        .get_claim::<Value>(key_claim)
+        .ok()
+        .flatten()?
+    {
+        Value::Null => None,
+        Value::String(key) => Some(key),
+        key => Some(key.to_string()),
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let limit = self.limit;
+        let window = self.window;
+        let key_claim = self.key_claim.clone();
+        let store = self.store.clone();
+        Box::pin(async move {
+            let key = req
+                .extensions()
+                .get::<AccessToken>()
+                .and_then(|token| rate_limit_key(token, &key_claim));
+            let Some(key) = key else {
+                let res = service.call(req).await?;
+                return Ok(res);
+            };
+
+            let context = error_context(&req);
+            let hit = store
+                .hit(&key, window)
+                .await
+                .map_err(|e| {
+                    log::info!("{}", e);
+                    e
+                })
+                .map_err(|e| CorrelatedError::new(e, &context))?;
+            if hit.count > limit {
+                let e = RateLimitError::Exceeded {
+                    limit,
+                    reset: hit.reset,
+                };
+                log::info!("{}", e);
+                return Err(CorrelatedError::new(e, &context).into());
+            }
+            let mut res = service.call(req).await?;
+            insert_rate_limit_headers(res.headers_mut(), limit, limit - hit.count, hit.reset);
+            Ok(res)
+        })
+    }
+
+    forward_ready!(service);
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{
+        test::{init_service, try_call_service, TestRequest},
+        web, App,
+    };
+
+    use jsonwebtoken::Header;
+
+    use super::*;
+    use crate::{token::Token, AuthorizationClaims, Claims, Scope};
+
+    fn token() -> AccessToken {
+        let aud = vec!["audience".to_string()];
+        let extension = AuthorizationClaims {
+            scopes: vec![Scope::new("read", "projects")],
+        };
+        let claims = Claims::new(
+            "issuer",
+            "subject",
+            &aud,
+            Duration::from_secs(60),
+            extension,
+        );
+        Token::new(Header::default(), claims)
+    }
+
+    #[test]
+    fn store_counts_hits_within_window() {
+        let store = InMemoryRateLimitStore::new();
+        let window = Duration::from_secs(60);
+        let now = Instant::now();
+        store.hit_at("subject", window, now);
+        let hit = store.hit_at("subject", window, now + Duration::from_secs(10));
+        assert_eq!(hit.count, 2);
+        assert_eq!(hit.reset, Duration::from_secs(50));
+        let other = store.hit_at("other", window, now);
+        assert_eq!(other.count, 1);
+    }
+
+    #[test]
+    fn store_resets_after_window() {
+        let store = InMemoryRateLimitStore::new();
+        let window = Duration::from_secs(60);
+        let now = Instant::now();
+        store.hit_at("subject", window, now);
+        let hit = store.hit_at("subject", window, now + Duration::from_secs(61));
+        assert_eq!(hit.count, 1);
+    }
+
+    #[test]
+    fn exceeded_response_has_rate_limit_headers() {
+        let e = RateLimitError::Exceeded {
+            limit: 10,
+            reset: Duration::from_secs(30),
+        };
+        let response = e.error_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let headers = response.headers();
+        assert_eq!(
+            headers.get(RETRY_AFTER).map(|v| v.as_bytes()),
+            Some(&b"30"[..])
+        );
+        assert_eq!(
+            headers.get("ratelimit-limit").map(|v| v.as_bytes()),
+            Some(&b"10"[..])
+        );
+        assert_eq!(
+            headers.get("ratelimit-remaining").map(|v| v.as_bytes()),
+            Some(&b"0"[..])
+        );
+    }
+
+    #[test]
+    fn key_is_read_from_any_claim() {
+        let token = token();
+        assert_eq!(rate_limit_key(&token, "sub").as_deref(), Some("subject"));
+        assert_eq!(rate_limit_key(&token, "iss").as_deref(), Some("issuer"));
+        assert_eq!(
+            rate_limit_key(&token, "aud").as_deref(),
+            Some(r#"["audience"]"#)
+        );
+        assert_eq!(
+            rate_limit_key(&token, "scope").as_deref(),
+            Some("read:projects")
+        );
+        assert_eq!(rate_limit_key(&token, "tenant"), None);
+    }
+
+    #[actix_web::test]
+    async fn workers_share_the_store() {
+        let store = InMemoryRateLimitStore::new();
+        let app = || {
+            let store = store.clone();
+            init_service(
+                App::new()
+                    .wrap(RateLimitFactory::new(1, Duration::from_secs(60), store))
+                    .wrap_fn(|req, srv| {
+                        req.extensions_mut().insert(token());
+                        srv.call(req)
+                    })
+                    .default_service(web::to(HttpResponse::Ok)),
+            )
+        };
+        let (first, second) = (app().await, app().await);
+        let req = TestRequest::default().to_request();
+        assert!(try_call_service(&first, req).await.is_ok());
+        let req = TestRequest::default().to_request();
+        assert!(try_call_service(&second, req).await.is_err());
+    }
+}
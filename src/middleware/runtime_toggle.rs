@@ -0,0 +1,50 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+// Shared switch for JWTFactory and AuthorizationFactory enforcement. Clones
+// share the flag across every worker, so flipping it (e.g. for a break-glass
+// mode) takes effect on the next request without rebuilding the App.
+#[derive(Debug, Clone)]
+pub struct RuntimeToggle {
+    enabled: Arc<AtomicBool>,
+}
+
+impl RuntimeToggle {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(enabled)),
+        }
+    }
+
+    pub fn set(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for RuntimeToggle {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clones_share_state() {
+        let toggle = RuntimeToggle::default();
+        let shared = toggle.clone();
+        assert!(shared.is_enabled());
+        toggle.set(false);
+        assert!(!shared.is_enabled());
+        shared.set(true);
+        assert!(toggle.is_enabled());
+    }
+}
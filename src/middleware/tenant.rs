@@ -0,0 +1,85 @@
+use std::{
+    convert::Infallible,
+    future::{ready, Ready},
+};
+
+use actix_web::{FromRequest, HttpMessage};
+use serde_json::Value;
+
+use crate::Claims;
+
+#[derive(Debug, Clone)]
+pub(crate) struct TenantClaim {
+    pub name: String,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tenant(Option<String>);
+
+impl Tenant {
+    pub(crate) fn from_claims<Extension>(claims: &Claims<Extension>, name: &str) -> Self {
+        let tenant = match claims.additional.get(name) {
+            Some(Value::String(tenant)) => Some(tenant.clone()),
+            Some(Value::Number(tenant)) => Some(tenant.to_string()),
+            _ => None,
+        };
+        Self(tenant)
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+}
+
+impl FromRequest for Tenant {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        _payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        let tenant = req.extensions().get::<Tenant>().cloned();
+        let result = Ok(tenant.unwrap_or(Tenant(None)));
+        ready(result)
+    }
+}
+
+impl std::ops::Deref for Tenant {
+    type Target = Option<String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn claims(string: &str) -> Claims<serde_json::Value> {
+        serde_json::from_str(string).expect("Expected deserialize")
+    }
+
+    #[test]
+    fn tenant_is_read_from_configured_claim() {
+        let claims = claims(r#"{"iss":"issuer","exp":1000,"org_id":"org_123"}"#);
+        let tenant = Tenant::from_claims(&claims, "org_id");
+        assert_eq!(tenant.id(), Some("org_123"));
+    }
+
+    #[test]
+    fn tenant_is_read_from_namespaced_claim() {
+        let claims = claims(r#"{"iss":"issuer","exp":1000,"https://example.com/tenant":42}"#);
+        let tenant = Tenant::from_claims(&claims, "https://example.com/tenant");
+        assert_eq!(tenant.id(), Some("42"));
+    }
+
+    #[test]
+    fn tenant_is_missing_when_claim_is_absent() {
+        let claims = claims(r#"{"iss":"issuer","exp":1000}"#);
+        let tenant = Tenant::from_claims(&claims, "tid");
+        assert_eq!(tenant.id(), None);
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::Display;
+
+use actix_web::{
+    cookie::{time, Cookie, CookieJar, Key, SameSite},
+    HttpRequest,
+};
+
+// Keeps tokens for backend-for-frontend apps in an encrypted and authenticated
+// cookie that JavaScript cannot read. Register it with App::app_data and add
+// TokenSource::EncryptedCookie to JWTFactory to authenticate with it.
+#[derive(Clone)]
+pub struct TokenCookie {
+    name: String,
+    key: Key,
+    path: String,
+    max_age: Option<time::Duration>,
+    same_site: SameSite,
+    secure: bool,
+}
+
+impl TokenCookie {
+    pub fn new(name: &str, key: Key) -> Self {
+        Self {
+            name: name.to_string(),
+            key,
+            path: "/".to_string(),
+            max_age: None,
+            same_site: SameSite::Strict,
+            secure: true,
+        }
+    }
+
+    pub fn path(mut self, value: &str) -> Self {
+        self.path = value.to_string();
+        self
+    }
+
+    // Without a max age the cookie lasts for the browser session. Use the
+    // token's lifetime so the cookie does not outlive it.
+    pub fn max_age(mut self, value: std::time::Duration) -> Self {
+        self.max_age = time::Duration::try_from(value).ok();
+        self
+    }
+
+    pub fn same_site(mut self, value: SameSite) -> Self {
+        self.same_site = value;
+        self
+    }
+
+    // Only for local development over plain HTTP.
+    pub fn secure(mut self, value: bool) -> Self {
+        self.secure = value;
+        self
+    }
+
+    fn build(&self, value: String) -> Cookie<'static> {
+        let mut cookie = Cookie::build(self.name.clone(), value)
+            .path(self.path.clone())
+            .http_only(true)
+            .secure(self.secure)
+            .same_site(self.same_site)
+            .finish();
+        if let Some(max_age) = self.max_age {
+            cookie.set_max_age(max_age);
+        }
+        cookie
+    }
+
+    // The cookie to set on the response, e.g. with HttpResponseBuilder::cookie.
+    pub fn cookie(&self, token: impl Display) -> Cookie<'static> {
+        let mut jar = CookieJar::new();
+        jar.private_mut(&self.key)
+            .add(self.build(token.to_string()));
+        jar.get(&self.name)
+            .cloned()
+            .expect("expected the cookie just added")
+    }
+
+    // Clears the cookie on logout.
+    pub fn removal(&self) -> Cookie<'static> {
+        let mut cookie = self.build(String::new());
+        cookie.make_removal();
+        cookie
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn decrypt(&self, cookie: Cookie<'static>) -> Option<String> {
+        let mut jar = CookieJar::new();
+        jar.add_original(cookie);
+        let cookie = jar.private(&self.key).get(&self.name)?;
+        Some(cookie.value().to_string())
+    }
+
+    // None when the request has no cookie or it was not encrypted with this
+    // cookie's key, e.g. after tampering or key rotation.
+    pub fn token(&self, req: &HttpRequest) -> Option<String> {
+        self.decrypt(req.cookie(&self.name)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn token_cookie() -> TokenCookie {
+        TokenCookie::new("access_token", Key::generate())
+    }
+
+    #[test]
+    fn cookie_is_encrypted_and_http_only() {
+        let token_cookie = token_cookie();
+        let cookie = token_cookie.cookie("header.payload.signature");
+        assert_ne!(cookie.value(), "header.payload.signature");
+        assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(cookie.secure(), Some(true));
+        let req = TestRequest::default().cookie(cookie).to_http_request();
+        assert_eq!(
+            token_cookie.token(&req).as_deref(),
+            Some("header.payload.signature")
+        );
+    }
+
+    #[test]
+    fn cookies_from_other_keys_are_rejected() {
+        let cookie = token_cookie().cookie("header.payload.signature");
+        let req = TestRequest::default().cookie(cookie).to_http_request();
+        assert!(token_cookie().token(&req).is_none());
+        let plain = Cookie::new("access_token", "header.payload.signature");
+        let req = TestRequest::default().cookie(plain).to_http_request();
+        assert!(token_cookie().token(&req).is_none());
+    }
+}
@@ -0,0 +1,22 @@
+// Stored in the request extensions by JWTFactory and AuthorizationFactory, also
+// when they reject the request, so logging or metrics middleware wrapped around
+// them can tell why. Such middleware keeps a clone of the HttpRequest to read it
+// after the inner service has returned an error.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationOutcome {
+    // A token was found and its signature and expiry verified.
+    pub decoded: bool,
+    // AuthorizationFactory accepted the claims; false when it did not run.
+    pub validated: bool,
+    pub reasons: Vec<String>,
+}
+
+impl ValidationOutcome {
+    pub(crate) fn new(decoded: bool, validated: bool, reasons: Vec<String>) -> Self {
+        Self {
+            decoded,
+            validated,
+            reasons,
+        }
+    }
+}
@@ -1,18 +1,240 @@
-use crate::middleware::{authorization::Authorization, authorization_error::AuthorizationError};
+use std::fmt::Display;
 
-pub fn verify(
+use crate::{
+    compare::secure_eq,
+    middleware::{
+        authorization::Authorization,
+        authorization_error::{AuthorizationError, ScopeDiff},
+    },
+    AccessToken, Scope,
+};
+
+pub trait VerifyWith<C: ?Sized> {
+    fn verify_with(&self, token: &AccessToken, context: &C) -> Result<(), AuthorizationError>;
+}
+
+impl<C, F> VerifyWith<C> for F
+where
+    C: ?Sized,
+    F: Fn(&AccessToken, &C) -> bool,
+{
+    fn verify_with(&self, token: &AccessToken, context: &C) -> Result<(), AuthorizationError> {
+        self(token, context)
+            .then_some(())
+            .ok_or(AuthorizationError::UnauthorizedContext)
+    }
+}
+
+pub struct IsSubject;
+
+impl VerifyWith<str> for IsSubject {
+    fn verify_with(&self, token: &AccessToken, owner: &str) -> Result<(), AuthorizationError> {
+        let sub = token.claims().sub.as_ref();
+        sub.filter(|sub| secure_eq(sub.as_str(), owner))
+            .map(|_| ())
+            .ok_or(AuthorizationError::UnauthorizedContext)
+    }
+}
+
+fn required_scopes<A, R>(requirements: &[(R, A)]) -> Vec<Scope>
+where
+    A: Display,
+    R: Display,
+{
+    requirements
+        .iter()
+        .map(|(resource, action)| Scope::new(&action.to_string(), &resource.to_string()))
+        .collect()
+}
+
+pub fn verify<A, R>(
     auth: &Authorization,
-    resource: &str,
-    required_action: &str,
-) -> Result<(), AuthorizationError> {
+    resource: R,
+    required_action: A,
+) -> Result<(), AuthorizationError>
+where
+    A: Display,
+    R: Display,
+{
     let token = auth.as_ref().ok_or(AuthorizationError::Unauthorized)?;
-    let actions = token
-        .actions(resource)
-        .ok_or(AuthorizationError::UnauthorizedResource(
-            resource.to_string(),
-        ))?;
-    actions.iter().find(|v| *v == required_action).ok_or(
-        AuthorizationError::UnauthorizedAction(required_action.to_string()),
-    )?;
+    let resource = resource.to_string();
+    let required_action = required_action.to_string();
+    let diff = || {
+        let required = vec![Scope::new(&required_action, &resource)];
+        ScopeDiff::new(required, token.scopes().clone())
+    };
+    match auth.grants(&resource, &required_action) {
+        Some(true) => Ok(()),
+        Some(false) => Err(AuthorizationError::UnauthorizedAction(diff())),
+        None => Err(AuthorizationError::UnauthorizedResource(diff())),
+    }
+}
+
+pub fn verify_with<A, R, C, V>(
+    auth: &Authorization,
+    resource: R,
+    required_action: A,
+    context: &C,
+    condition: &V,
+) -> Result<(), AuthorizationError>
+where
+    A: Display,
+    R: Display,
+    C: ?Sized,
+    V: VerifyWith<C>,
+{
+    verify(auth, resource, required_action)?;
+    let token = auth.as_ref().ok_or(AuthorizationError::Unauthorized)?;
+    condition.verify_with(token, context)
+}
+
+pub fn verify_any<A, R>(
+    auth: &Authorization,
+    requirements: &[(R, A)],
+) -> Result<(), AuthorizationError>
+where
+    A: Display,
+    R: Display,
+{
+    let mut error = AuthorizationError::Unauthorized;
+    for (resource, required_action) in requirements {
+        match verify(auth, resource, required_action) {
+            Ok(()) => return Ok(()),
+            Err(e) => error = e,
+        }
+    }
+    Err(error.requiring(required_scopes(requirements)))
+}
+
+pub fn verify_all<A, R>(
+    auth: &Authorization,
+    requirements: &[(R, A)],
+) -> Result<(), AuthorizationError>
+where
+    A: Display,
+    R: Display,
+{
+    auth.as_ref().ok_or(AuthorizationError::Unauthorized)?;
+    for (resource, required_action) in requirements {
+        verify(auth, resource, required_action)
+            .map_err(|e| e.requiring(required_scopes(requirements)))?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use std::{fmt, time::Duration};
+
+    use jsonwebtoken::Header;
+
+    use super::*;
+    use crate::{token::Token, AccessToken, AuthorizationClaims, Claims};
+
+    fn authorization(scopes: Vec<Scope>) -> Authorization {
+        let aud = vec!["audience".to_string()];
+        let extension = AuthorizationClaims { scopes };
+        let claims = Claims::new(
+            "issuer",
+            "subject",
+            &aud,
+            Duration::from_secs(60),
+            extension,
+        );
+        Authorization::new(Some(Token::new(Header::default(), claims)))
+    }
+
+    #[test]
+    fn verify_any_succeeds_when_one_requirement_is_met() {
+        let auth = authorization(vec![Scope::new("admin", "users")]);
+        let result = verify_any(&auth, &[("users", "read"), ("users", "admin")]);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn verify_any_fails_when_no_requirement_is_met() {
+        let auth = authorization(vec![Scope::new("read", "projects")]);
+        let error = verify_any(&auth, &[("users", "read"), ("users", "admin")])
+            .expect_err("expected to fail");
+        assert!(matches!(error, AuthorizationError::UnauthorizedResource(_)));
+    }
+
+    #[test]
+    fn verify_all_fails_when_one_requirement_is_missing() {
+        let auth = authorization(vec![Scope::new("read", "users")]);
+        let error = verify_all(&auth, &[("users", "read"), ("users", "delete")])
+            .expect_err("expected to fail");
+        assert!(matches!(error, AuthorizationError::UnauthorizedAction(_)));
+        let missing = error.scope_diff().expect("expected scope diff").missing();
+        assert_eq!(missing, vec![&Scope::new("delete", "users")]);
+    }
+
+    #[test]
+    fn verify_all_succeeds_when_every_requirement_is_met() {
+        let auth = authorization(vec![
+            Scope::new("read", "users"),
+            Scope::new("delete", "users"),
+        ]);
+        let result = verify_all(&auth, &[("users", "read"), ("users", "delete")]);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn verify_all_fails_without_token() {
+        let auth = Authorization::new(None);
+        let error = verify_all::<&str, &str>(&auth, &[]).expect_err("expected to fail");
+        assert!(matches!(error, AuthorizationError::Unauthorized));
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum Action {
+        Read,
+    }
+
+    impl Display for Action {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Action::Read => write!(f, "read"),
+            }
+        }
+    }
+
+    #[test]
+    fn verify_accepts_typed_actions() {
+        let auth = authorization(vec![Scope::new("read", "users")]);
+        let result = verify::<Action, &str>(&auth, "users", Action::Read);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn verify_with_checks_scope_and_ownership() {
+        let auth = authorization(vec![Scope::new("read", "projects")]);
+        let owned = verify_with(&auth, "projects", "read", "subject", &IsSubject);
+        assert!(owned.is_ok());
+        let error = verify_with(&auth, "projects", "read", "someone_else", &IsSubject)
+            .expect_err("expected to fail");
+        assert!(matches!(error, AuthorizationError::UnauthorizedContext));
+    }
+
+    #[test]
+    fn verify_with_accepts_closures() {
+        struct Project {
+            archived: bool,
+        }
+
+        let auth = authorization(vec![Scope::new("read", "projects")]);
+        let project = Project { archived: true };
+        let not_archived = |_: &AccessToken, project: &Project| !project.archived;
+        let error = verify_with(&auth, "projects", "read", &project, &not_archived)
+            .expect_err("expected to fail");
+        assert!(matches!(error, AuthorizationError::UnauthorizedContext));
+    }
+
+    #[test]
+    fn verify_with_requires_scope_before_context() {
+        let auth = authorization(vec![]);
+        let error = verify_with(&auth, "projects", "read", "subject", &IsSubject)
+            .expect_err("expected to fail");
+        assert!(matches!(error, AuthorizationError::UnauthorizedResource(_)));
+    }
+}
@@ -0,0 +1,356 @@
+use std::{
+    collections::HashMap,
+    future::{ready, Ready},
+};
+
+use actix_web::{
+    body::BoxBody,
+    http::{header::SEC_WEBSOCKET_PROTOCOL, StatusCode},
+    web, FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError,
+};
+use jsonwebtoken::jwk::JwkSet;
+
+use crate::{
+    middleware::{
+        authorization_middleware::{check_configured_claims, AuthorizationMiddlewareError},
+        error_response::{
+            internal_server_error_body, miswired, unauthorized_error_body, BearerChallenge,
+            BearerError, ErrorBody, MiddlewareError,
+        },
+        ExpectedClaims,
+    },
+    AccessToken, DecodeOptions, EncodedAccessToken,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketTokenSource {
+    Header(String),
+    Cookie(String),
+    Query(String),
+    Protocol(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct WebSocketHandshake {
+    pub token: AccessToken,
+    pub protocol: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebSocketAuthError {
+    #[error("no token present in the handshake")]
+    NoToken,
+    #[error("no JWK set available")]
+    NoJWKSet,
+    #[error("no expected {0} configured for WebSocketAuth")]
+    NotConfigured(&'static str),
+    #[error("encoded token is not valid")]
+    InvalidEncodedToken,
+    #[error("{0}")]
+    InvalidClaims(AuthorizationMiddlewareError),
+}
+
+impl ResponseError for WebSocketAuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            WebSocketAuthError::NoJWKSet | WebSocketAuthError::NotConfigured(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        self.respond(None)
+    }
+}
+
+impl MiddlewareError for WebSocketAuthError {
+    fn error_body(&self) -> ErrorBody {
+        match self {
+            WebSocketAuthError::NoToken => unauthorized_error_body("NO_TOKEN", self),
+            WebSocketAuthError::NoJWKSet => internal_server_error_body("NO_JWK_SET", self),
+            WebSocketAuthError::NotConfigured(_) => {
+                internal_server_error_body("NOT_CONFIGURED", self)
+            }
+            WebSocketAuthError::InvalidEncodedToken => {
+                unauthorized_error_body("INVALID_ENCODED_TOKEN", self)
+            }
+            WebSocketAuthError::InvalidClaims(_) => unauthorized_error_body("INVALID_CLAIMS", self),
+        }
+    }
+
+    fn challenge(&self) -> Option<BearerChallenge> {
+        match self {
+            WebSocketAuthError::NoToken => Some(BearerChallenge::new()),
+            WebSocketAuthError::NoJWKSet | WebSocketAuthError::NotConfigured(_) => None,
+            WebSocketAuthError::InvalidEncodedToken | WebSocketAuthError::InvalidClaims(_) => {
+                Some(BearerChallenge::new().error(BearerError::InvalidToken))
+            }
+        }
+    }
+}
+
+// Upgrade routes cannot be wrapped by AuthorizationFactory, so the handshake
+// applies its claim checks itself. An issuer and an audience must be configured;
+// without them every handshake fails with NotConfigured.
+#[derive(Debug, Clone)]
+pub struct WebSocketAuth {
+    sources: Vec<WebSocketTokenSource>,
+    scope_prefix: Option<String>,
+    decode_options: DecodeOptions,
+    issuer: Option<String>,
+    expected_claims: ExpectedClaims,
+}
+
+impl Default for WebSocketAuth {
+    fn default() -> Self {
+        Self {
+            sources: vec![
+                WebSocketTokenSource::Header("Authorization".to_string()),
+                WebSocketTokenSource::Protocol("bearer.".to_string()),
+            ],
+            scope_prefix: None,
+            decode_options: DecodeOptions::default(),
+            issuer: None,
+            expected_claims: ExpectedClaims::new(),
+        }
+    }
+}
+
+impl WebSocketAuth {
+    pub fn new(sources: Vec<WebSocketTokenSource>) -> Self {
+        Self {
+            sources,
+            ..Default::default()
+        }
+    }
+
+    pub fn issuer(mut self, value: &str) -> Self {
+        self.issuer = Some(value.to_string());
+        self
+    }
+
+    pub fn audience(mut self, value: &str) -> Self {
+        self.expected_claims = self.expected_claims.audience(value);
+        self
+    }
+
+    // Checked like AuthorizationFactory::with_expected_claims; its issuer, when
+    // set, replaces the one given to issuer().
+    pub fn expected_claims(mut self, value: ExpectedClaims) -> Self {
+        self.expected_claims = value;
+        self
+    }
+
+    pub fn scope_prefix(mut self, value: &str) -> Self {
+        self.scope_prefix = Some(value.to_string());
+        self
+    }
+
+    pub fn decode_options(mut self, value: DecodeOptions) -> Self {
+        self.decode_options = value;
+        self
+    }
+
+    fn find_token(
+        &self,
+        req: &HttpRequest,
+        source: &WebSocketTokenSource,
+    ) -> Option<(String, Option<String>)> {
+        match source {
+            WebSocketTokenSource::Header(name) => {
+                let value = req.headers().get(name)?.to_str().ok()?;
+                let token = value.strip_prefix("Bearer ").unwrap_or(value);
+                Some((token.to_string(), None))
+            }
+            WebSocketTokenSource::Cookie(name) => {
+                let cookie = req.cookie(name)?;
+                Some((cookie.value().to_string(), None))
+            }
+            WebSocketTokenSource::Query(name) => {
+                let query = web::Query::<HashMap<String, String>>::from_query(req.query_string());
+                let token = query.ok()?.get(name)?.clone();
+                Some((token, None))
+            }
+            WebSocketTokenSource::Protocol(prefix) => {
+                let protocols = req.headers().get(SEC_WEBSOCKET_PROTOCOL)?.to_str().ok()?;
+                protocols
+                    .split(',')
+                    .map(str::trim)
+                    .find_map(|protocol| protocol.strip_prefix(prefix.as_str()))
+                    .map(|token| {
+                        let protocol = format!("{prefix}{token}");
+                        (token.to_string(), Some(protocol))
+                    })
+            }
+        }
+    }
+
+    pub fn authenticate(
+        &self,
+        req: &HttpRequest,
+    ) -> Result<WebSocketHandshake, WebSocketAuthError> {
+        let issuer = self
+            .expected_claims
+            .configured_issuer(self.issuer.as_deref())
+            .map_err(WebSocketAuthError::NotConfigured)
+            .map_err(|e| {
+                log::info!("{}", e);
+                e
+            })?;
+        let (encoded, protocol) = self
+            .sources
+            .iter()
+            .find_map(|source| self.find_token(req, source))
+            .ok_or(WebSocketAuthError::NoToken)
+            .map_err(|e| {
+                log::info!("{}", e);
+                e
+            })?;
+        let jwk_set = req.extensions().get::<JwkSet>().cloned().ok_or_else(|| {
+            miswired(
+                WebSocketAuthError::NoJWKSet,
+                "JwkSetFactory",
+                "the handshake is authenticated",
+            )
+        })?;
+        let encoded_token: EncodedAccessToken = encoded.into();
+        let token = encoded_token
+            .decode_with(&jwk_set, self.scope_prefix.as_deref(), &self.decode_options)
+            .map_err(|_| WebSocketAuthError::InvalidEncodedToken)
+            .map_err(|e| {
+                log::info!("{}", e);
+                e
+            })?;
+        check_configured_claims(issuer, &self.expected_claims, token.claims())
+            .map_err(WebSocketAuthError::InvalidClaims)?;
+        req.extensions_mut().insert(token.clone());
+        Ok(WebSocketHandshake { token, protocol })
+    }
+}
+
+impl FromRequest for WebSocketHandshake {
+    type Error = WebSocketAuthError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+        let result = match req.app_data::<WebSocketAuth>() {
+            Some(auth) => auth.authenticate(req),
+            None => WebSocketAuth::default().authenticate(req),
+        };
+        ready(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{cookie::Cookie, test::TestRequest};
+
+    use super::*;
+    use crate::{
+        token::tests::{generate_token, JWKS_JSON},
+        Scope,
+    };
+
+    fn request(req: TestRequest) -> HttpRequest {
+        let req = req.to_http_request();
+        let jwk_set: JwkSet = serde_json::from_str(JWKS_JSON).expect("expected JWK set");
+        req.extensions_mut().insert(jwk_set);
+        req
+    }
+
+    fn encoded() -> String {
+        let scopes = vec![Scope::new("read", "projects")];
+        generate_token(scopes).expect("expected token").to_string()
+    }
+
+    fn auth() -> WebSocketAuth {
+        WebSocketAuth::default()
+            .issuer("issuer")
+            .audience("audience")
+    }
+
+    #[test]
+    fn token_is_read_from_protocol() {
+        let protocols = format!("chat, bearer.{}", encoded());
+        let req =
+            request(TestRequest::default().insert_header((SEC_WEBSOCKET_PROTOCOL, protocols)));
+        let handshake = auth().authenticate(&req).expect("expected handshake");
+        assert_eq!(handshake.token.claims().iss, "issuer");
+        assert_eq!(handshake.protocol, Some(format!("bearer.{}", encoded())));
+        assert!(req.extensions().get::<AccessToken>().is_some());
+    }
+
+    #[actix_web::test]
+    async fn handshake_is_extracted_using_app_data_config() {
+        let auth = WebSocketAuth::new(vec![WebSocketTokenSource::Query("token".to_string())])
+            .issuer("issuer")
+            .audience("audience");
+        let uri = format!("/ws?token={}", encoded());
+        let req = request(TestRequest::default().uri(&uri).app_data(auth));
+        let handshake = WebSocketHandshake::extract(&req)
+            .await
+            .expect("expected handshake");
+        assert_eq!(handshake.token.claims().iss, "issuer");
+    }
+
+    #[test]
+    fn token_is_read_from_cookie_and_query() {
+        let sources = vec![
+            WebSocketTokenSource::Cookie("access_token".to_string()),
+            WebSocketTokenSource::Query("access_token".to_string()),
+        ];
+        let auth = WebSocketAuth::new(sources)
+            .issuer("issuer")
+            .audience("audience");
+        let req = request(TestRequest::default().cookie(Cookie::new("access_token", encoded())));
+        assert!(auth.authenticate(&req).is_ok());
+        let uri = format!("/ws?access_token={}", encoded());
+        let req = request(TestRequest::default().uri(&uri));
+        let handshake = auth.authenticate(&req).expect("expected handshake");
+        assert_eq!(handshake.protocol, None);
+    }
+
+    #[test]
+    fn missing_or_invalid_token_is_rejected() {
+        let req = request(TestRequest::default());
+        let error = auth().authenticate(&req).expect_err("expected error");
+        assert!(matches!(error, WebSocketAuthError::NoToken));
+        let req =
+            request(TestRequest::default().insert_header(("Authorization", "Bearer invalid")));
+        let error = auth().authenticate(&req).expect_err("expected error");
+        assert!(matches!(error, WebSocketAuthError::InvalidEncodedToken));
+    }
+
+    #[test]
+    fn tokens_for_other_issuers_or_audiences_are_rejected() {
+        let bearer = format!("Bearer {}", encoded());
+        let req = request(TestRequest::default().insert_header(("Authorization", bearer)));
+        let error = WebSocketAuth::default()
+            .authenticate(&req)
+            .expect_err("expected error");
+        assert!(matches!(error, WebSocketAuthError::NotConfigured(_)));
+        assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let auth = WebSocketAuth::default().issuer("issuer").audience("other");
+        let error = auth.authenticate(&req).expect_err("expected error");
+        assert!(matches!(
+            error,
+            WebSocketAuthError::InvalidClaims(
+                AuthorizationMiddlewareError::AudienceMismatch { .. }
+            )
+        ));
+        assert_eq!(error.status_code(), StatusCode::UNAUTHORIZED);
+
+        let auth = WebSocketAuth::default()
+            .issuer("other")
+            .audience("audience");
+        let error = auth.authenticate(&req).expect_err("expected error");
+        assert!(matches!(
+            error,
+            WebSocketAuthError::InvalidClaims(AuthorizationMiddlewareError::IssuerMismatch { .. })
+        ));
+        assert!(req.extensions().get::<AccessToken>().is_none());
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    fmt::{Debug, Formatter},
+    time::Duration,
+};
+
+use jsonwebtoken::jwk::JwkSet;
+use reqwest::{Client, Request};
+
+use crate::{AccessToken, EncodedAccessToken, EncodedTokenError};
+
+const JWT_BEARER_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+
+#[derive(Debug, thiserror::Error)]
+pub enum OnBehalfOfError {
+    #[error("token request failed: {0}")]
+    RequestError(String),
+    #[error("token endpoint rejected the exchange: {error}: {description}")]
+    Rejected { error: String, description: String },
+    #[error("unable to deserialize token response")]
+    DeserializeError,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct ErrorResponse {
+    #[serde(default)]
+    error: String,
+    #[serde(default)]
+    error_description: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct OnBehalfOfToken {
+    pub access_token: EncodedAccessToken,
+    pub expires_in: Option<Duration>,
+    pub scope: Option<String>,
+}
+
+impl OnBehalfOfToken {
+    pub fn decode(self, jwk_set: &JwkSet) -> Result<AccessToken, EncodedTokenError> {
+        self.access_token.decode(jwk_set)
+    }
+}
+
+// Exchanges an incoming access token for one issued to this API's client, so
+// downstream APIs can be called as the original user (OAuth 2.0 On-Behalf-Of).
+#[derive(Clone)]
+pub struct OnBehalfOf {
+    client: Client,
+    token_url: String,
+    client_id: String,
+    client_secret: String,
+}
+
+impl Debug for OnBehalfOf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnBehalfOf")
+            .field("token_url", &self.token_url)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &"<redacted>")
+            .finish()
+    }
+}
+
+impl OnBehalfOf {
+    pub fn new(token_url: &str, client_id: &str, client_secret: &str) -> Self {
+        Self {
+            client: Client::new(),
+            token_url: token_url.to_string(),
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+        }
+    }
+
+    pub fn client(mut self, value: Client) -> Self {
+        self.client = value;
+        self
+    }
+
+    pub async fn exchange(
+        &self,
+        assertion: &str,
+        scopes: &[&str],
+    ) -> Result<OnBehalfOfToken, OnBehalfOfError> {
+        let request = self
+            .request(assertion, scopes)
+            .map_err(|e| OnBehalfOfError::RequestError(e.to_string()))?;
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(|e| OnBehalfOfError::RequestError(e.to_string()))?;
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| OnBehalfOfError::RequestError(e.to_string()))?;
+        token_from_response(status.is_success(), &body).map_err(|e| {
+            log::info!("{}", e);
+            e
+        })
+    }
+
+    fn request(&self, assertion: &str, scopes: &[&str]) -> Result<Request, reqwest::Error> {
+        let scope = scopes.join(" ");
+        let form = [
+            ("grant_type", JWT_BEARER_GRANT_TYPE),
+            ("client_id", self.client_id.as_str()),
+            ("client_secret", self.client_secret.as_str()),
+            ("assertion", assertion),
+            ("scope", scope.as_str()),
+            ("requested_token_use", "on_behalf_of"),
+        ];
+        self.client.post(&self.token_url).form(&form).build()
+    }
+}
+
+fn token_from_response(success: bool, body: &[u8]) -> Result<OnBehalfOfToken, OnBehalfOfError> {
+    if !success {
+        let error = serde_json::from_slice::<ErrorResponse>(body).unwrap_or_default();
+        return Err(OnBehalfOfError::Rejected {
+            error: error.error,
+            description: error.error_description,
+        });
+    }
+    let response = serde_json::from_slice::<TokenResponse>(body)
+        .map_err(|_| OnBehalfOfError::DeserializeError)?;
+    Ok(OnBehalfOfToken {
+        access_token: response.access_token.into(),
+        expires_in: response.expires_in.map(Duration::from_secs),
+        scope: response.scope,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn successful_response_yields_token() {
+        let body = br#"{
+            "token_type": "Bearer",
+            "scope": "https://graph.microsoft.com/user.read",
+            "expires_in": 3600,
+            "access_token": "header.payload.signature"
+        }"#;
+        let token = token_from_response(true, body).expect("expected token");
+        assert_eq!(token.access_token.to_string(), "header.payload.signature");
+        assert_eq!(token.expires_in, Some(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn error_response_is_rejected() {
+        let body = br#"{
+            "error": "invalid_grant",
+            "error_description": "AADSTS50013: Assertion failed signature validation."
+        }"#;
+        let error = token_from_response(false, body).expect_err("expected error");
+        assert!(matches!(
+            error,
+            OnBehalfOfError::Rejected { error, .. } if error == "invalid_grant"
+        ));
+    }
+
+    #[test]
+    fn exchange_request_carries_the_jwt_bearer_grant() {
+        let obo = OnBehalfOf::new("https://login.example.com/token", "client", "secret");
+        let request = obo
+            .request(
+                "header.payload.signature",
+                &["api://downstream/read", "offline_access"],
+            )
+            .expect("expected request");
+        assert_eq!(request.method(), reqwest::Method::POST);
+        assert_eq!(request.url().as_str(), "https://login.example.com/token");
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .expect("expected form body");
+        assert_eq!(
+            String::from_utf8_lossy(body),
+            "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Ajwt-bearer\
+             &client_id=client&client_secret=secret&assertion=header.payload.signature\
+             &scope=api%3A%2F%2Fdownstream%2Fread+offline_access\
+             &requested_token_use=on_behalf_of"
+        );
+    }
+
+    #[test]
+    fn debug_redacts_client_secret() {
+        let obo = OnBehalfOf::new("https://login.example.com/token", "client", "s3cr3t");
+        let debug = format!("{obo:?}");
+        assert!(debug.contains("client"));
+        assert!(!debug.contains("s3cr3t"));
+    }
+}
@@ -0,0 +1,318 @@
+use std::{
+    fmt::{Display, Formatter},
+    marker::PhantomData,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use jsonwebtoken::{get_current_timestamp, Algorithm, Header};
+use pasetors::{
+    keys::{AsymmetricPublicKey, AsymmetricSecretKey},
+    token::UntrustedToken,
+    version4::{PublicToken, V4},
+    Public,
+};
+use serde_json::{Map, Value};
+
+use crate::{token::Token, Claims, DecodeOptions};
+
+const PUBLIC_HEADER: &str = "v4.public.";
+const TIME_CLAIMS: [&str; 3] = ["exp", "iat", "nbf"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum PasetoError {
+    #[error("token is not a v4.public PASETO")]
+    InvalidToken,
+    #[error("no matching PASETO key found")]
+    NoMatchingKey,
+    #[error("PASETO signature could not be verified")]
+    VerificationFailed,
+    #[error("PASETO claims are invalid: {0}")]
+    InvalidClaims(String),
+    #[error("PASETO is expired")]
+    Expired,
+    #[error("PASETO is not yet valid")]
+    NotYetValid,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PasetoKeySet {
+    keys: Vec<(Option<String>, AsymmetricPublicKey<V4>)>,
+}
+
+impl PasetoKeySet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn key(mut self, kid: Option<&str>, key: AsymmetricPublicKey<V4>) -> Self {
+        self.keys.push((kid.map(ToString::to_string), key));
+        self
+    }
+
+    fn candidates<'a>(
+        &'a self,
+        kid: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a AsymmetricPublicKey<V4>> + 'a {
+        self.keys
+            .iter()
+            .filter(move |(key_kid, _)| kid.is_none() || key_kid.as_deref() == kid)
+            .map(|(_, key)| key)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EncodedPaseto<Extension> {
+    encoded: String,
+    phantom_data: PhantomData<Extension>,
+}
+
+impl<Extension> From<&str> for EncodedPaseto<Extension> {
+    fn from(encoded: &str) -> Self {
+        let encoded = encoded.strip_prefix("Bearer ").unwrap_or(encoded);
+        encoded.to_string().into()
+    }
+}
+
+impl<Extension> From<String> for EncodedPaseto<Extension> {
+    fn from(encoded: String) -> Self {
+        Self {
+            encoded,
+            phantom_data: Default::default(),
+        }
+    }
+}
+
+impl<Extension> Display for EncodedPaseto<Extension> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.encoded)
+    }
+}
+
+fn footer_kid(footer: &[u8]) -> Option<String> {
+    let footer: Map<String, Value> = serde_json::from_slice(footer).ok()?;
+    footer.get("kid")?.as_str().map(ToString::to_string)
+}
+
+fn convert_time_claims(
+    claims: &mut Map<String, Value>,
+    convert: impl Fn(&Value) -> Option<Value>,
+) -> Result<(), PasetoError> {
+    for name in TIME_CLAIMS {
+        if let Some(value) = claims.get_mut(name) {
+            *value = convert(value)
+                .ok_or_else(|| PasetoError::InvalidClaims(format!("{name} is not a valid time")))?;
+        }
+    }
+    Ok(())
+}
+
+impl<Extension> EncodedPaseto<Extension> {
+    pub fn is_paseto(encoded: &str) -> bool {
+        let encoded = encoded.strip_prefix("Bearer ").unwrap_or(encoded);
+        encoded.starts_with(PUBLIC_HEADER)
+    }
+
+    // The payload before its signature is verified, for limits that are
+    // checked ahead of verification.
+    pub(crate) fn untrusted_payload(&self) -> Result<Vec<u8>, PasetoError> {
+        let untrusted = UntrustedToken::<Public, V4>::try_from(self.encoded.as_str())
+            .map_err(|_| PasetoError::InvalidToken)?;
+        Ok(untrusted.untrusted_payload().to_vec())
+    }
+}
+
+impl<Extension> EncodedPaseto<Extension>
+where
+    Extension: serde::Serialize,
+{
+    pub fn new(
+        claims: &Claims<Extension>,
+        key: &AsymmetricSecretKey<V4>,
+        kid: Option<&str>,
+    ) -> Result<Self, PasetoError> {
+        let invalid = |e: serde_json::Error| PasetoError::InvalidClaims(e.to_string());
+        let mut payload = match serde_json::to_value(claims).map_err(invalid)? {
+            Value::Object(payload) => payload,
+            _ => return Err(PasetoError::InvalidClaims("expected an object".to_string())),
+        };
+        convert_time_claims(&mut payload, |value| {
+            let timestamp = DateTime::<Utc>::from_timestamp(value.as_i64()?, 0)?;
+            Some(timestamp.to_rfc3339_opts(SecondsFormat::Secs, true).into())
+        })?;
+        let payload = serde_json::to_vec(&payload).map_err(invalid)?;
+        let footer = kid.map(|kid| serde_json::json!({ "kid": kid }).to_string());
+        let encoded = PublicToken::sign(key, &payload, footer.as_deref().map(str::as_bytes), None)
+            .map_err(|_| PasetoError::InvalidToken)?;
+        Ok(encoded.into())
+    }
+}
+
+impl<Extension> EncodedPaseto<Extension>
+where
+    for<'a> Extension: serde::Deserialize<'a>,
+{
+    pub fn decode(self, keys: &PasetoKeySet) -> Result<Token<Extension>, PasetoError> {
+        self.decode_with_options(keys, &DecodeOptions::default())
+    }
+
+    pub fn decode_with_options(
+        self,
+        keys: &PasetoKeySet,
+        options: &DecodeOptions,
+    ) -> Result<Token<Extension>, PasetoError> {
+        let untrusted = UntrustedToken::<Public, V4>::try_from(self.encoded.as_str())
+            .map_err(|_| PasetoError::InvalidToken)?;
+        let kid = footer_kid(untrusted.untrusted_footer());
+        let candidates = keys.candidates(kid.as_deref()).collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return Err(PasetoError::NoMatchingKey);
+        }
+        let trusted = candidates
+            .into_iter()
+            .find_map(|key| PublicToken::verify(key, &untrusted, None, None).ok())
+            .ok_or(PasetoError::VerificationFailed)?;
+        let mut payload: Map<String, Value> = serde_json::from_str(trusted.payload())
+            .map_err(|e| PasetoError::InvalidClaims(e.to_string()))?;
+        convert_time_claims(&mut payload, |value| {
+            let timestamp = DateTime::parse_from_rfc3339(value.as_str()?).ok()?;
+            Some(timestamp.timestamp().into())
+        })?;
+        let nbf = payload.get("nbf").and_then(Value::as_u64);
+        let claims: Claims<Extension> = serde_json::from_value(Value::Object(payload))
+            .map_err(|e| PasetoError::InvalidClaims(e.to_string()))?;
+        let now = get_current_timestamp();
+        let leeway = options.expiry_leeway().as_secs();
+        if claims.exp < now.saturating_sub(leeway) {
+            return Err(PasetoError::Expired);
+        }
+        if nbf.is_some_and(|nbf| nbf > now.saturating_add(leeway)) {
+            return Err(PasetoError::NotYetValid);
+        }
+        let header = Header {
+            typ: Some("PASETO".to_string()),
+            alg: Algorithm::EdDSA,
+            kid,
+            ..Default::default()
+        };
+        Ok(Token::new(header, claims))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use pasetors::keys::{AsymmetricKeyPair, Generate};
+
+    use super::*;
+    use crate::{AuthorizationClaims, Scope};
+
+    fn claims(lifetime: Duration) -> Claims<AuthorizationClaims> {
+        let extension = AuthorizationClaims {
+            scopes: vec![Scope::new("read", "user")],
+        };
+        let aud = vec!["audience".to_string()];
+        Claims::new("issuer", "subject", &aud, lifetime, extension)
+    }
+
+    #[test]
+    fn paseto_round_trips_claims() {
+        let key_pair = AsymmetricKeyPair::<V4>::generate().expect("expected key pair");
+        let keys = PasetoKeySet::new().key(Some("key-1"), key_pair.public.clone());
+        let claims = claims(Duration::from_secs(60));
+        let encoded: EncodedPaseto<AuthorizationClaims> =
+            EncodedPaseto::new(&claims, &key_pair.secret, Some("key-1")).expect("expected token");
+        assert!(EncodedPaseto::<AuthorizationClaims>::is_paseto(
+            &encoded.to_string()
+        ));
+        let token = encoded.decode(&keys).expect("expected decoded token");
+        assert_eq!(token.claims().iss, "issuer");
+        assert_eq!(token.claims().exp, claims.exp);
+        assert_eq!(token.claims().extension, claims.extension);
+        assert_eq!(token.header().kid, Some("key-1".to_string()));
+    }
+
+    #[test]
+    fn paseto_rejects_wrong_key_and_expired_tokens() {
+        let key_pair = AsymmetricKeyPair::<V4>::generate().expect("expected key pair");
+        let other = AsymmetricKeyPair::<V4>::generate().expect("expected key pair");
+        let claims = claims(Duration::from_secs(60));
+        let encoded: EncodedPaseto<AuthorizationClaims> =
+            EncodedPaseto::new(&claims, &key_pair.secret, None).expect("expected token");
+        let keys = PasetoKeySet::new().key(None, other.public);
+        assert!(matches!(
+            encoded.decode(&keys),
+            Err(PasetoError::VerificationFailed)
+        ));
+
+        let mut claims = claims;
+        claims.exp = 1000;
+        let encoded: EncodedPaseto<AuthorizationClaims> =
+            EncodedPaseto::new(&claims, &key_pair.secret, None).expect("expected token");
+        let keys = PasetoKeySet::new().key(None, key_pair.public);
+        assert!(matches!(encoded.decode(&keys), Err(PasetoError::Expired)));
+    }
+
+    #[test]
+    fn paseto_applies_decode_leeway_and_not_before() {
+        let key_pair = AsymmetricKeyPair::<V4>::generate().expect("expected key pair");
+        let keys = PasetoKeySet::new().key(None, key_pair.public);
+        let mut expired = claims(Duration::from_secs(60));
+        expired.exp = get_current_timestamp() - 120;
+        let encoded: EncodedPaseto<AuthorizationClaims> =
+            EncodedPaseto::new(&expired, &key_pair.secret, None).expect("expected token");
+        assert!(matches!(
+            encoded.clone().decode(&keys),
+            Err(PasetoError::Expired)
+        ));
+        let options = DecodeOptions::new().leeway(Duration::from_secs(300));
+        assert!(encoded.decode_with_options(&keys, &options).is_ok());
+
+        let mut immature = claims(Duration::from_secs(3600));
+        let nbf = get_current_timestamp() + 600;
+        immature.additional.insert("nbf".to_string(), nbf.into());
+        let encoded: EncodedPaseto<AuthorizationClaims> =
+            EncodedPaseto::new(&immature, &key_pair.secret, None).expect("expected token");
+        assert!(matches!(
+            encoded.decode(&keys),
+            Err(PasetoError::NotYetValid)
+        ));
+    }
+
+    #[test]
+    fn paseto_is_held_to_claim_count_limits() {
+        let key_pair = AsymmetricKeyPair::<V4>::generate().expect("expected key pair");
+        let claims = claims(Duration::from_secs(60));
+        let paseto: EncodedPaseto<AuthorizationClaims> =
+            EncodedPaseto::new(&claims, &key_pair.secret, None).expect("expected token");
+        let jwk_set = serde_json::from_str(crate::token::tests::JWKS_JSON).expect("expected JWKS");
+        let options = DecodeOptions::new()
+            .paseto_keys(PasetoKeySet::new().key(None, key_pair.public))
+            .max_audiences(0);
+        let encoded: crate::EncodedAccessToken = paseto.to_string().into();
+        let result = encoded.decode_with_options(&jwk_set, &options);
+        assert!(matches!(
+            result,
+            Err(crate::EncodedTokenError::LimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn paseto_is_accepted_by_jwt_decode_when_configured() {
+        let key_pair = AsymmetricKeyPair::<V4>::generate().expect("expected key pair");
+        let claims = claims(Duration::from_secs(60));
+        let paseto: EncodedPaseto<AuthorizationClaims> =
+            EncodedPaseto::new(&claims, &key_pair.secret, None).expect("expected token");
+        let jwk_set = serde_json::from_str(crate::token::tests::JWKS_JSON).expect("expected JWKS");
+        let options =
+            crate::DecodeOptions::new().paseto_keys(PasetoKeySet::new().key(None, key_pair.public));
+        let encoded: crate::EncodedAccessToken = format!("Bearer {paseto}").as_str().into();
+        let token = encoded
+            .decode_with_options(&jwk_set, &options)
+            .expect("expected decoded token");
+        assert_eq!(token.claims().sub, Some("subject".into()));
+        let encoded: crate::EncodedAccessToken = paseto.to_string().into();
+        let token = encoded.decode_with(&jwk_set, Some("app:"), &options);
+        assert!(token.is_err());
+    }
+}
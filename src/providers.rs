@@ -0,0 +1,167 @@
+pub mod auth0;
+pub mod azure;
+pub mod cognito;
+pub mod firebase;
+pub mod github_actions;
+pub mod google;
+pub mod keycloak;
+pub mod kubernetes;
+pub mod okta;
+pub mod spiffe;
+
+use std::rc::Rc;
+
+use crate::{
+    compare::secure_contains, middleware::issuer_middleware::IssuerFactory, AuthorizationClaims,
+    AuthorizationFactory, Claims, ClaimsProfile, ClaimsValidator, DecodeOptions, Issuer,
+    JWTFactory, JwkSetFactory, ScopeError,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProviderClaimsError {
+    #[error("invalid claim: {0}")]
+    InvalidClaim(#[from] serde_json::Error),
+    #[error(transparent)]
+    ScopeError(#[from] ScopeError),
+}
+
+type Validator = Rc<dyn ClaimsValidator<AuthorizationClaims>>;
+
+// Restricts a string claim to the values a preset accepts, such as its clients,
+// repositories or namespaces.
+pub(crate) struct AllowedValues {
+    claim: &'static str,
+    values: Vec<String>,
+}
+
+impl AllowedValues {
+    pub(crate) fn new(claim: &'static str, values: &[&str]) -> Self {
+        let values = values.iter().map(ToString::to_string).collect();
+        Self { claim, values }
+    }
+
+    // For claims that presets read themselves, e.g. from a nested object.
+    pub(crate) fn check(&self, value: Option<&str>) -> Result<(), String> {
+        let value = value.ok_or_else(|| format!("Missing {} claim", self.claim))?;
+        match secure_contains(self.values.iter().map(String::as_str), value) {
+            true => Ok(()),
+            false => Err(format!("{} {value} is not allowed", self.claim)),
+        }
+    }
+}
+
+impl ClaimsValidator<AuthorizationClaims> for AllowedValues {
+    fn validate(&self, claims: &Claims<AuthorizationClaims>) -> Result<(), String> {
+        let value = claims
+            .get_claim::<String>(self.claim)
+            .map_err(|e| e.to_string())?;
+        self.check(value.as_deref())
+    }
+}
+
+pub struct Preset<I: Issuer> {
+    issuer: I,
+    audience: String,
+    claims_profile: ClaimsProfile,
+    decode_options: DecodeOptions,
+    validators: Vec<Validator>,
+    accept_authorized_party: bool,
+}
+
+impl<I: Issuer + Clone> Preset<I> {
+    pub fn new(issuer: I, audience: &str) -> Self {
+        Self {
+            issuer,
+            audience: audience.to_string(),
+            claims_profile: ClaimsProfile::default(),
+            decode_options: DecodeOptions::default(),
+            validators: Vec::new(),
+            accept_authorized_party: false,
+        }
+    }
+
+    pub fn claims_profile(mut self, value: ClaimsProfile) -> Self {
+        self.claims_profile = value;
+        self
+    }
+
+    pub fn decode_options(mut self, value: DecodeOptions) -> Self {
+        self.decode_options = value;
+        self
+    }
+
+    pub fn validator(mut self, value: impl ClaimsValidator<AuthorizationClaims> + 'static) -> Self {
+        self.validators.push(Rc::new(value));
+        self
+    }
+
+    pub fn accept_authorized_party(mut self, value: bool) -> Self {
+        self.accept_authorized_party = value;
+        self
+    }
+
+    pub fn issuer(&self) -> &I {
+        &self.issuer
+    }
+
+    pub fn issuer_factory(&self) -> IssuerFactory<I> {
+        IssuerFactory::new(self.issuer.clone())
+    }
+
+    pub fn jwk_set_factory(&self) -> JwkSetFactory<I> {
+        JwkSetFactory::new()
+    }
+
+    pub fn jwt_factory(&self) -> JWTFactory {
+        JWTFactory::new().decode_options(self.decode_options.clone())
+    }
+
+    pub fn authorization_factory(&self) -> AuthorizationFactory<I> {
+        let factory = AuthorizationFactory::new(self.audience.clone())
+            .claims_profile(self.claims_profile.clone())
+            .accept_authorized_party(self.accept_authorized_party);
+        self.validators.iter().fold(factory, |factory, validator| {
+            let validator = validator.clone();
+            factory
+                .validator(move |claims: &Claims<AuthorizationClaims>| validator.validate(claims))
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::time::Duration;
+
+    use serde_json::Value;
+
+    use super::*;
+
+    // Claims as a preset's IdP issues them, with `additional` holding the
+    // provider-specific ones. Null members are left out.
+    pub(crate) fn claims(iss: &str, sub: &str, additional: Value) -> Claims<AuthorizationClaims> {
+        let extension = AuthorizationClaims { scopes: vec![] };
+        let mut claims = Claims::new(iss, sub, &[], Duration::from_secs(60), extension);
+        if let Value::Object(additional) = additional {
+            let additional = additional.into_iter().filter(|(_, value)| !value.is_null());
+            claims.additional.extend(additional);
+        }
+        claims
+    }
+
+    #[test]
+    fn allowed_values_require_a_listed_claim() {
+        let allowed = AllowedValues::new("cid", &["client"]);
+        let claims =
+            |cid: Value| tests::claims("issuer", "subject", serde_json::json!({ "cid": cid }));
+        assert!(allowed.validate(&claims("client".into())).is_ok());
+        assert_eq!(
+            allowed.validate(&claims("other".into())),
+            Err("cid other is not allowed".to_string())
+        );
+        assert_eq!(
+            allowed.validate(&claims(Value::Null)),
+            Err("Missing cid claim".to_string())
+        );
+        assert!(allowed.validate(&claims(1.into())).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    providers::{Preset, ProviderClaimsError},
+    AuthorizationClaims, Claims, FromClaims, Issuer, Scope,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Auth0Issuer {
+    domain: String,
+}
+
+impl Auth0Issuer {
+    pub fn new(domain: &str) -> Self {
+        let domain = domain
+            .trim_start_matches("https://")
+            .trim_end_matches('/')
+            .to_string();
+        Self { domain }
+    }
+}
+
+impl Issuer for Auth0Issuer {
+    fn url(&self) -> String {
+        format!("https://{}/", self.domain)
+    }
+
+    fn jwks_url(&self) -> String {
+        format!("https://{}/.well-known/jwks.json", self.domain)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Auth0Claims {
+    pub scopes: Vec<Scope>,
+    pub permissions: Vec<Scope>,
+    pub azp: Option<String>,
+}
+
+impl FromClaims for Auth0Claims {
+    type Error = ProviderClaimsError;
+
+    fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error> {
+        let permissions = claims
+            .get_claim::<Vec<String>>("permissions")?
+            .unwrap_or_default()
+            .iter()
+            .map(|permission| permission.parse())
+            .collect::<Result<_, _>>()?;
+        let azp = claims.get_claim::<String>("azp")?;
+        Ok(Self {
+            scopes: claims.extension.scopes.clone(),
+            permissions,
+            azp,
+        })
+    }
+}
+
+pub fn preset(domain: &str, audience: &str) -> Preset<Auth0Issuer> {
+    Preset::new(Auth0Issuer::new(domain), audience)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::providers::tests;
+
+    #[test]
+    fn issuer_uses_domain() {
+        let issuer = Auth0Issuer::new("https://tenant.eu.auth0.com/");
+        assert_eq!(issuer.url(), "https://tenant.eu.auth0.com/");
+        assert!(issuer.matches("https://tenant.eu.auth0.com/"));
+        assert_eq!(
+            issuer.jwks_url(),
+            "https://tenant.eu.auth0.com/.well-known/jwks.json"
+        );
+    }
+
+    #[test]
+    fn claims_include_permissions_and_azp() {
+        let additional = json!({ "permissions": ["delete:users"], "azp": "client" });
+        let mut claims = tests::claims("https://tenant.auth0.com/", "auth0|123", additional);
+        claims.extension.scopes = vec![Scope::new("read", "users")];
+        let auth0 = Auth0Claims::from_claims(&claims).expect("expected claims");
+        assert_eq!(auth0.scopes, vec![Scope::new("read", "users")]);
+        assert_eq!(auth0.permissions, vec![Scope::new("delete", "users")]);
+        assert_eq!(auth0.azp, Some("client".to_string()));
+    }
+}
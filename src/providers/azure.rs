@@ -0,0 +1,203 @@
+use jsonwebtoken::Algorithm;
+
+use crate::{
+    compare::secure_eq,
+    providers::{AllowedValues, Preset, ProviderClaimsError},
+    AuthorizationClaims, Claims, DecodeOptions, FromClaims, Issuer, IssuerTemplate, OnBehalfOf,
+    Scope,
+};
+
+const MULTI_TENANTS: [&str; 3] = ["common", "organizations", "consumers"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AzureTokenVersion {
+    V1,
+    #[default]
+    V2,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AzureIssuer {
+    tenant: String,
+    version: AzureTokenVersion,
+    allowed_tenants: Vec<String>,
+}
+
+impl AzureIssuer {
+    pub fn new(tenant: &str) -> Self {
+        Self {
+            tenant: tenant.to_string(),
+            version: AzureTokenVersion::default(),
+            allowed_tenants: Vec::new(),
+        }
+    }
+
+    pub fn version(mut self, value: AzureTokenVersion) -> Self {
+        self.version = value;
+        self
+    }
+
+    pub fn allowed_tenants(mut self, tenants: &[&str]) -> Self {
+        self.allowed_tenants = tenants.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    pub fn is_multi_tenant(&self) -> bool {
+        MULTI_TENANTS.contains(&self.tenant.as_str())
+    }
+
+    pub fn token_url(&self) -> String {
+        format!(
+            "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
+            self.tenant
+        )
+    }
+
+    pub fn on_behalf_of(&self, client_id: &str, client_secret: &str) -> OnBehalfOf {
+        OnBehalfOf::new(&self.token_url(), client_id, client_secret)
+    }
+
+    fn template(&self) -> IssuerTemplate {
+        let template = match self.version {
+            AzureTokenVersion::V1 => IssuerTemplate::new("https://sts.windows.net/{tenantid}/"),
+            AzureTokenVersion::V2 => {
+                IssuerTemplate::new("https://login.microsoftonline.com/{tenantid}/v2.0")
+            }
+        };
+        match self.allowed_tenants.is_empty() {
+            true => template,
+            false => {
+                let tenants = self.allowed_tenants.iter().map(String::as_str);
+                template.allowed_tenants(&tenants.collect::<Vec<_>>())
+            }
+        }
+    }
+}
+
+impl Issuer for AzureIssuer {
+    fn url(&self) -> String {
+        self.template().url(&self.tenant)
+    }
+
+    // Multi-tenant endpoints sign tokens with the issuer of the user's home tenant.
+    fn matches(&self, iss: &str) -> bool {
+        match self.is_multi_tenant() {
+            true => self.template().matches(iss),
+            false => secure_eq(iss, &self.url()),
+        }
+    }
+
+    fn jwks_url(&self) -> String {
+        let path = match self.version {
+            AzureTokenVersion::V1 => "discovery/keys",
+            AzureTokenVersion::V2 => "discovery/v2.0/keys",
+        };
+        format!("https://login.microsoftonline.com/{}/{}", self.tenant, path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AzureClaims {
+    pub scopes: Vec<Scope>,
+    pub roles: Vec<String>,
+    pub tid: Option<String>,
+    pub oid: Option<String>,
+}
+
+impl FromClaims for AzureClaims {
+    type Error = ProviderClaimsError;
+
+    fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            scopes: claims.extension.scopes.clone(),
+            roles: claims.get_claim("roles")?.unwrap_or_default(),
+            tid: claims.get_claim("tid")?,
+            oid: claims.get_claim("oid")?,
+        })
+    }
+}
+
+fn validate_tenant(
+    claims: &Claims<AuthorizationClaims>,
+    issuer: &AzureIssuer,
+) -> Result<(), String> {
+    let tid = claims
+        .get_claim::<String>("tid")
+        .map_err(|e| e.to_string())?;
+    let tenants = match issuer.is_multi_tenant() {
+        true if issuer.allowed_tenants.is_empty() => {
+            return tid.map(|_| ()).ok_or("Missing tid claim".to_string());
+        }
+        true => issuer.allowed_tenants.iter().map(String::as_str).collect(),
+        false => vec![issuer.tenant.as_str()],
+    };
+    AllowedValues::new("tid", &tenants).check(tid.as_deref())
+}
+
+// Entra ID only signs with RS256 and every token carries tid, which is checked
+// against the configured tenant since the issuer alone is not enough for
+// multi-tenant apps. Use oid together with tid as the stable user key.
+pub fn preset(issuer: AzureIssuer, audience: &str) -> Preset<AzureIssuer> {
+    let tenant_issuer = issuer.clone();
+    Preset::new(issuer, audience)
+        .decode_options(DecodeOptions::new().algorithms(&[Algorithm::RS256]))
+        .validator(move |claims: &Claims<AuthorizationClaims>| {
+            validate_tenant(claims, &tenant_issuer)
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::providers::tests;
+
+    fn claims(tid: &str) -> Claims<AuthorizationClaims> {
+        let additional = json!({ "tid": tid, "oid": "object", "roles": ["Admin"] });
+        tests::claims("issuer", "subject", additional)
+    }
+
+    #[test]
+    fn issuer_depends_on_version() {
+        let issuer = AzureIssuer::new("tenant-a");
+        assert_eq!(
+            issuer.url(),
+            "https://login.microsoftonline.com/tenant-a/v2.0"
+        );
+        assert_eq!(
+            issuer.jwks_url(),
+            "https://login.microsoftonline.com/tenant-a/discovery/v2.0/keys"
+        );
+        let issuer = issuer.version(AzureTokenVersion::V1);
+        assert_eq!(issuer.url(), "https://sts.windows.net/tenant-a/");
+        assert_eq!(
+            issuer.jwks_url(),
+            "https://login.microsoftonline.com/tenant-a/discovery/keys"
+        );
+    }
+
+    #[test]
+    fn multi_tenant_issuer_matches_allowed_tenants() {
+        let issuer = AzureIssuer::new("organizations").allowed_tenants(&["tenant-a"]);
+        assert!(issuer.matches("https://login.microsoftonline.com/tenant-a/v2.0"));
+        assert!(!issuer.matches("https://login.microsoftonline.com/tenant-b/v2.0"));
+        assert!(validate_tenant(&claims("tenant-a"), &issuer).is_ok());
+        assert!(validate_tenant(&claims("tenant-b"), &issuer).is_err());
+        let issuer = AzureIssuer::new("organizations");
+        assert!(validate_tenant(&claims("tenant-b"), &issuer).is_ok());
+        let missing = tests::claims("issuer", "subject", json!({}));
+        assert!(validate_tenant(&missing, &issuer).is_err());
+    }
+
+    #[test]
+    fn single_tenant_requires_matching_tid() {
+        let issuer = AzureIssuer::new("tenant-a");
+        assert!(!issuer.matches("https://login.microsoftonline.com/tenant-b/v2.0"));
+        assert!(validate_tenant(&claims("tenant-a"), &issuer).is_ok());
+        assert!(validate_tenant(&claims("tenant-b"), &issuer).is_err());
+        let azure = AzureClaims::from_claims(&claims("tenant-a")).expect("expected claims");
+        assert_eq!(azure.roles, vec!["Admin".to_string()]);
+        assert_eq!(azure.oid, Some("object".to_string()));
+    }
+}
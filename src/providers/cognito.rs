@@ -0,0 +1,134 @@
+use crate::{
+    compare::secure_eq,
+    providers::{Preset, ProviderClaimsError},
+    AuthorizationClaims, Claims, ClaimsProfile, FromClaims, Issuer,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CognitoIssuer {
+    region: String,
+    user_pool_id: String,
+}
+
+impl CognitoIssuer {
+    pub fn new(region: &str, user_pool_id: &str) -> Self {
+        Self {
+            region: region.to_string(),
+            user_pool_id: user_pool_id.to_string(),
+        }
+    }
+}
+
+impl Issuer for CognitoIssuer {
+    fn url(&self) -> String {
+        format!(
+            "https://cognito-idp.{}.amazonaws.com/{}",
+            self.region, self.user_pool_id
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenUse {
+    Access,
+    Id,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CognitoClaims {
+    pub token_use: Option<TokenUse>,
+    pub groups: Vec<String>,
+    pub client_id: Option<String>,
+    pub username: Option<String>,
+}
+
+impl FromClaims for CognitoClaims {
+    type Error = ProviderClaimsError;
+
+    fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error> {
+        let username = match claims.get_claim("username")? {
+            Some(username) => Some(username),
+            None => claims.get_claim("cognito:username")?,
+        };
+        Ok(Self {
+            token_use: claims.get_claim("token_use")?,
+            groups: claims.get_claim("cognito:groups")?.unwrap_or_default(),
+            client_id: claims.get_claim("client_id")?,
+            username,
+        })
+    }
+}
+
+fn validate_token(
+    claims: &Claims<AuthorizationClaims>,
+    token_use: TokenUse,
+    client_id: &str,
+) -> Result<(), String> {
+    let cognito = CognitoClaims::from_claims(claims).map_err(|e| e.to_string())?;
+    if cognito.token_use != Some(token_use) {
+        return Err("Unexpected token_use".to_string());
+    }
+    let client_matches = cognito
+        .client_id
+        .is_some_and(|claim| secure_eq(&claim, client_id));
+    if token_use == TokenUse::Access && !client_matches {
+        return Err("Client does not match".to_string());
+    }
+    Ok(())
+}
+
+pub fn preset(
+    region: &str,
+    user_pool_id: &str,
+    client_id: &str,
+    token_use: TokenUse,
+) -> Preset<CognitoIssuer> {
+    let client = client_id.to_string();
+    let claims_profile = ClaimsProfile::new().require_audience(token_use == TokenUse::Id);
+    Preset::new(CognitoIssuer::new(region, user_pool_id), client_id)
+        .claims_profile(claims_profile)
+        .validator(move |claims: &Claims<AuthorizationClaims>| {
+            validate_token(claims, token_use, &client)
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::providers::tests;
+
+    fn claims(token_use: &str) -> Claims<AuthorizationClaims> {
+        let additional = json!({
+            "token_use": token_use,
+            "client_id": "client",
+            "cognito:groups": ["admins"],
+        });
+        tests::claims("issuer", "subject", additional)
+    }
+
+    #[test]
+    fn issuer_uses_region_and_pool() {
+        let issuer = CognitoIssuer::new("eu-west-1", "eu-west-1_abc");
+        assert_eq!(
+            issuer.url(),
+            "https://cognito-idp.eu-west-1.amazonaws.com/eu-west-1_abc"
+        );
+        assert_eq!(
+            issuer.jwks_url(),
+            "https://cognito-idp.eu-west-1.amazonaws.com/eu-west-1_abc/.well-known/jwks.json"
+        );
+    }
+
+    #[test]
+    fn token_use_and_client_are_validated() {
+        assert!(validate_token(&claims("access"), TokenUse::Access, "client").is_ok());
+        assert!(validate_token(&claims("id"), TokenUse::Access, "client").is_err());
+        assert!(validate_token(&claims("access"), TokenUse::Access, "other").is_err());
+        let cognito = CognitoClaims::from_claims(&claims("id")).expect("expected claims");
+        assert_eq!(cognito.token_use, Some(TokenUse::Id));
+        assert_eq!(cognito.groups, vec!["admins".to_string()]);
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::{
+    providers::{Preset, ProviderClaimsError},
+    AuthorizationClaims, Claims, FromClaims, Issuer, KeySetFormat,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FirebaseIssuer {
+    project_id: String,
+}
+
+impl FirebaseIssuer {
+    pub fn new(project_id: &str) -> Self {
+        Self {
+            project_id: project_id.to_string(),
+        }
+    }
+}
+
+impl Issuer for FirebaseIssuer {
+    fn url(&self) -> String {
+        format!("https://securetoken.google.com/{}", self.project_id)
+    }
+
+    fn jwks_url(&self) -> String {
+        "https://www.googleapis.com/robot/v1/metadata/x509/securetoken@system.gserviceaccount.com"
+            .to_string()
+    }
+
+    fn key_set_format(&self) -> KeySetFormat {
+        KeySetFormat::X509Certificates
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+pub struct FirebaseInfo {
+    #[serde(default)]
+    pub sign_in_provider: Option<String>,
+    #[serde(default)]
+    pub identities: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FirebaseClaims {
+    pub user_id: Option<String>,
+    pub auth_time: Option<u64>,
+    pub email: Option<String>,
+    pub firebase: FirebaseInfo,
+}
+
+impl FromClaims for FirebaseClaims {
+    type Error = ProviderClaimsError;
+
+    fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            user_id: claims.get_claim("user_id")?,
+            auth_time: claims.get_claim("auth_time")?,
+            email: claims.get_claim("email")?,
+            firebase: claims.get_claim("firebase")?.unwrap_or_default(),
+        })
+    }
+}
+
+fn validate_auth_time(claims: &Claims<AuthorizationClaims>, now: u64) -> Result<(), String> {
+    let auth_time = claims
+        .get_claim::<u64>("auth_time")
+        .map_err(|e| e.to_string())?
+        .ok_or("Missing auth_time claim")?;
+    match auth_time <= now {
+        true => Ok(()),
+        false => Err("Authentication time is in the future".to_string()),
+    }
+}
+
+// Firebase ID tokens use the project id as audience and record when the user
+// signed in, which must not be in the future.
+pub fn preset(project_id: &str) -> Preset<FirebaseIssuer> {
+    Preset::new(FirebaseIssuer::new(project_id), project_id).validator(
+        |claims: &Claims<AuthorizationClaims>| {
+            let now = Utc::now().timestamp() as u64;
+            validate_auth_time(claims, now)
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::providers::tests;
+
+    fn claims(auth_time: Option<u64>) -> Claims<AuthorizationClaims> {
+        let additional = json!({
+            "user_id": "subject",
+            "firebase": {"sign_in_provider": "password", "identities": {"email": ["user@example.com"]}},
+            "auth_time": auth_time,
+        });
+        tests::claims("issuer", "subject", additional)
+    }
+
+    #[test]
+    fn issuer_uses_project_and_certificates() {
+        let issuer = FirebaseIssuer::new("project");
+        assert_eq!(issuer.url(), "https://securetoken.google.com/project");
+        assert_eq!(issuer.key_set_format(), KeySetFormat::X509Certificates);
+    }
+
+    #[test]
+    fn auth_time_must_be_in_the_past() {
+        assert!(validate_auth_time(&claims(Some(1000)), 2000).is_ok());
+        assert!(validate_auth_time(&claims(Some(3000)), 2000).is_err());
+        assert!(validate_auth_time(&claims(None), 2000).is_err());
+        let firebase = FirebaseClaims::from_claims(&claims(Some(1000))).expect("expected claims");
+        assert_eq!(firebase.user_id, Some("subject".to_string()));
+        assert_eq!(
+            firebase.firebase.sign_in_provider,
+            Some("password".to_string())
+        );
+    }
+}
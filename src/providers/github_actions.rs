@@ -0,0 +1,108 @@
+use crate::{
+    providers::{AllowedValues, Preset, ProviderClaimsError},
+    AuthorizationClaims, Claims, FromClaims, Issuer,
+};
+
+const GITHUB_ACTIONS_ISSUER: &str = "https://token.actions.githubusercontent.com";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitHubActionsIssuer;
+
+impl GitHubActionsIssuer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Issuer for GitHubActionsIssuer {
+    fn url(&self) -> String {
+        GITHUB_ACTIONS_ISSUER.to_string()
+    }
+
+    fn jwks_url(&self) -> String {
+        format!("{GITHUB_ACTIONS_ISSUER}/.well-known/jwks")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct GitHubActionsClaims {
+    pub repository: String,
+    pub repository_owner: String,
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    #[serde(default)]
+    pub ref_type: Option<String>,
+    #[serde(default)]
+    pub environment: Option<String>,
+    #[serde(default)]
+    pub workflow: Option<String>,
+    #[serde(default)]
+    pub job_workflow_ref: Option<String>,
+    #[serde(default)]
+    pub actor: Option<String>,
+    #[serde(default)]
+    pub sha: Option<String>,
+    #[serde(default)]
+    pub run_id: Option<String>,
+}
+
+impl FromClaims for GitHubActionsClaims {
+    type Error = ProviderClaimsError;
+
+    fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error> {
+        let value = serde_json::Value::Object(claims.additional.clone());
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+pub fn preset(audience: &str, repositories: &[&str]) -> Preset<GitHubActionsIssuer> {
+    let preset = Preset::new(GitHubActionsIssuer::new(), audience);
+    match repositories.is_empty() {
+        true => preset,
+        false => preset.validator(AllowedValues::new("repository", repositories)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{providers::tests, ClaimsValidator};
+
+    fn claims() -> Claims<AuthorizationClaims> {
+        let sub = "repo:octo-org/octo-repo:environment:prod";
+        let additional = json!({
+            "repository": "octo-org/octo-repo",
+            "repository_owner": "octo-org",
+            "ref": "refs/heads/main",
+            "environment": "prod",
+        });
+        tests::claims(GITHUB_ACTIONS_ISSUER, sub, additional)
+    }
+
+    #[test]
+    fn issuer_uses_github_jwks() {
+        assert_eq!(
+            GitHubActionsIssuer::new().jwks_url(),
+            "https://token.actions.githubusercontent.com/.well-known/jwks"
+        );
+    }
+
+    #[test]
+    fn workflow_claims_are_typed() {
+        let github = GitHubActionsClaims::from_claims(&claims()).expect("expected claims");
+        assert_eq!(github.repository, "octo-org/octo-repo");
+        assert_eq!(github.git_ref, "refs/heads/main");
+        assert_eq!(github.environment, Some("prod".to_string()));
+        assert_eq!(github.actor, None);
+    }
+
+    #[test]
+    fn repository_must_be_allowed() {
+        let allowed = AllowedValues::new("repository", &["octo-org/octo-repo"]);
+        assert!(allowed.validate(&claims()).is_ok());
+        let other = AllowedValues::new("repository", &["octo-org/other"]);
+        assert!(other.validate(&claims()).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    claims::check_hosted_domain,
+    compare::secure_eq,
+    providers::{Preset, ProviderClaimsError},
+    AuthorizationClaims, Claims, FromClaims, Issuer, UserClaims,
+};
+
+const GOOGLE_ISSUER: &str = "https://accounts.google.com";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GoogleIssuer;
+
+impl GoogleIssuer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Issuer for GoogleIssuer {
+    fn url(&self) -> String {
+        GOOGLE_ISSUER.to_string()
+    }
+
+    // Google signs tokens with either form of the issuer.
+    fn matches(&self, iss: &str) -> bool {
+        secure_eq(iss, GOOGLE_ISSUER) || secure_eq(iss, "accounts.google.com")
+    }
+
+    fn jwks_url(&self) -> String {
+        "https://www.googleapis.com/oauth2/v3/certs".to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoogleClaims {
+    pub user: UserClaims,
+    pub hd: Option<String>,
+}
+
+impl FromClaims for GoogleClaims {
+    type Error = ProviderClaimsError;
+
+    fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            user: UserClaims::from_claims(claims)?,
+            hd: claims.get_claim("hd")?,
+        })
+    }
+}
+
+pub fn preset(client_id: &str, hosted_domain: Option<&str>) -> Preset<GoogleIssuer> {
+    let preset = Preset::new(GoogleIssuer::new(), client_id);
+    let Some(hosted_domain) = hosted_domain else {
+        return preset;
+    };
+    let hosted_domains = vec![hosted_domain.to_string()];
+    preset.validator(move |claims: &Claims<AuthorizationClaims>| {
+        check_hosted_domain(claims, &hosted_domains).map_err(|e| e.to_string())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::providers::tests;
+
+    fn claims(hd: Option<&str>) -> Claims<AuthorizationClaims> {
+        let additional = json!({
+            "email": "user@example.com",
+            "email_verified": true,
+            "given_name": "Ada",
+            "hd": hd,
+        });
+        tests::claims(GOOGLE_ISSUER, "subject", additional)
+    }
+
+    #[test]
+    fn issuer_matches_both_forms() {
+        let issuer = GoogleIssuer::new();
+        assert!(issuer.matches("https://accounts.google.com"));
+        assert!(issuer.matches("accounts.google.com"));
+        assert!(!issuer.matches("https://accounts.google.com/"));
+        assert_eq!(
+            issuer.jwks_url(),
+            "https://www.googleapis.com/oauth2/v3/certs"
+        );
+    }
+
+    #[test]
+    fn hosted_domain_must_match() {
+        let hosted_domains = ["example.com".to_string()];
+        assert!(check_hosted_domain(&claims(Some("example.com")), &hosted_domains).is_ok());
+        assert!(check_hosted_domain(&claims(Some("Example.COM")), &hosted_domains).is_ok());
+        assert!(check_hosted_domain(&claims(Some("other.com")), &hosted_domains).is_err());
+        assert!(check_hosted_domain(&claims(None), &hosted_domains).is_err());
+    }
+
+    #[test]
+    fn user_claims_are_exposed() {
+        let google =
+            GoogleClaims::from_claims(&claims(Some("example.com"))).expect("expected claims");
+        assert_eq!(google.user.email, Some("user@example.com".to_string()));
+        assert_eq!(google.user.email_verified, Some(true));
+        assert_eq!(google.user.first_name, Some("Ada".to_string()));
+        assert_eq!(google.hd, Some("example.com".to_string()));
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use crate::{
+    providers::{Preset, ProviderClaimsError},
+    AuthorizationClaims, Claims, ClaimsProfile, FromClaims, Issuer, Scope,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeycloakIssuer {
+    base_url: String,
+    realm: String,
+}
+
+impl KeycloakIssuer {
+    pub fn new(base_url: &str, realm: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            realm: realm.to_string(),
+        }
+    }
+}
+
+impl Issuer for KeycloakIssuer {
+    fn url(&self) -> String {
+        format!("{}/realms/{}", self.base_url, self.realm)
+    }
+
+    fn jwks_url(&self) -> String {
+        format!("{}/protocol/openid-connect/certs", self.url())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+struct Roles {
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeycloakClaims {
+    pub scopes: Vec<Scope>,
+    pub realm_roles: Vec<String>,
+    pub resource_roles: HashMap<String, Vec<String>>,
+    pub azp: Option<String>,
+    pub preferred_username: Option<String>,
+}
+
+impl KeycloakClaims {
+    // Realm roles become "<role>:realm" and client roles "<role>:<client>", so
+    // they can be checked alongside the token's own scopes.
+    pub fn role_scopes(&self) -> Vec<Scope> {
+        let realm = self
+            .realm_roles
+            .iter()
+            .map(|role| Scope::new(role, "realm"));
+        let resource = self
+            .resource_roles
+            .iter()
+            .flat_map(|(client, roles)| roles.iter().map(move |role| Scope::new(role, client)));
+        realm.chain(resource).collect()
+    }
+}
+
+impl FromClaims for KeycloakClaims {
+    type Error = ProviderClaimsError;
+
+    fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error> {
+        let realm_access: Roles = claims.get_claim("realm_access")?.unwrap_or_default();
+        let resource_access: HashMap<String, Roles> =
+            claims.get_claim("resource_access")?.unwrap_or_default();
+        let mut keycloak = Self {
+            scopes: claims.extension.scopes.clone(),
+            realm_roles: realm_access.roles,
+            resource_roles: resource_access
+                .into_iter()
+                .map(|(client, access)| (client, access.roles))
+                .collect(),
+            azp: claims.get_claim("azp")?,
+            preferred_username: claims.get_claim("preferred_username")?,
+        };
+        keycloak.scopes.extend(keycloak.role_scopes());
+        Ok(keycloak)
+    }
+}
+
+// Keycloak access tokens usually carry the client in azp and either omit aud
+// or set it to "account", so the client id is accepted in place of aud.
+pub fn preset(base_url: &str, realm: &str, client_id: &str) -> Preset<KeycloakIssuer> {
+    Preset::new(KeycloakIssuer::new(base_url, realm), client_id)
+        .claims_profile(ClaimsProfile::new().require_audience(false))
+        .accept_authorized_party(true)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::providers::tests;
+
+    #[test]
+    fn issuer_uses_realm() {
+        let issuer = KeycloakIssuer::new("https://auth.example.com/", "main");
+        assert_eq!(issuer.url(), "https://auth.example.com/realms/main");
+        assert_eq!(
+            issuer.jwks_url(),
+            "https://auth.example.com/realms/main/protocol/openid-connect/certs"
+        );
+    }
+
+    #[test]
+    fn roles_are_mapped_into_scopes() {
+        let additional = json!({
+            "azp": "client",
+            "realm_access": {"roles": ["admin"]},
+            "resource_access": {"client": {"roles": ["editor"]}},
+        });
+        let mut claims = tests::claims("issuer", "subject", additional);
+        claims.extension.scopes = vec![Scope::new("read", "users")];
+        let keycloak = KeycloakClaims::from_claims(&claims).expect("expected claims");
+        assert_eq!(keycloak.azp, Some("client".to_string()));
+        assert_eq!(
+            keycloak.scopes,
+            vec![
+                Scope::new("read", "users"),
+                Scope::new("admin", "realm"),
+                Scope::new("editor", "client"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{
+    providers::{AllowedValues, Preset, ProviderClaimsError},
+    AuthorizationClaims, Claims, FromClaims, Issuer,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KubernetesIssuer {
+    url: String,
+    jwks_url: Option<String>,
+}
+
+impl KubernetesIssuer {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            jwks_url: None,
+        }
+    }
+
+    pub fn keys_url(mut self, value: &str) -> Self {
+        self.jwks_url = Some(value.to_string());
+        self
+    }
+}
+
+impl Issuer for KubernetesIssuer {
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    // The API server publishes its keys under the OIDC discovery path, unless
+    // the cluster serves them from somewhere else.
+    fn jwks_url(&self) -> String {
+        match &self.jwks_url {
+            Some(jwks_url) => jwks_url.clone(),
+            None => format!("{}/openid/v1/jwks", self.url),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct KubernetesObject {
+    pub name: String,
+    pub uid: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct KubernetesClaims {
+    pub namespace: String,
+    pub serviceaccount: KubernetesObject,
+    #[serde(default)]
+    pub pod: Option<KubernetesObject>,
+    #[serde(default)]
+    pub node: Option<KubernetesObject>,
+}
+
+impl KubernetesClaims {
+    pub fn service_account(&self) -> String {
+        format!(
+            "system:serviceaccount:{}:{}",
+            self.namespace, self.serviceaccount.name
+        )
+    }
+}
+
+impl FromClaims for KubernetesClaims {
+    type Error = ProviderClaimsError;
+
+    fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error> {
+        let kubernetes = claims.get_claim("kubernetes.io")?;
+        kubernetes.ok_or_else(|| {
+            let e = serde::de::Error::missing_field("kubernetes.io");
+            ProviderClaimsError::InvalidClaim(e)
+        })
+    }
+}
+
+fn validate_namespace(
+    claims: &Claims<AuthorizationClaims>,
+    namespaces: &AllowedValues,
+) -> Result<(), String> {
+    let kubernetes = KubernetesClaims::from_claims(claims).map_err(|e| e.to_string())?;
+    namespaces.check(Some(&kubernetes.namespace))
+}
+
+pub fn preset(
+    issuer: KubernetesIssuer,
+    audience: &str,
+    namespaces: &[&str],
+) -> Preset<KubernetesIssuer> {
+    let preset = Preset::new(issuer, audience);
+    if namespaces.is_empty() {
+        return preset;
+    }
+    let namespaces = AllowedValues::new("namespace", namespaces);
+    preset.validator(move |claims: &Claims<AuthorizationClaims>| {
+        validate_namespace(claims, &namespaces)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::providers::tests;
+
+    fn claims() -> Claims<AuthorizationClaims> {
+        let sub = "system:serviceaccount:default:builder";
+        let additional = json!({
+            "kubernetes.io": {
+                "namespace": "default",
+                "pod": {"name": "builder-0", "uid": "pod-uid"},
+                "serviceaccount": {"name": "builder", "uid": "sa-uid"}
+            }
+        });
+        tests::claims("issuer", sub, additional)
+    }
+
+    #[test]
+    fn jwks_url_defaults_to_discovery_path() {
+        let issuer = KubernetesIssuer::new("https://kubernetes.default.svc/");
+        assert_eq!(
+            issuer.jwks_url(),
+            "https://kubernetes.default.svc/openid/v1/jwks"
+        );
+        let issuer = issuer.keys_url("https://keys.example.com/jwks");
+        assert_eq!(issuer.jwks_url(), "https://keys.example.com/jwks");
+    }
+
+    #[test]
+    fn kubernetes_claims_are_typed() {
+        let kubernetes = KubernetesClaims::from_claims(&claims()).expect("expected claims");
+        assert_eq!(kubernetes.namespace, "default");
+        assert_eq!(
+            kubernetes.pod.as_ref().map(|pod| pod.name.as_str()),
+            Some("builder-0")
+        );
+        assert_eq!(
+            kubernetes.service_account(),
+            "system:serviceaccount:default:builder"
+        );
+    }
+
+    #[test]
+    fn namespace_must_be_allowed() {
+        let default = AllowedValues::new("namespace", &["default"]);
+        assert!(validate_namespace(&claims(), &default).is_ok());
+        let kube_system = AllowedValues::new("namespace", &["kube-system"]);
+        assert!(validate_namespace(&claims(), &kube_system).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{
+    providers::{AllowedValues, Preset, ProviderClaimsError},
+    AuthorizationClaims, Claims, FromClaims, Issuer, Scope,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OktaIssuer {
+    org_url: String,
+    auth_server_id: String,
+}
+
+impl OktaIssuer {
+    pub fn new(org_url: &str, auth_server_id: &str) -> Self {
+        Self {
+            org_url: org_url.trim_end_matches('/').to_string(),
+            auth_server_id: auth_server_id.to_string(),
+        }
+    }
+}
+
+impl Issuer for OktaIssuer {
+    fn url(&self) -> String {
+        format!("{}/oauth2/{}", self.org_url, self.auth_server_id)
+    }
+
+    fn jwks_url(&self) -> String {
+        format!("{}/v1/keys", self.url())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OktaClaims {
+    pub scopes: Vec<Scope>,
+    pub cid: Option<String>,
+    pub uid: Option<String>,
+}
+
+impl FromClaims for OktaClaims {
+    type Error = ProviderClaimsError;
+
+    fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            scopes: claims.extension.scopes.clone(),
+            cid: claims.get_claim("cid")?,
+            uid: claims.get_claim("uid")?,
+        })
+    }
+}
+
+pub fn preset(
+    org_url: &str,
+    auth_server_id: &str,
+    audience: &str,
+    client_ids: &[&str],
+) -> Preset<OktaIssuer> {
+    let preset = Preset::new(OktaIssuer::new(org_url, auth_server_id), audience);
+    match client_ids.is_empty() {
+        true => preset,
+        false => preset.validator(AllowedValues::new("cid", client_ids)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{providers::tests, ClaimsValidator};
+
+    fn claims(cid: Option<&str>) -> Claims<AuthorizationClaims> {
+        tests::claims("issuer", "subject", json!({ "cid": cid }))
+    }
+
+    #[test]
+    fn issuer_uses_authorization_server() {
+        let issuer = OktaIssuer::new("https://example.okta.com/", "default");
+        assert_eq!(issuer.url(), "https://example.okta.com/oauth2/default");
+        assert_eq!(
+            issuer.jwks_url(),
+            "https://example.okta.com/oauth2/default/v1/keys"
+        );
+    }
+
+    #[test]
+    fn cid_must_be_allowed() {
+        let client_ids = AllowedValues::new("cid", &["client"]);
+        assert!(client_ids.validate(&claims(Some("client"))).is_ok());
+        assert!(client_ids.validate(&claims(Some("other"))).is_err());
+        assert!(client_ids.validate(&claims(None)).is_err());
+        let okta = OktaClaims::from_claims(&claims(Some("client"))).expect("expected claims");
+        assert_eq!(okta.cid, Some("client".to_string()));
+    }
+}
@@ -0,0 +1,259 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    compare::secure_eq, providers::Preset, AuthorizationClaims, Claims, ClaimsProfile,
+    DecodeOptions, FromClaims, Issuer,
+};
+
+const SPIFFE_SCHEME: &str = "spiffe://";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpiffeId {
+    trust_domain: String,
+    path: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SpiffeIdError {
+    #[error("SPIFFE ID \"{0}\" is invalid")]
+    InvalidId(String),
+    #[error("SPIFFE ID \"{0}\" is outside trust domain \"{1}\"")]
+    ForeignTrustDomain(String, String),
+    #[error("no subject")]
+    MissingSubject,
+}
+
+impl SpiffeId {
+    pub fn trust_domain(&self) -> &str {
+        &self.trust_domain
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl FromStr for SpiffeId {
+    type Err = SpiffeIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || SpiffeIdError::InvalidId(s.to_string());
+        let rest = s.strip_prefix(SPIFFE_SCHEME).ok_or_else(invalid)?;
+        let (trust_domain, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+        let valid_domain = !trust_domain.is_empty()
+            && trust_domain
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-._".contains(c));
+        let valid_path = path.is_empty()
+            || path[1..]
+                .split('/')
+                .all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+        match valid_domain && valid_path && !s.contains(['?', '#']) {
+            true => Ok(Self {
+                trust_domain: trust_domain.to_string(),
+                path: path.to_string(),
+            }),
+            false => Err(invalid()),
+        }
+    }
+}
+
+impl Display for SpiffeId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", SPIFFE_SCHEME, self.trust_domain, self.path)
+    }
+}
+
+// JWT-SVIDs may omit iss. The preset decodes those as issued by the trust
+// domain's URL; tokens that carry one must name it or the configured jwt_issuer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpiffeIssuer {
+    trust_domain: String,
+    bundle_url: String,
+    jwt_issuer: Option<String>,
+}
+
+impl SpiffeIssuer {
+    pub fn new(trust_domain: &str, bundle_url: &str) -> Self {
+        Self {
+            trust_domain: trust_domain.to_string(),
+            bundle_url: bundle_url.to_string(),
+            jwt_issuer: None,
+        }
+    }
+
+    // The iss SPIRE is configured to set on JWT-SVIDs, if any.
+    pub fn jwt_issuer(mut self, value: &str) -> Self {
+        self.jwt_issuer = Some(value.to_string());
+        self
+    }
+}
+
+impl Issuer for SpiffeIssuer {
+    fn url(&self) -> String {
+        format!("{}{}", SPIFFE_SCHEME, self.trust_domain)
+    }
+
+    fn matches(&self, iss: &str) -> bool {
+        let jwt_issuer = self.jwt_issuer.as_deref();
+        secure_eq(iss, &self.url()) | jwt_issuer.is_some_and(|issuer| secure_eq(iss, issuer))
+    }
+
+    fn jwks_url(&self) -> String {
+        self.bundle_url.clone()
+    }
+}
+
+// Claims requires iss, so a JWT-SVID payload without one gets the issuer given.
+pub(crate) fn svid_claims<Extension>(
+    payload: &[u8],
+    issuer: &str,
+) -> Result<Claims<Extension>, serde_json::Error>
+where
+    for<'a> Extension: serde::Deserialize<'a>,
+{
+    let mut payload: Map<String, Value> = serde_json::from_slice(payload)?;
+    payload.entry("iss").or_insert_with(|| Value::from(issuer));
+    serde_json::from_value(Value::Object(payload))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpiffeClaims {
+    pub spiffe_id: SpiffeId,
+}
+
+impl FromClaims for SpiffeClaims {
+    type Error = SpiffeIdError;
+
+    fn from_claims(claims: &Claims<AuthorizationClaims>) -> Result<Self, Self::Error> {
+        let sub = claims.sub.as_ref().ok_or(SpiffeIdError::MissingSubject)?;
+        Ok(Self {
+            spiffe_id: sub.as_str().parse()?,
+        })
+    }
+}
+
+fn validate_spiffe_id(
+    claims: &Claims<AuthorizationClaims>,
+    trust_domain: &str,
+    allowed_ids: &[SpiffeId],
+) -> Result<(), String> {
+    let spiffe_id = SpiffeClaims::from_claims(claims)
+        .map_err(|e| e.to_string())?
+        .spiffe_id;
+    if spiffe_id.trust_domain() != trust_domain {
+        let e = SpiffeIdError::ForeignTrustDomain(spiffe_id.to_string(), trust_domain.to_string());
+        return Err(e.to_string());
+    }
+    match allowed_ids.is_empty() || allowed_ids.contains(&spiffe_id) {
+        true => Ok(()),
+        false => Err(format!("SPIFFE ID {spiffe_id} is not allowed")),
+    }
+}
+
+pub fn preset(
+    issuer: SpiffeIssuer,
+    audience: &str,
+    allowed_ids: &[SpiffeId],
+) -> Preset<SpiffeIssuer> {
+    let trust_domain = issuer.trust_domain.clone();
+    let allowed_ids = allowed_ids.to_vec();
+    let decode_options = DecodeOptions::new().svid_issuer(&issuer.url());
+    Preset::new(issuer, audience)
+        .claims_profile(ClaimsProfile::new().require_issued_at(false))
+        .decode_options(decode_options)
+        .validator(move |claims: &Claims<AuthorizationClaims>| {
+            validate_spiffe_id(claims, &trust_domain, &allowed_ids)
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use jsonwebtoken::jwk::JwkSet;
+    use serde_json::json;
+
+    use super::*;
+    use crate::providers::tests;
+
+    fn claims(sub: &str) -> Claims<AuthorizationClaims> {
+        tests::claims("", sub, json!({}))
+    }
+
+    #[test]
+    fn spiffe_ids_are_parsed() {
+        let id: SpiffeId = "spiffe://example.org/ns/default/sa/api"
+            .parse()
+            .expect("expected SPIFFE ID");
+        assert_eq!(id.trust_domain(), "example.org");
+        assert_eq!(id.path(), "/ns/default/sa/api");
+        assert_eq!(id.to_string(), "spiffe://example.org/ns/default/sa/api");
+        let invalid = [
+            "https://example.org/api",
+            "spiffe:///api",
+            "spiffe://Example.org/api",
+            "spiffe://example.org/api/",
+            "spiffe://example.org/../api",
+            "spiffe://example.org/api?query",
+        ];
+        for id in invalid {
+            assert!(id.parse::<SpiffeId>().is_err(), "{id}");
+        }
+    }
+
+    #[test]
+    fn subject_must_be_in_trust_domain() {
+        let api: SpiffeId = "spiffe://example.org/api".parse().expect("expected ID");
+        assert!(
+            validate_spiffe_id(&claims("spiffe://example.org/api"), "example.org", &[]).is_ok()
+        );
+        assert!(validate_spiffe_id(&claims("spiffe://other.org/api"), "example.org", &[]).is_err());
+        assert!(validate_spiffe_id(&claims("api"), "example.org", &[]).is_err());
+        let allowed = [api];
+        assert!(
+            validate_spiffe_id(&claims("spiffe://example.org/web"), "example.org", &allowed)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn missing_issuer_is_the_trust_domain() {
+        let issuer = SpiffeIssuer::new("example.org", "https://example.org/bundle");
+        let payload = br#"{"sub": "spiffe://example.org/api", "exp": 1000}"#;
+        let claims: Claims<AuthorizationClaims> =
+            svid_claims(payload, &issuer.url()).expect("expected claims");
+        assert_eq!(claims.iss, "spiffe://example.org");
+        assert!(issuer.matches(&claims.iss));
+        assert!(!issuer.matches("https://other.example.org"));
+        let issuer = issuer.jwt_issuer("https://oidc.example.org");
+        assert!(issuer.matches("https://oidc.example.org"));
+        let payload = br#"{"iss": "https://other.example.org", "exp": 1000}"#;
+        let claims: Claims<AuthorizationClaims> =
+            svid_claims(payload, &issuer.url()).expect("expected claims");
+        assert!(!issuer.matches(&claims.iss));
+    }
+
+    #[test]
+    fn trust_bundle_is_a_jwk_set() {
+        let bundle = r#"{
+            "spiffe_refresh_hint": 300,
+            "keys": [{
+                "kty": "EC",
+                "use": "jwt-svid",
+                "kid": "key",
+                "crv": "P-256",
+                "x": "fK-wKTnKL7KFLM27lqq5DC-bxrVaH6rDV-IcCSEOeL4",
+                "y": "wq-g3TQWxYlV51TCPH030yXsRxvujD4hUUaIQrXk4KI"
+            }]
+        }"#;
+        let jwk_set: JwkSet = serde_json::from_str(bundle).expect("expected bundle");
+        assert!(jwk_set.find("key").is_some());
+    }
+}
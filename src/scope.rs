@@ -1,6 +1,7 @@
 use std::{
     fmt,
     fmt::{Display, Formatter},
+    marker::PhantomData,
     str::FromStr,
 };
 
@@ -9,9 +10,9 @@ use serde::{de, de::Visitor, Deserializer, Serialize, Serializer};
 use crate::{scope_deserializer::ScopeDeserializerError, serde_scope};
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct Scope {
-    pub action: String,
-    pub resource: String,
+pub struct Scope<A = String, R = String> {
+    pub action: A,
+    pub resource: R,
 }
 
 impl Scope {
@@ -23,15 +24,29 @@ impl Scope {
     }
 }
 
+impl<A, R> Scope<A, R> {
+    pub fn typed(action: A, resource: R) -> Self {
+        Self { action, resource }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ScopeError {
     #[error("scope \"{0}\" has invalid format; expected format action:resource")]
     InvalidScopeFormat(String),
+    #[error("scope \"{0}\" does not start with prefix \"{1}\"")]
+    MissingScopePrefix(String, String),
     #[error(transparent)]
     DeserializeError(#[from] ScopeDeserializerError),
 }
 
-impl FromStr for Scope {
+impl<A, R> FromStr for Scope<A, R>
+where
+    A: FromStr,
+    A::Err: Display,
+    R: FromStr,
+    R::Err: Display,
+{
     type Err = ScopeError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -39,22 +54,53 @@ impl FromStr for Scope {
     }
 }
 
-impl Display for Scope {
+impl<A, R> Scope<A, R>
+where
+    A: FromStr,
+    A::Err: Display,
+    R: FromStr,
+    R::Err: Display,
+{
+    pub fn parse_with_prefix(s: &str, prefix: &str) -> Result<Self, ScopeError> {
+        let unprefixed = s
+            .strip_prefix(prefix)
+            .ok_or_else(|| ScopeError::MissingScopePrefix(s.to_string(), prefix.to_string()))?;
+        unprefixed.parse()
+    }
+}
+
+impl<A, R> Display for Scope<A, R>
+where
+    A: Display,
+    R: Display,
+{
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let string = serde_scope::to_string(self).map_err(|_| fmt::Error)?;
         write!(f, "{}", string)
     }
 }
 
-impl<'de> de::Deserialize<'de> for Scope {
+impl<'de, A, R> de::Deserialize<'de> for Scope<A, R>
+where
+    A: FromStr,
+    A::Err: Display,
+    R: FromStr,
+    R::Err: Display,
+{
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        struct ScopeVisitor;
+        struct ScopeVisitor<A, R>(PhantomData<A>, PhantomData<R>);
 
-        impl<'de> Visitor<'de> for ScopeVisitor {
-            type Value = Scope;
+        impl<'de, A, R> Visitor<'de> for ScopeVisitor<A, R>
+        where
+            A: FromStr,
+            A::Err: Display,
+            R: FromStr,
+            R::Err: Display,
+        {
+            type Value = Scope<A, R>;
 
             fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
                 formatter.write_str("Scope")
@@ -64,28 +110,29 @@ impl<'de> de::Deserialize<'de> for Scope {
             where
                 E: de::Error,
             {
-                let parts = v
-                    .split(":")
-                    .map(FromStr::from_str)
-                    .collect::<Result<Vec<String>, _>>()
-                    .map_err(de::Error::custom)?;
-                if parts.len() < 2 || parts[0].len() < 1 || parts[1].len() < 1 {
-                    return Err(ScopeError::InvalidScopeFormat(v.to_string()))
-                        .map_err(de::Error::custom);
-                }
-                let action = &parts[0];
-                let resource = &parts[1];
-                let scope = Scope::new(action, resource);
+                let (action, resource) = v
+                    .split_once(':')
+                    .filter(|(action, resource)| !action.is_empty() && !resource.is_empty())
+                    .ok_or_else(|| {
+                        de::Error::custom(ScopeError::InvalidScopeFormat(v.to_string()))
+                    })?;
+                let action = action.parse().map_err(de::Error::custom)?;
+                let resource = resource.parse().map_err(de::Error::custom)?;
+                let scope = Scope::typed(action, resource);
                 Ok(scope)
             }
         }
 
-        let visitor = ScopeVisitor {};
+        let visitor = ScopeVisitor(PhantomData, PhantomData);
         deserializer.deserialize_str(visitor)
     }
 }
 
-impl Serialize for Scope {
+impl<A, R> Serialize for Scope<A, R>
+where
+    A: Display,
+    R: Display,
+{
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
@@ -97,8 +144,37 @@ impl Serialize for Scope {
 
 #[cfg(test)]
 mod test {
+    use std::{fmt, fmt::Display, str::FromStr};
+
     use crate::{scope::Scope, ScopeError};
 
+    #[derive(Debug, Clone, PartialEq)]
+    enum Action {
+        Read,
+        Write,
+    }
+
+    impl FromStr for Action {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "read" => Ok(Action::Read),
+                "write" => Ok(Action::Write),
+                _ => Err(format!("unknown action {s}")),
+            }
+        }
+    }
+
+    impl Display for Action {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Action::Read => write!(f, "read"),
+                Action::Write => write!(f, "write"),
+            }
+        }
+    }
+
     #[test]
     fn scope_can_be_parsed_from_string() {
         let scope: Scope = "create:users".parse().expect("expected to parse");
@@ -114,7 +190,7 @@ mod test {
         let scope = "create"
             .parse::<Scope>()
             .expect_err("expected to fail to parse");
-        println!("{}", scope.to_string());
+        println!("{}", scope);
         assert!(matches!(scope, ScopeError::DeserializeError(_)))
     }
 
@@ -128,4 +204,69 @@ mod test {
         let expected_string = "create:users";
         assert_eq!(string, expected_string);
     }
+
+    #[test]
+    fn typed_scope_can_be_parsed_from_string() {
+        let scope: Scope<Action, String> = "write:users".parse().expect("expected to parse");
+        let expected_scope = Scope::typed(Action::Write, "users".to_string());
+        assert_eq!(scope, expected_scope);
+    }
+
+    #[test]
+    fn typed_scope_cannot_be_parsed_from_unknown_action() {
+        let result = "delete:users".parse::<Scope<Action, String>>();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn typed_scope_can_be_serialized_to_string() {
+        let scope = Scope::typed(Action::Read, "users".to_string());
+        assert_eq!(scope.to_string(), "read:users");
+    }
+
+    #[test]
+    fn scope_can_be_parsed_with_uri_prefix() {
+        let scope = Scope::<String, String>::parse_with_prefix(
+            "https://api.example.com/read:users",
+            "https://api.example.com/",
+        )
+        .expect("expected to parse");
+        assert_eq!(scope, Scope::new("read", "users"));
+    }
+
+    #[test]
+    fn scope_can_be_parsed_with_namespace_prefix() {
+        let scope = Scope::<String, String>::parse_with_prefix("app:read:users", "app:")
+            .expect("expected to parse");
+        assert_eq!(scope, Scope::new("read", "users"));
+    }
+
+    #[test]
+    fn scope_cannot_be_parsed_without_expected_prefix() {
+        let error = Scope::<String, String>::parse_with_prefix("read:users", "app:")
+            .expect_err("expected to fail to parse");
+        assert!(matches!(error, ScopeError::MissingScopePrefix(_, _)));
+    }
+
+    #[test]
+    fn scope_resource_can_contain_colons() {
+        let scope: Scope = "read:urn:project:42".parse().expect("expected to parse");
+        let expected_scope = Scope::new("read", "urn:project:42");
+        assert_eq!(scope, expected_scope);
+    }
+
+    #[test]
+    fn scope_with_colons_in_resource_round_trips() {
+        let scope = Scope::new("read", "urn:project:42");
+        let string = scope.to_string();
+        assert_eq!(string, "read:urn:project:42");
+        let parsed: Scope = string.parse().expect("expected to parse");
+        assert_eq!(parsed, scope);
+    }
+
+    #[test]
+    fn scope_cannot_be_parsed_with_empty_resource() {
+        let result = "read:".parse::<Scope>();
+        assert!(result.is_err());
+    }
 }
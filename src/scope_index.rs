@@ -0,0 +1,41 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::Scope;
+
+// Actions granted per resource, so checking a scope only compares the actions
+// of one resource however many scopes the token carries.
+#[derive(Debug, Default)]
+pub(crate) struct ScopeIndex {
+    resources: HashMap<String, HashSet<String>>,
+}
+
+impl ScopeIndex {
+    pub(crate) fn new(scopes: &[Scope]) -> Self {
+        let mut resources = HashMap::<String, HashSet<String>>::new();
+        for scope in scopes {
+            let actions = resources.entry(scope.resource.clone()).or_default();
+            actions.insert(scope.action.clone());
+        }
+        Self { resources }
+    }
+
+    // None when no scope names the resource, otherwise whether one grants the
+    // action.
+    pub(crate) fn grants(&self, resource: &str, action: &str) -> Option<bool> {
+        let actions = self.resources.get(resource)?;
+        Some(actions.contains(action))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn grants_distinguishes_unknown_resources_from_actions() {
+        let index = ScopeIndex::new(&[Scope::new("read", "users")]);
+        assert_eq!(index.grants("users", "read"), Some(true));
+        assert_eq!(index.grants("users", "write"), Some(false));
+        assert_eq!(index.grants("teams", "read"), None);
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::scope::Scope;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownScopePolicy {
+    Log,
+    Reject,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScopeRegistry {
+    resources: HashMap<String, HashSet<String>>,
+    policy: UnknownScopePolicy,
+}
+
+impl ScopeRegistry {
+    pub fn new() -> Self {
+        Self {
+            resources: HashMap::new(),
+            policy: UnknownScopePolicy::Log,
+        }
+    }
+
+    pub fn register(mut self, resource: &str, actions: &[&str]) -> Self {
+        self.resources
+            .entry(resource.to_string())
+            .or_default()
+            .extend(actions.iter().map(|action| action.to_string()));
+        self
+    }
+
+    pub fn policy(mut self, value: UnknownScopePolicy) -> Self {
+        self.policy = value;
+        self
+    }
+
+    pub fn unknown_scope_policy(&self) -> UnknownScopePolicy {
+        self.policy
+    }
+
+    pub fn contains(&self, scope: &Scope) -> bool {
+        self.resources
+            .get(&scope.resource)
+            .is_some_and(|actions| actions.contains(&scope.action))
+    }
+
+    pub fn unknown_scopes<'a>(&self, scopes: &'a [Scope]) -> Vec<&'a Scope> {
+        scopes
+            .iter()
+            .filter(|scope| !self.contains(scope))
+            .collect()
+    }
+}
+
+impl Default for ScopeRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn registry_contains_registered_scopes() {
+        let registry = ScopeRegistry::new().register("users", &["create", "read"]);
+        assert!(registry.contains(&Scope::new("read", "users")));
+        assert!(!registry.contains(&Scope::new("delete", "users")));
+        assert!(!registry.contains(&Scope::new("read", "projects")));
+    }
+
+    #[test]
+    fn registry_reports_unknown_scopes() {
+        let registry = ScopeRegistry::new()
+            .register("users", &["read"])
+            .register("users", &["create"]);
+        let scopes = vec![
+            Scope::new("create", "users"),
+            Scope::new("read", "users"),
+            Scope::new("read", "projects"),
+        ];
+        let unknown = registry.unknown_scopes(&scopes);
+        assert_eq!(unknown, vec![&Scope::new("read", "projects")]);
+    }
+}
@@ -6,22 +6,49 @@ pub struct ScopeSerializer {
     pub output: String,
 }
 
+impl ScopeSerializer {
+    fn push<T: Display>(&mut self, v: T) -> Result<(), ScopeSerializerError> {
+        self.output += &v.to_string();
+        Ok(())
+    }
+
+    fn push_element<T>(&mut self, value: &T) -> Result<(), ScopeSerializerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut serializer = ScopeSerializer {
+            output: String::new(),
+        };
+        value.serialize(&mut serializer)?;
+        if serializer.output.is_empty() {
+            return Ok(());
+        }
+        if !self.output.is_empty() {
+            self.output.push(' ');
+        }
+        self.output += &serializer.output;
+        Ok(())
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ScopeSerializerError {
-    #[error("something went wrong")]
-    Error,
+    #[error("scope serializer error: {0}")]
+    Error(String),
+    #[error("{0} is not supported by the scope format")]
+    Unsupported(&'static str),
 }
 
 impl ser::Error for ScopeSerializerError {
-    fn custom<T>(_msg: T) -> Self
+    fn custom<T>(msg: T) -> Self
     where
         T: Display,
     {
-        todo!()
+        Self::Error(msg.to_string())
     }
 }
 
-impl<'a> ser::Serializer for &'a mut ScopeSerializer {
+impl ser::Serializer for &mut ScopeSerializer {
     type Ok = ();
     type Error = ScopeSerializerError;
     type SerializeSeq = Self;
@@ -32,52 +59,52 @@ impl<'a> ser::Serializer for &'a mut ScopeSerializer {
     type SerializeStruct = Self;
     type SerializeStructVariant = Self;
 
-    fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.push(v)
     }
 
-    fn serialize_i8(self, _v: i8) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.push(v)
     }
 
-    fn serialize_i16(self, _v: i16) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.push(v)
     }
 
-    fn serialize_i32(self, _v: i32) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.push(v)
     }
 
-    fn serialize_i64(self, _v: i64) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.push(v)
     }
 
-    fn serialize_u8(self, _v: u8) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.push(v)
     }
 
-    fn serialize_u16(self, _v: u16) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.push(v)
     }
 
-    fn serialize_u32(self, _v: u32) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.push(v)
     }
 
-    fn serialize_u64(self, _v: u64) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        self.push(v)
     }
 
-    fn serialize_f32(self, _v: f32) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.push(v)
     }
 
-    fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.push(v)
     }
 
-    fn serialize_char(self, _v: char) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.push(v)
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
@@ -86,49 +113,49 @@ impl<'a> ser::Serializer for &'a mut ScopeSerializer {
     }
 
     fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        todo!()
+        Err(ScopeSerializerError::Unsupported("bytes"))
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        todo!()
+        Ok(())
     }
 
-    fn serialize_some<T: ?Sized>(self, _v: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, v: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        todo!()
+        v.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        todo!()
+        Ok(())
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
-        todo!()
+        Ok(())
     }
 
     fn serialize_unit_variant(
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        todo!()
+        self.serialize_str(variant)
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         _name: &'static str,
-        _value: &T,
+        value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        todo!()
+        value.serialize(self)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -136,17 +163,17 @@ impl<'a> ser::Serializer for &'a mut ScopeSerializer {
         _value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        todo!()
+        Err(ScopeSerializerError::Unsupported("newtype variant"))
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        todo!()
+        Ok(self)
     }
 
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        todo!()
+        Ok(self)
     }
 
     fn serialize_tuple_struct(
@@ -154,7 +181,7 @@ impl<'a> ser::Serializer for &'a mut ScopeSerializer {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        todo!()
+        Ok(self)
     }
 
     fn serialize_tuple_variant(
@@ -164,11 +191,11 @@ impl<'a> ser::Serializer for &'a mut ScopeSerializer {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        todo!()
+        Err(ScopeSerializerError::Unsupported("tuple variant"))
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        todo!()
+        Err(ScopeSerializerError::Unsupported("map"))
     }
 
     fn serialize_struct(
@@ -176,7 +203,7 @@ impl<'a> ser::Serializer for &'a mut ScopeSerializer {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        todo!()
+        Ok(self)
     }
 
     fn serialize_struct_variant(
@@ -186,118 +213,118 @@ impl<'a> ser::Serializer for &'a mut ScopeSerializer {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        todo!()
+        Err(ScopeSerializerError::Unsupported("struct variant"))
     }
 }
 
-impl<'a> ser::SerializeSeq for &'a mut ScopeSerializer {
+impl ser::SerializeSeq for &mut ScopeSerializer {
     type Ok = ();
     type Error = ScopeSerializerError;
 
-    fn serialize_element<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        todo!()
+        self.push_element(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        todo!()
+        Ok(())
     }
 }
 
-impl<'a> ser::SerializeTuple for &'a mut ScopeSerializer {
+impl ser::SerializeTuple for &mut ScopeSerializer {
     type Ok = ();
     type Error = ScopeSerializerError;
 
-    fn serialize_element<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        todo!()
+        self.push_element(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        todo!()
+        Ok(())
     }
 }
 
-impl<'a> ser::SerializeTupleStruct for &'a mut ScopeSerializer {
+impl ser::SerializeTupleStruct for &mut ScopeSerializer {
     type Ok = ();
     type Error = ScopeSerializerError;
 
-    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        todo!()
+        self.push_element(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        todo!()
+        Ok(())
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut ScopeSerializer {
+impl ser::SerializeTupleVariant for &mut ScopeSerializer {
     type Ok = ();
     type Error = ScopeSerializerError;
 
-    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        todo!()
+        Err(ScopeSerializerError::Unsupported("tuple variant"))
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        todo!()
+        Err(ScopeSerializerError::Unsupported("tuple variant"))
     }
 }
 
-impl<'a> ser::SerializeMap for &'a mut ScopeSerializer {
+impl ser::SerializeMap for &mut ScopeSerializer {
     type Ok = ();
     type Error = ScopeSerializerError;
 
-    fn serialize_key<T: ?Sized>(&mut self, _key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, _key: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        todo!()
+        Err(ScopeSerializerError::Unsupported("map"))
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        todo!()
+        Err(ScopeSerializerError::Unsupported("map"))
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        todo!()
+        Err(ScopeSerializerError::Unsupported("map"))
     }
 }
 
-impl<'a> ser::SerializeStruct for &'a mut ScopeSerializer {
+impl ser::SerializeStruct for &mut ScopeSerializer {
     type Ok = ();
     type Error = ScopeSerializerError;
 
     fn serialize_field<T>(
         &mut self,
         _key: &'static str,
-        _value: &T,
+        value: &T,
     ) -> Result<(), ScopeSerializerError>
     where
         T: ?Sized + Serialize,
     {
-        todo!()
+        self.push_element(value)
     }
 
     fn end(self) -> Result<(), ScopeSerializerError> {
-        todo!()
+        Ok(())
     }
 }
 
-impl<'a> ser::SerializeStructVariant for &'a mut ScopeSerializer {
+impl ser::SerializeStructVariant for &mut ScopeSerializer {
     type Ok = ();
     type Error = ScopeSerializerError;
 
@@ -309,10 +336,10 @@ impl<'a> ser::SerializeStructVariant for &'a mut ScopeSerializer {
     where
         T: ?Sized + Serialize,
     {
-        todo!()
+        Err(ScopeSerializerError::Unsupported("struct variant"))
     }
 
     fn end(self) -> Result<(), ScopeSerializerError> {
-        todo!()
+        Err(ScopeSerializerError::Unsupported("struct variant"))
     }
 }
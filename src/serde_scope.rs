@@ -11,7 +11,7 @@ where
 {
     let mut deserializer = ScopeDeserializer::from_str(s);
     let t = T::deserialize(&mut deserializer)?;
-    if deserializer.input.is_empty() {
+    if deserializer.input.trim_start().is_empty() {
         Ok(t)
     } else {
         Err(ScopeDeserializerError::Error(
@@ -30,3 +30,47 @@ where
     value.serialize(&mut serializer)?;
     Ok(serializer.output)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Scope;
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Grant {
+        primary: Scope,
+        others: Vec<Scope>,
+    }
+
+    #[test]
+    fn scopes_can_be_deserialized_from_space_separated_string() {
+        let scopes: Vec<Scope> = from_str("create:users  read:users").expect("expected to parse");
+        let expected_scopes = vec![Scope::new("create", "users"), Scope::new("read", "users")];
+        assert_eq!(scopes, expected_scopes);
+    }
+
+    #[test]
+    fn scopes_can_be_serialized_to_space_separated_string() {
+        let scopes = vec![Scope::new("create", "users"), Scope::new("read", "users")];
+        let string = to_string(&scopes).expect("expected to serialize");
+        assert_eq!(string, "create:users read:users");
+    }
+
+    #[test]
+    fn structs_containing_scopes_round_trip() {
+        let grant = Grant {
+            primary: Scope::new("admin", "users"),
+            others: vec![Scope::new("read", "projects"), Scope::new("read", "teams")],
+        };
+        let string = to_string(&grant).expect("expected to serialize");
+        assert_eq!(string, "admin:users read:projects read:teams");
+        let parsed: Grant = from_str(&string).expect("expected to parse");
+        assert_eq!(parsed, grant);
+    }
+
+    #[test]
+    fn single_scope_rejects_trailing_scopes() {
+        let result = from_str::<Scope>("create:users read:users");
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub jwks_hits: u64,
+    pub jwks_fetches: u64,
+    pub jwks_fetch_failures: u64,
+    pub jwks_last_refresh: Option<SystemTime>,
+    pub decode_successes: u64,
+    pub decode_failures: u64,
+}
+
+impl Stats {
+    pub fn jwks_hit_ratio(&self) -> f64 {
+        let lookups = self.jwks_hits + self.jwks_fetches;
+        match lookups {
+            0 => 0.0,
+            _ => self.jwks_hits as f64 / lookups as f64,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    jwks_hits: AtomicU64,
+    jwks_fetches: AtomicU64,
+    jwks_fetch_failures: AtomicU64,
+    jwks_last_refresh: AtomicU64,
+    decode_successes: AtomicU64,
+    decode_failures: AtomicU64,
+}
+
+// Clones share their counters, so one collector can be handed to the factories of
+// every worker and read from anywhere in the application.
+#[derive(Debug, Clone, Default)]
+pub struct StatsCollector {
+    counters: Arc<Counters>,
+}
+
+impl StatsCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn snapshot(&self) -> Stats {
+        let counters = &self.counters;
+        let last_refresh = counters.jwks_last_refresh.load(Ordering::Relaxed);
+        Stats {
+            jwks_hits: counters.jwks_hits.load(Ordering::Relaxed),
+            jwks_fetches: counters.jwks_fetches.load(Ordering::Relaxed),
+            jwks_fetch_failures: counters.jwks_fetch_failures.load(Ordering::Relaxed),
+            jwks_last_refresh: (last_refresh > 0)
+                .then(|| UNIX_EPOCH + Duration::from_millis(last_refresh)),
+            decode_successes: counters.decode_successes.load(Ordering::Relaxed),
+            decode_failures: counters.decode_failures.load(Ordering::Relaxed),
+        }
+    }
+
+    // Must be called from within an actix runtime.
+    pub fn log_every(&self, period: Duration) {
+        let collector = self.clone();
+        actix_web::rt::spawn(async move {
+            let mut interval = actix_web::rt::time::interval(period);
+            loop {
+                interval.tick().await;
+                let stats = collector.snapshot();
+                log::info!(
+                    "JWK set hits {}, fetches {}, failures {}, hit ratio {:.2}; decodes {}, failures {}",
+                    stats.jwks_hits,
+                    stats.jwks_fetches,
+                    stats.jwks_fetch_failures,
+                    stats.jwks_hit_ratio(),
+                    stats.decode_successes,
+                    stats.decode_failures
+                );
+            }
+        });
+    }
+
+    pub(crate) fn jwks_hit(&self) {
+        self.counters.jwks_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn jwks_fetch(&self, succeeded: bool) {
+        let counters = &self.counters;
+        counters.jwks_fetches.fetch_add(1, Ordering::Relaxed);
+        if !succeeded {
+            counters.jwks_fetch_failures.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_millis() as u64)
+            .unwrap_or_default();
+        counters.jwks_last_refresh.store(now, Ordering::Relaxed);
+    }
+
+    pub(crate) fn decode(&self, succeeded: bool) {
+        let counter = match succeeded {
+            true => &self.counters.decode_successes,
+            false => &self.counters.decode_failures,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn snapshot_reflects_recorded_events() {
+        let collector = StatsCollector::new();
+        collector.jwks_hit();
+        collector.jwks_hit();
+        collector.jwks_hit();
+        collector.jwks_fetch(true);
+        collector.jwks_fetch(false);
+        collector.decode(true);
+        collector.decode(false);
+        let stats = collector.clone().snapshot();
+        assert_eq!(stats.jwks_hits, 3);
+        assert_eq!(stats.jwks_fetches, 2);
+        assert_eq!(stats.jwks_fetch_failures, 1);
+        assert!(stats.jwks_last_refresh.is_some());
+        assert_eq!(stats.jwks_hit_ratio(), 0.6);
+        assert_eq!((stats.decode_successes, stats.decode_failures), (1, 1));
+    }
+
+    #[test]
+    fn hit_ratio_is_zero_without_lookups() {
+        assert_eq!(Stats::default().jwks_hit_ratio(), 0.0);
+    }
+}
@@ -1,8 +1,12 @@
 use std::collections::HashMap;
 
+use jsonwebtoken::{jwk::JwkSet, EncodingKey, Header};
+
 use crate::{
+    claims::RawAuthorizationClaims,
     token::{ActionList, Resource, Token},
-    AuthorizationClaims, EncodedToken, Scope,
+    AuthorizationClaims, Claims, DecodeOptions, EncodedToken, EncodedTokenError, Scope,
+    ScopeFormat,
 };
 
 pub type EncodedAccessToken = EncodedToken<AuthorizationClaims>;
@@ -10,14 +14,65 @@ pub type AccessToken = Token<AuthorizationClaims>;
 
 impl AccessToken {
     pub fn scopes(&self) -> &Vec<Scope> {
-        &self.claims.scopes()
+        self.claims.scopes()
     }
 
     pub fn resources(&self) -> HashMap<Resource, ActionList> {
         self.claims.resources()
     }
 
+    // Reads the scopes directly rather than building the whole resource map.
     pub fn actions(&self, resource: &str) -> Option<ActionList> {
-        self.resources().get(resource).map(Clone::clone)
+        let actions = self
+            .scopes()
+            .iter()
+            .filter(|scope| scope.resource == resource)
+            .map(|scope| scope.action.clone())
+            .collect::<ActionList>();
+        (!actions.is_empty()).then_some(actions)
+    }
+}
+
+impl EncodedAccessToken {
+    pub fn new_with_scope_format(
+        header: Header,
+        claims: Claims<AuthorizationClaims>,
+        key: EncodingKey,
+        format: ScopeFormat,
+    ) -> Result<Self, EncodedTokenError> {
+        let claims = claims
+            .to_value_with_scope_format(format)
+            .map_err(jsonwebtoken::errors::Error::from)?;
+        let encoded_token = jsonwebtoken::encode(&header, &claims, &key)?.into();
+        Ok(encoded_token)
+    }
+
+    pub fn decode_with_scope_prefix(
+        self,
+        jwk_set: &JwkSet,
+        prefix: &str,
+    ) -> Result<AccessToken, EncodedTokenError> {
+        self.decode_with(jwk_set, Some(prefix), &DecodeOptions::default())
+    }
+
+    pub(crate) fn decode_with(
+        self,
+        jwk_set: &JwkSet,
+        scope_prefix: Option<&str>,
+        options: &DecodeOptions,
+    ) -> Result<AccessToken, EncodedTokenError> {
+        let Some(prefix) = scope_prefix else {
+            return self.decode_with_options(jwk_set, options);
+        };
+        let encoded_token: EncodedToken<RawAuthorizationClaims> = self.to_string().into();
+        let token = encoded_token.decode_with_options(jwk_set, options)?;
+        let claims = token
+            .claims
+            .try_map_extension(|extension| extension.with_scope_prefix(prefix))?;
+        Ok(Token {
+            header: token.header,
+            claims,
+            raw: token.raw,
+        })
     }
 }
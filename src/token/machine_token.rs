@@ -0,0 +1,4 @@
+use crate::{token::Token, EncodedToken, MachineClaims};
+
+pub type EncodedMachineToken = EncodedToken<MachineClaims>;
+pub type MachineToken = Token<MachineClaims>;
@@ -0,0 +1,166 @@
+use std::{collections::HashMap, time::SystemTime};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{EncodingKey, Header};
+use serde_json::Value;
+
+use crate::{
+    compare::secure_scope_contains, AccessToken, EncodedAccessToken, EncodedTokenError, Scope,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RemintError {
+    #[error("no internal audience is mapped for {0:?}")]
+    UnmappedAudience(Vec<String>),
+    #[error(transparent)]
+    EncodeError(#[from] EncodedTokenError),
+}
+
+// Re-signs validated tokens with the gateway's key for internal services that
+// expect their own audience. Public audiences are replaced by the internal ones
+// mapped to them and unmapped audiences are dropped. The subject and exp are
+// kept, so a re-minted token never outlives the original. iss names the gateway
+// and jti is new, so internal services can tell re-minted tokens from originals;
+// cnf and azp are dropped since they describe the original token's presenter.
+#[derive(Clone)]
+pub struct TokenReminter {
+    header: Header,
+    key: EncodingKey,
+    issuer: String,
+    audiences: HashMap<String, String>,
+    scopes: Option<Vec<Scope>>,
+}
+
+impl TokenReminter {
+    // `header` names the algorithm and kid of `key`, as published in the JWK
+    // set internal services trust.
+    pub fn new(issuer: &str, header: Header, key: EncodingKey) -> Self {
+        Self {
+            header,
+            key,
+            issuer: issuer.to_string(),
+            audiences: HashMap::new(),
+            scopes: None,
+        }
+    }
+
+    pub fn audience(mut self, public: &str, internal: &str) -> Self {
+        self.audiences
+            .insert(public.to_string(), internal.to_string());
+        self
+    }
+
+    // Keeps only these scopes of the original token.
+    pub fn narrow_scopes(mut self, values: &[Scope]) -> Self {
+        self.scopes = Some(values.to_vec());
+        self
+    }
+
+    pub fn remint(&self, token: &AccessToken) -> Result<EncodedAccessToken, RemintError> {
+        let mut claims = token.claims().clone();
+        let public = claims.aud.iter().flatten().cloned().collect::<Vec<_>>();
+        let mut internal = Vec::new();
+        for audience in public.iter().filter_map(|aud| self.audiences.get(aud)) {
+            if !internal.contains(audience) {
+                internal.push(audience.clone());
+            }
+        }
+        if internal.is_empty() {
+            return Err(RemintError::UnmappedAudience(public));
+        }
+        claims.aud = Some(internal.into());
+        claims.iss = self.issuer.clone();
+        claims.additional.remove("cnf");
+        claims.additional.remove("azp");
+        let jti = URL_SAFE_NO_PAD.encode(rand::random::<[u8; 16]>());
+        claims
+            .additional
+            .insert("jti".to_string(), Value::from(jti));
+        if let Some(scopes) = &self.scopes {
+            claims
+                .extension
+                .scopes
+                .retain(|scope| secure_scope_contains(scopes, scope));
+        }
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("Expected system time since epoch");
+        claims.iat = Some(now.as_secs());
+        let encoded = EncodedAccessToken::new(self.header.clone(), claims, self.key.clone())?;
+        Ok(encoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{token::Token, AuthorizationClaims, Claims};
+
+    fn token() -> AccessToken {
+        let aud = vec!["https://api.example.com".to_string(), "other".to_string()];
+        let extension = AuthorizationClaims {
+            scopes: vec![Scope::new("read", "users"), Scope::new("write", "users")],
+        };
+        let mut claims = Claims::new(
+            "issuer",
+            "subject",
+            &aud,
+            Duration::from_secs(60),
+            extension,
+        );
+        claims
+            .additional
+            .insert("jti".to_string(), "original".into());
+        claims.additional.insert("azp".to_string(), "client".into());
+        let cnf = serde_json::json!({ "jkt": "thumbprint" });
+        claims.additional.insert("cnf".to_string(), cnf);
+        Token::new(Header::default(), claims)
+    }
+
+    fn reminter() -> TokenReminter {
+        TokenReminter::new(
+            "gateway",
+            Header::default(),
+            EncodingKey::from_secret(b"secret"),
+        )
+        .audience("https://api.example.com", "users-service")
+    }
+
+    #[test]
+    fn remint_swaps_audience_and_narrows_scopes() {
+        let reminter = reminter().narrow_scopes(&[Scope::new("read", "users")]);
+        let encoded = reminter.remint(&token()).expect("expected token");
+        let reminted = encoded.decode_insecure().expect("expected claims");
+        let claims = reminted.claims();
+        assert_eq!(claims.iss, "gateway");
+        assert_eq!(claims.aud, Some(vec!["users-service".to_string()].into()));
+        assert_eq!(claims.scopes(), &vec![Scope::new("read", "users")]);
+        assert_eq!(claims.exp, token().claims().exp);
+    }
+
+    #[test]
+    fn remint_replaces_jti_and_drops_presenter_claims() {
+        let encoded = reminter().remint(&token()).expect("expected token");
+        let reminted = encoded.decode_insecure().expect("expected claims");
+        let additional = &reminted.claims().additional;
+        let jti = additional.get("jti").and_then(Value::as_str);
+        assert!(jti.is_some_and(|jti| jti != "original"));
+        assert!(!additional.contains_key("cnf"));
+        assert!(!additional.contains_key("azp"));
+    }
+
+    #[test]
+    fn remint_requires_a_mapped_audience() {
+        let reminter = TokenReminter::new(
+            "gateway",
+            Header::default(),
+            EncodingKey::from_secret(b"secret"),
+        );
+        assert!(matches!(
+            reminter.remint(&token()),
+            Err(RemintError::UnmappedAudience(_))
+        ));
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use rsa::{pkcs8::DecodePublicKey, traits::PublicKeyParts, RsaPublicKey};
+use x509_cert::{
+    der::{DecodePem, Encode},
+    Certificate,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum CertificateError {
+    #[error("invalid certificate {0}: {1}")]
+    InvalidCertificate(String, String),
+    #[error("certificate {0} does not hold an RSA key")]
+    UnsupportedKey(String),
+}
+
+fn jwk_from_certificate(kid: &str, pem: &str) -> Result<Jwk, CertificateError> {
+    let invalid =
+        |e: &dyn ToString| CertificateError::InvalidCertificate(kid.to_string(), e.to_string());
+    let certificate = Certificate::from_pem(pem).map_err(|e| invalid(&e))?;
+    let spki = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|e| invalid(&e))?;
+    let key = RsaPublicKey::from_public_key_der(&spki)
+        .map_err(|_| CertificateError::UnsupportedKey(kid.to_string()))?;
+    let jwk = serde_json::json!({
+        "kty": "RSA",
+        "alg": "RS256",
+        "use": "sig",
+        "kid": kid,
+        "n": URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+        "e": URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+    });
+    serde_json::from_value(jwk).map_err(|e| invalid(&e))
+}
+
+// Some providers publish a map of key ids to PEM certificates instead of a
+// JWK set. Converting them keeps the rest of the decode path unchanged.
+pub(crate) fn jwk_set_from_certificates(
+    certificates: &HashMap<String, String>,
+) -> Result<JwkSet, CertificateError> {
+    let keys = certificates
+        .iter()
+        .map(|(kid, pem)| jwk_from_certificate(kid, pem))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(JwkSet { keys })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::token::tests::generate_token;
+
+    pub(crate) const CERTIFICATE: &str = r#"-----BEGIN CERTIFICATE-----
+MIIDDjCCAfagAwIBAgIUAZbKCk9oiMw/zOmUl6Gf2yJTLgwwDQYJKoZIhvcNAQEL
+BQAwFjEUMBIGA1UEAwwLc2VjdXJldG9rZW4wIBcNMjYxMDE2MTgwMTQ1WhgPMjEy
+NjA5MjIxODAxNDVaMBYxFDASBgNVBAMMC3NlY3VyZXRva2VuMIIBITANBgkqhkiG
+9w0BAQEFAAOCAQ4AMIIBCQKCAQBHSqoiB5cHHxlOwed23xAuOC2c/8YE/gQm8KeT
+/NtLpAqwP6BAC7D9ZLIygtmdaVRc6/q9i1s/MaKF2RUNDVj3IAWBno7pM4ypiEr0
+HcMwbNbVZS27Lswrbb5d9dECIqk/NyWuZD0tU470f4jgdkNyvl3wSNxGEsQdLUAa
+8dePyVELB7wf8K0LV2o+HG+6HfMWa1nlHl9X/PpsinpiXXnXeSYyAtd06er2NwBm
++T8Fx3ACaSVrRxjbDMAGELI6s1GC8ODFd0xsQ2pdTV3sbQHsSzleCKqP4Za3YBd5
+yCmulM4Bwo2pue25OIjWVJH/BwyGVG2sRBm8IQQ5FFDK2iALAgMBAAGjUzBRMB0G
+A1UdDgQWBBQhPx9SsAjnkGnAR2GjBhh53UmBRzAfBgNVHSMEGDAWgBQhPx9SsAjn
+kGnAR2GjBhh53UmBRzAPBgNVHRMBAf8EBTADAQH/MA0GCSqGSIb3DQEBCwUAA4IB
+AQALXWOUTtp1V83G+v+eId6pHXjK3l2FJIOpAp09ECy0hLtIAMNVfniV/oFJ+Ijp
++oSa1i6IjlbEbjAL4gC3Rrwmn6Pb1RRNlzfffbdS9lRjDWN4k4FfJE5EvFN+tVfN
+DZdekGNSNWc0mEiFjbyMJNk6jovzjhSJZ/NIjHxi8qZ73XjP4KRATBAgroTUDVoA
+Per3dzo+6WkQFkJ1O2ZxZxwfwxhBfzzAi4f4h0uLvLmmQmoxyTJE/Mc70LWtrOw0
+XrnqofeF18nQyOFFgzC4yozrPe1aM0ttZzYPpWpjdGhuvWqrqmzGirXNudHUSsPF
+3TxCK6TrkZTn8SzcGgIKzfES
+-----END CERTIFICATE-----
+"#;
+
+    #[test]
+    fn certificates_can_verify_tokens() {
+        let certificates =
+            HashMap::from([("QeiAb2kNPCohaTF8f51Tm".to_string(), CERTIFICATE.to_string())]);
+        let jwk_set = jwk_set_from_certificates(&certificates).expect("expected JWK set");
+        let token = generate_token(vec![]).expect("expected token");
+        assert!(token.decode(&jwk_set).is_ok());
+    }
+
+    #[test]
+    fn invalid_certificates_are_rejected() {
+        let certificates = HashMap::from([("kid".to_string(), "not a certificate".to_string())]);
+        let result = jwk_set_from_certificates(&certificates);
+        assert!(matches!(
+            result,
+            Err(CertificateError::InvalidCertificate(..))
+        ));
+    }
+}